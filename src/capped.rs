@@ -0,0 +1,468 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::config::DBUid;
+use crate::error::DBError;
+use crate::format::DBFormatHeader;
+use crate::serializer::DBSerializer;
+use crate::stream::DBFileStream;
+use crate::stream::DBSliceStream;
+use crate::structures::DBEntry;
+use crate::structures::DBItemEntry;
+use crate::traits::FileBox;
+use crate::BLOCK_SIZE;
+
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use bincode::Decode;
+use bincode::Encode;
+
+pub const RING_MAGIC: [u8; BLOCK_SIZE] = [0xC2, 0xB5, 0x72, 0x62];
+pub const RING_HEADER_BLOCKS: usize = 8;
+pub const RING_HEADER_SIZE: usize = RING_HEADER_BLOCKS * BLOCK_SIZE;
+
+// Keeps every offset within the ring representable in a 32-bit block
+const MAX_CAPACITY: u32 = (u32::MAX - RING_HEADER_SIZE as u32) / 2;
+
+/// Limits of a capped database.
+///
+/// A byte capacity is always required since an entry limit on its own cannot
+/// bound the storage used by variable-size entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DBCapacity {
+    pub max_bytes: usize,
+    pub max_entries: Option<u32>,
+}
+
+impl DBCapacity {
+    pub fn new(max_bytes: usize) -> Self {
+        let max_entries: Option<u32> = None;
+        Self {
+            max_bytes,
+            max_entries,
+        }
+    }
+
+    pub fn with_max_entries(mut self, max_entries: u32) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+}
+
+/// Ring state stored in the first blocks of a capped database file, after
+/// the format header when the file has one.
+///
+/// Offsets are absolute file positions. Live chunks occupy `[head, tail)`,
+/// or `[head, wrap)` followed by `[data_start, tail)` once the ring has
+/// wrapped around. Chunks start on `B`-byte block boundaries, each behind a
+/// block holding its length so the head moves past it without reading it.
+/// With 64-bit UIDs the high half of `next_uid` is stored in a block after
+/// the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DBRingHeader<const B: usize = BLOCK_SIZE, C = DBStandardConfig> {
+    pub capacity: u32,
+    pub max_entries: u32,
+    pub head: u32,
+    pub tail: u32,
+    pub wrap: u32,
+    pub count: u32,
+    pub next_uid: u64,
+    pub marker: PhantomData<C>,
+}
+
+impl<const B: usize, C: DBConfig> DBRingHeader<B, C> {
+    const OFFSET: usize = DBFormatHeader::<B, C>::SIZE;
+    const SIZE: usize = match C::ENCODING.uid_size {
+        8 => RING_HEADER_SIZE + BLOCK_SIZE,
+        _ => RING_HEADER_SIZE,
+    };
+
+    fn encode_block(value: u32) -> [u8; BLOCK_SIZE] {
+        value.to_le_bytes()
+    }
+
+    fn decode_block(buffer: &[u8]) -> u32 {
+        let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        block.copy_from_slice(&buffer[..BLOCK_SIZE]);
+        u32::from_le_bytes(block)
+    }
+
+    fn read_fully(file: &mut FileBox, buffer: &mut [u8]) -> Result<usize, DBError> {
+        let mut length: usize = 0;
+        while length < buffer.len() {
+            let read: usize = file.read(&mut buffer[length..])?;
+            if read == 0 {
+                break;
+            }
+            length += read;
+        }
+        Ok(length)
+    }
+
+    /// Reads the length block at `position`, returning the bytes taken by
+    /// the block and the chunk behind it.
+    fn slot_length(&self, file: &mut FileBox, position: u32) -> Result<u32, DBError> {
+        let offset: Option<usize> = Some(position as usize);
+        let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        file.seek(position as usize)?;
+        if Self::read_fully(file, &mut block)? < BLOCK_SIZE {
+            return Err(DBError::InvalidData { offset });
+        }
+
+        // Every chunk holds at least a UID block and an EOE block
+        let length: u32 = Self::decode_block(&block);
+        let slot: Option<u32> = length.checked_add(B as u32);
+        let end: Option<u32> = slot.and_then(|slot| position.checked_add(slot));
+        match (slot, end) {
+            (Some(slot), Some(end))
+                if length >= 2 * B as u32
+                    && length.is_multiple_of(B as u32)
+                    && end <= self.data_end() =>
+            {
+                Ok(slot)
+            }
+            _ => Err(DBError::InvalidData { offset }),
+        }
+    }
+
+    fn data_start(&self) -> u32 {
+        let end: usize = Self::OFFSET + Self::SIZE;
+        end.next_multiple_of(B) as u32
+    }
+
+    fn data_end(&self) -> u32 {
+        self.data_start().saturating_add(self.capacity)
+    }
+
+    fn is_valid(&self) -> bool {
+        let range: core::ops::RangeInclusive<u32> = self.data_start()..=self.data_end();
+        // Every chunk holds at least a UID block and an EOE block
+        let max_count: u32 = self.capacity / (3 * B as u32);
+        self.capacity <= MAX_CAPACITY
+            && self.count <= max_count
+            && range.contains(&self.head)
+            && range.contains(&self.tail)
+            && range.contains(&self.wrap)
+    }
+
+    fn evict_head(&mut self, file: &mut FileBox) -> Result<(), DBError> {
+        if self.count == 0 {
+            return Err(DBError::InvalidData {
+                offset: Some(Self::OFFSET),
+            });
+        }
+
+        self.head += self.slot_length(file, self.head)?;
+        self.count -= 1;
+
+        if self.head == self.wrap && self.is_wrapped() {
+            self.head = self.data_start();
+        }
+        if self.count == 0 {
+            self.reset();
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.head = self.data_start();
+        self.tail = self.data_start();
+        self.wrap = self.data_start();
+        self.count = 0;
+    }
+}
+
+impl<const B: usize, C: DBConfig> DBRingHeader<B, C> {
+    /// Fails with `DBError::CapacityTooLarge` when the byte capacity does
+    /// not fit the 32-bit offsets of the ring.
+    pub fn new(capacity: &DBCapacity) -> Result<Self, DBError> {
+        let max_bytes: Option<u32> = u32::try_from(capacity.max_bytes).ok();
+        let max_bytes: Option<u32> = max_bytes.filter(|max_bytes| *max_bytes <= MAX_CAPACITY);
+        let max_bytes: u32 = max_bytes.ok_or(DBError::CapacityTooLarge {
+            capacity: capacity.max_bytes,
+            limit: MAX_CAPACITY as usize,
+        })?;
+        let mut header: DBRingHeader<B, C> = Self {
+            capacity: max_bytes,
+            max_entries: capacity.max_entries.unwrap_or(0),
+            head: 0,
+            tail: 0,
+            wrap: 0,
+            count: 0,
+            next_uid: 0,
+            marker: PhantomData,
+        };
+        header.reset();
+        Ok(header)
+    }
+
+    pub fn limits(&self) -> DBCapacity {
+        let mut capacity: DBCapacity = DBCapacity::new(self.capacity as usize);
+        if self.max_entries > 0 {
+            capacity = capacity.with_max_entries(self.max_entries);
+        }
+        capacity
+    }
+
+    pub fn is_wrapped(&self) -> bool {
+        self.count > 0 && self.tail <= self.head
+    }
+
+    pub fn read(file: &mut FileBox) -> Result<Option<Self>, DBError> {
+        let mut buffer: [u8; RING_HEADER_SIZE + BLOCK_SIZE] = [0; RING_HEADER_SIZE + BLOCK_SIZE];
+        let buffer: &mut [u8] = &mut buffer[..Self::SIZE];
+        file.seek(Self::OFFSET)?;
+        let length: usize = Self::read_fully(file, buffer)?;
+
+        let offset: Option<usize> = Some(Self::OFFSET);
+        if length == 0 {
+            return Ok(None);
+        }
+        if length < Self::SIZE || buffer[..BLOCK_SIZE] != RING_MAGIC {
+            return Err(DBError::InvalidData { offset });
+        }
+
+        let mut values: [u32; RING_HEADER_BLOCKS] = [0; RING_HEADER_BLOCKS];
+        for (value, block) in values
+            .iter_mut()
+            .zip(buffer[BLOCK_SIZE..].chunks(BLOCK_SIZE))
+        {
+            *value = Self::decode_block(block);
+        }
+
+        let [capacity, max_entries, head, tail, wrap, count, low_uid, high_uid] = values;
+        let next_uid: u64 = ((high_uid as u64) << 32) | low_uid as u64;
+        let marker: PhantomData<C> = PhantomData;
+        let header: DBRingHeader<B, C> = Self {
+            capacity,
+            max_entries,
+            head,
+            tail,
+            wrap,
+            count,
+            next_uid,
+            marker,
+        };
+
+        if !header.is_valid() {
+            return Err(DBError::InvalidData { offset });
+        }
+        Ok(Some(header))
+    }
+
+    /// Reads the header if the ring magic follows the format header. Files
+    /// with compact chunks are never capped.
+    pub fn detect(file: &mut FileBox) -> Result<Option<Self>, DBError> {
+        if C::ENCODING.compact {
+            return Ok(None);
+        }
+
+        let mut magic: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        file.seek(Self::OFFSET)?;
+        let length: usize = Self::read_fully(file, &mut magic)?;
+
+        if length < BLOCK_SIZE || magic != RING_MAGIC {
+            return Ok(None);
+        }
+        Self::read(file)
+    }
+
+    pub fn write(&self, file: &mut FileBox) -> Result<(), DBError> {
+        let values: [u32; RING_HEADER_BLOCKS] = [
+            self.capacity,
+            self.max_entries,
+            self.head,
+            self.tail,
+            self.wrap,
+            self.count,
+            self.next_uid as u32,
+            (self.next_uid >> 32) as u32,
+        ];
+
+        let mut buffer: [u8; RING_HEADER_SIZE + BLOCK_SIZE] = [0; RING_HEADER_SIZE + BLOCK_SIZE];
+        let buffer: &mut [u8] = &mut buffer[..Self::SIZE];
+        buffer[..BLOCK_SIZE].copy_from_slice(&RING_MAGIC);
+        for (value, block) in values
+            .iter()
+            .zip(buffer[BLOCK_SIZE..].chunks_mut(BLOCK_SIZE))
+        {
+            block.copy_from_slice(&Self::encode_block(*value));
+        }
+
+        file.seek(Self::OFFSET)?;
+        file.write_all(buffer)?;
+        Ok(())
+    }
+
+    /// Writes a serialized chunk behind its length block at the tail,
+    /// evicting the oldest chunks until both the byte capacity and the entry
+    /// limit are satisfied. Evicting a chunk only reads its length block.
+    pub fn append(&mut self, file: &mut FileBox, chunk: &[u8]) -> Result<(), DBError> {
+        let length: u32 = u32::try_from(chunk.len() + B).unwrap_or(u32::MAX);
+        if length > self.capacity {
+            let size: usize = chunk.len() + B;
+            let capacity: usize = self.capacity as usize;
+            return Err(DBError::EntryTooLarge { size, capacity });
+        }
+        let next_uid: Option<u64> = self.next_uid.checked_add(1);
+        let next_uid: Option<u64> = next_uid.filter(|uid| C::UID::from_u64(*uid).is_some());
+        let next_uid: u64 = next_uid.ok_or(DBError::UIDOverflow)?;
+
+        while self.max_entries > 0 && self.count >= self.max_entries {
+            self.evict_head(file)?;
+        }
+
+        loop {
+            if !self.is_wrapped() {
+                if self.tail + length <= self.data_end() {
+                    break;
+                }
+                self.wrap = self.tail;
+                self.tail = self.data_start();
+                if self.count == 0 {
+                    self.reset();
+                }
+                continue;
+            }
+
+            if self.tail + length <= self.head {
+                break;
+            }
+            self.evict_head(file)?;
+        }
+
+        let mut block: [u8; B] = [0; B];
+        block[..BLOCK_SIZE].copy_from_slice(&Self::encode_block(length - B as u32));
+        file.seek(self.tail as usize)?;
+        file.write_all(&block)?;
+        file.write_all(chunk)?;
+
+        self.tail += length;
+        let count: Option<u32> = self.count.checked_add(1);
+        let offset: Option<usize> = Some(Self::OFFSET);
+        self.count = count.ok_or(DBError::InvalidData { offset })?;
+        self.next_uid = next_uid;
+        self.write(file)
+    }
+}
+
+/// Position of an iterator walking the live chunks of a capped database.
+pub struct DBRingCursor {
+    start: usize,
+    position: usize,
+    wrap: Option<usize>,
+    remaining: u32,
+    prefix: usize,
+}
+
+impl DBRingCursor {
+    pub fn new<const B: usize, C: DBConfig>(header: &DBRingHeader<B, C>) -> Self {
+        let start: usize = header.data_start() as usize;
+        let position: usize = header.head as usize;
+        let mut wrap: Option<usize> = None;
+        if header.is_wrapped() {
+            wrap = Some(header.wrap as usize);
+        }
+        let remaining: u32 = header.count;
+        let prefix: usize = B;
+        Self {
+            start,
+            position,
+            wrap,
+            remaining,
+            prefix,
+        }
+    }
+
+    /// Returns the position of the next chunk, past its length block.
+    fn next_position(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        if self.wrap == Some(self.position) {
+            self.position = self.start;
+            self.wrap = None;
+        }
+        Some(self.position + self.prefix)
+    }
+
+    /// Moves past a chunk of `length` bytes and its length block.
+    fn advance(&mut self, length: Option<usize>) {
+        if let Some(length) = length {
+            self.position += self.prefix + length;
+            self.remaining -= 1;
+            return;
+        }
+        self.remaining = 0;
+    }
+
+    pub fn next_chunk<const N: usize, const B: usize, C: DBConfig>(
+        &mut self,
+        stream: &mut DBFileStream<'_, N, B, C>,
+    ) -> Option<Result<Vec<u8>, DBError>> {
+        let (_, chunk) = self.next_located_chunk(stream)?;
+        Some(chunk)
+    }
+
+    /// Like `next_chunk`, but also returns the file offset of the chunk.
+    pub fn next_located_chunk<const N: usize, const B: usize, C: DBConfig>(
+        &mut self,
+        stream: &mut DBFileStream<'_, N, B, C>,
+    ) -> Option<(usize, Result<Vec<u8>, DBError>)> {
+        let position: usize = self.next_position()?;
+        let chunk: Result<Vec<u8>, DBError> =
+            stream.seek(position).and_then(|_| stream.iter_chunk());
+
+        // A chunk over the memory budget is skipped, not buffered
+        let length: Option<usize> = match &chunk {
+            Ok(chunk) => Some(chunk.len()),
+            Err(DBError::MemoryBudgetExceeded { .. }) => Some(stream.get_position() - position),
+            Err(_) => None,
+        };
+        self.advance(length);
+        Some((position, chunk))
+    }
+
+    /// Decodes the next entry straight from the stream, see
+    /// `DBSerializer::deserialize_from_stream`.
+    pub fn next_entry<const N: usize, const B: usize, C: DBConfig, T>(
+        &mut self,
+        stream: &mut DBFileStream<'_, N, B, C>,
+        serializer: &DBSerializer<'_, T, B, C>,
+    ) -> Option<Result<DBItemEntry<T, C>, DBError>>
+    where
+        T: IntoIterator + Eq,
+        T::Item: Encode + Decode + Hash + Eq,
+    {
+        let position: usize = self.next_position()?;
+        if let Err(error) = stream.seek(position) {
+            self.advance(None);
+            return Some(Err(error));
+        }
+
+        let entry: Result<DBEntry<T::Item, C::UID>, DBError> =
+            serializer.deserialize_from_stream(stream);
+        let mut length: Option<usize> = None;
+        if !matches!(entry, Err(DBError::EndOfFileStream)) {
+            length = Some(stream.get_position() - position);
+        }
+        self.advance(length);
+        Some(entry)
+    }
+
+    pub fn next_slice<'a, const B: usize, C: DBConfig>(
+        &mut self,
+        stream: &mut DBSliceStream<'a, B, C>,
+    ) -> Option<Result<&'a [u8], DBError>> {
+        let position: usize = self.next_position()?;
+        let chunk: Result<&'a [u8], DBError> =
+            stream.seek(position).and_then(|_| stream.iter_chunk());
+
+        self.advance(chunk.as_ref().ok().map(|chunk| chunk.len()));
+        Some(chunk)
+    }
+}
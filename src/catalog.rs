@@ -0,0 +1,306 @@
+extern crate alloc;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::config::DBUid;
+use crate::db::Database;
+use crate::error::DBError;
+use crate::iterators::DBIterator;
+use crate::structures::DBEntry;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use bincode::decode_from_slice;
+use bincode::encode_to_vec;
+use bincode::error::DecodeError;
+use bincode::error::EncodeError;
+use bincode::Decode;
+use bincode::Encode;
+
+/// Record of a catalog file, either the name of a collection or an item of
+/// one. Items are encoded on their own, so records of other collections are
+/// skipped without knowing their type.
+#[derive(Encode, Decode, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum DBRecord {
+    /// Names collection `id` of items with schema `schema_id`, written the
+    /// first time the collection is used.
+    Collection {
+        id: u32,
+        name: String,
+        schema_id: u64,
+    },
+    /// Item of collection `id`.
+    Item { id: u32, bytes: Vec<u8> },
+}
+
+/// Item type of a catalog collection. `SCHEMA_ID` is stored with the name of
+/// each collection of the type and checked whenever the collection is used,
+/// so it must stay the same for as long as the encoding of the type does and
+/// differ from the ids of the other item types stored in the catalog.
+pub trait CatalogItem: Encode + Decode + Hash + Eq {
+    const SCHEMA_ID: u64;
+}
+
+/// Database of every collection in a catalog file.
+pub type DBRecordDatabase<'a, const CACHE: usize, const BLOCK: usize, C> =
+    Database<'a, BTreeSet<DBRecord>, CACHE, BLOCK, C>;
+
+/// Several named collections of different item types in a single file,
+/// stored as `DBRecord` entries of `database`. Each collection numbers its
+/// entries from 0, independently of the others. A name is bound to the
+/// schema id of its item type, see `CatalogItem`, and capped
+/// databases return `DBError::Unsupported` since evicting a name would
+/// orphan its items.
+pub struct DBCatalog<
+    'a,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    C: DBConfig,
+{
+    pub database: DBRecordDatabase<'a, CACHE, BLOCK, C>,
+}
+
+impl<'a, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBCatalog<'a, CACHE, BLOCK, C> {
+    /// Looks up collection `name`, returning its id and item schema id when
+    /// it is in the catalog along with the id a new collection takes.
+    fn find(&mut self, name: &str) -> Result<(Option<(u32, u64)>, u32), DBError> {
+        if self.database.capacity.is_some() {
+            return Err(DBError::Unsupported);
+        }
+
+        let mut next_id: u32 = 0;
+        for entry in self.database.get_iterator()? {
+            if let DBRecord::Collection {
+                id,
+                name: found,
+                schema_id,
+            } = entry?.item
+            {
+                if found == name {
+                    return Ok((Some((id, schema_id)), next_id));
+                }
+                next_id = next_id.max(id.saturating_add(1));
+            }
+        }
+        Ok((None, next_id))
+    }
+}
+
+impl<'a, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBCatalog<'a, CACHE, BLOCK, C> {
+    pub fn new(database: DBRecordDatabase<'a, CACHE, BLOCK, C>) -> Self {
+        Self { database }
+    }
+
+    /// Returns collection `name` of `I` items, adding the name to the catalog
+    /// the first time it is used. Fails with `DBError::TypeMismatch` when the
+    /// collection was added with an item type of another schema id.
+    pub fn collection<I: CatalogItem>(
+        &mut self,
+        name: &str,
+    ) -> Result<DBCollection<'_, 'a, I, CACHE, BLOCK, C>, DBError> {
+        let id: u32 = match self.find(name)? {
+            (Some((id, schema_id)), _) if schema_id == I::SCHEMA_ID => id,
+            (Some((id, _)), _) => return Err(DBError::TypeMismatch { id }),
+            (None, id) => {
+                let name: String = String::from(name);
+                let schema_id: u64 = I::SCHEMA_ID;
+                self.database.add_entry(&DBRecord::Collection {
+                    id,
+                    name,
+                    schema_id,
+                })?;
+                id
+            }
+        };
+        Ok(DBCollection::new(&mut self.database, id))
+    }
+
+    /// Names of the collections in the catalog, in the order they were added.
+    pub fn collections(&mut self) -> Result<Vec<String>, DBError> {
+        let mut names: Vec<String> = Vec::new();
+        for entry in self.database.get_iterator()? {
+            if let DBRecord::Collection { name, .. } = entry?.item {
+                names.push(name);
+            }
+        }
+        Ok(names)
+    }
+
+    /// Closes the underlying database, see `Database::close`.
+    pub fn close(self) -> Result<(), DBError> {
+        self.database.close()
+    }
+}
+
+/// Collection of `I` items in a `DBCatalog`, borrowed from the catalog.
+pub struct DBCollection<'c, 'a, I, const CACHE: usize, const BLOCK: usize, C>
+where
+    I: Encode + Decode + Hash + Eq,
+    C: DBConfig,
+{
+    database: &'c mut DBRecordDatabase<'a, CACHE, BLOCK, C>,
+    pub id: u32,
+    marker: PhantomData<I>,
+}
+
+impl<'c, 'a, I, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBCollection<'c, 'a, I, CACHE, BLOCK, C>
+where
+    I: Encode + Decode + Hash + Eq,
+{
+    /// UID in the file of the entry with collection UID `uid`.
+    fn record_uid(&mut self, uid: C::UID) -> Result<C::UID, DBError> {
+        let id: u32 = self.id;
+        let mut remaining: u64 = uid.into();
+        for entry in self.database.get_iterator()? {
+            let entry: DBEntry<DBRecord, C::UID> = entry?;
+            if matches!(entry.item, DBRecord::Item { id: found, .. } if found == id) {
+                if remaining == 0 {
+                    return Ok(entry.uid);
+                }
+                remaining -= 1;
+            }
+        }
+
+        Err(DBError::EntryNotFound {
+            uid: Some(uid.into()),
+        })
+    }
+}
+
+impl<'c, 'a, I, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBCollection<'c, 'a, I, CACHE, BLOCK, C>
+where
+    I: Encode + Decode + Hash + Eq,
+{
+    pub fn new(database: &'c mut DBRecordDatabase<'a, CACHE, BLOCK, C>, id: u32) -> Self {
+        let marker: PhantomData<I> = PhantomData;
+        Self {
+            database,
+            id,
+            marker,
+        }
+    }
+
+    pub fn add_entry(&mut self, item: &I) -> Result<(), DBError> {
+        let bytes: Result<Vec<u8>, EncodeError> = encode_to_vec(item, C::config());
+        let bytes: Vec<u8> = bytes.map_err(DBError::SerializeError)?;
+        let id: u32 = self.id;
+        self.database.add_entry(&DBRecord::Item { id, bytes })
+    }
+
+    pub fn get_iterator(
+        &mut self,
+    ) -> Result<DBCollectionIterator<'_, I, CACHE, BLOCK, C>, DBError> {
+        let iterator: DBIterator<'_, BTreeSet<DBRecord>, CACHE, BLOCK, C> =
+            self.database.get_iterator()?;
+        Ok(DBCollectionIterator::new(iterator, self.id))
+    }
+
+    pub fn get_by_uid(&mut self, uid: C::UID) -> Result<DBEntry<I, C::UID>, DBError> {
+        for entry in self.get_iterator()? {
+            let entry: DBEntry<I, C::UID> = entry?;
+            if entry.uid == uid {
+                return Ok(entry);
+            }
+        }
+
+        Err(DBError::EntryNotFound {
+            uid: Some(uid.into()),
+        })
+    }
+
+    /// Removes the entry, renumbering the entries of this collection after
+    /// it. Other collections keep their UIDs.
+    pub fn remove_by_uid(&mut self, uid: C::UID) -> Result<(), DBError> {
+        let record_uid: C::UID = self.record_uid(uid)?;
+        self.database.remove_by_uid(record_uid)
+    }
+}
+
+/// Iterates over the entries of a single collection, skipping the records
+/// of the others. Records that fail to decode are returned as errors, since
+/// their collection is unknown.
+pub struct DBCollectionIterator<'c, I, const CACHE: usize, const BLOCK: usize, C>
+where
+    I: Encode + Decode + Hash + Eq,
+    C: DBConfig,
+{
+    iterator: DBIterator<'c, BTreeSet<DBRecord>, CACHE, BLOCK, C>,
+    id: u32,
+    next_uid: u64,
+    marker: PhantomData<I>,
+}
+
+impl<'c, I, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBCollectionIterator<'c, I, CACHE, BLOCK, C>
+where
+    I: Encode + Decode + Hash + Eq,
+{
+    fn decode(&mut self, bytes: &[u8]) -> Result<DBEntry<I, C::UID>, DBError> {
+        let uid: Option<C::UID> = C::UID::from_u64(self.next_uid);
+        let uid: C::UID = uid.ok_or(DBError::UIDOverflow)?;
+        self.next_uid += 1;
+
+        let item: Result<(I, usize), DecodeError> = decode_from_slice(bytes, C::config());
+        let item: Result<I, DecodeError> = match item {
+            Ok((item, length)) if length == bytes.len() => Ok(item),
+            Ok(_) => Err(DecodeError::Other("item left bytes unread")),
+            Err(error) => Err(error),
+        };
+        match item {
+            Ok(item) => Ok(DBEntry::new(uid, item)),
+            Err(error) => Err(DBError::DeserializeError {
+                uid: uid.into(),
+                error,
+            }),
+        }
+    }
+}
+
+impl<'c, I, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBCollectionIterator<'c, I, CACHE, BLOCK, C>
+where
+    I: Encode + Decode + Hash + Eq,
+{
+    pub fn new(iterator: DBIterator<'c, BTreeSet<DBRecord>, CACHE, BLOCK, C>, id: u32) -> Self {
+        let next_uid: u64 = 0;
+        let marker: PhantomData<I> = PhantomData;
+        Self {
+            iterator,
+            id,
+            next_uid,
+            marker,
+        }
+    }
+}
+
+impl<'c, I, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBCollectionIterator<'c, I, CACHE, BLOCK, C>
+where
+    I: Encode + Decode + Hash + Eq,
+{
+    type Item = Result<DBEntry<I, C::UID>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for entry in self.iterator.by_ref() {
+            match entry {
+                Ok(DBEntry {
+                    item: DBRecord::Item { id, bytes },
+                    ..
+                }) if id == self.id => return Some(self.decode(&bytes)),
+                Ok(_) => continue,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,288 @@
+use crate::capped::DBRingCursor;
+use crate::cipher::DBCipher;
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::error::DBError;
+use crate::memory::DBMemory;
+use crate::serializer::DBSerializer;
+use crate::stream::DBFileStream;
+use crate::structures::DBItemEntry;
+use crate::traits::FileBox;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+use core::hash::Hash;
+
+use bincode::Decode;
+use bincode::Encode;
+
+/// Structural problem found while scanning a database file. Only encrypted
+/// entries carry a tag, so other damaged payloads show up as
+/// `UndecodablePayload` or, when an EOE block was lost, as `MissingEOE`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DBIssueKind {
+    /// The entry decoded, but more data followed it before the EOE block, or
+    /// within the payload length of a compact chunk, so the chunk has
+    /// swallowed the chunk after it.
+    MissingEOE,
+    /// Trailing bytes that never reach an EOE block, as left by a torn write.
+    TruncatedTail,
+    /// The UID is not greater than the UID of the previous valid chunk.
+    NonMonotonicUID { previous: u64 },
+    /// The payload could not be decoded as an entry.
+    UndecodablePayload,
+    /// The sealed payload of an encrypted entry was altered.
+    AuthenticationFailed,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DBIssue {
+    pub kind: DBIssueKind,
+    pub offset: usize,
+    pub length: usize,
+    pub uid: Option<u64>,
+}
+
+/// Result of `Database::check` and `Database::repair`.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DBCheckReport {
+    /// Number of chunks that decoded without problems.
+    pub entries: u32,
+    pub issues: Vec<DBIssue>,
+    /// Bytes moved into the quarantine file by `Database::repair`.
+    pub quarantined: usize,
+}
+
+impl DBCheckReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_spans(spans: &[DBChunkSpan]) -> Self {
+        let mut report: DBCheckReport = Self::new();
+        for span in spans {
+            if let Some(kind) = span.issue {
+                report.issues.push(DBIssue {
+                    kind,
+                    offset: span.offset,
+                    length: span.length,
+                    uid: span.uid,
+                });
+                continue;
+            }
+            report.entries = report.entries.saturating_add(1);
+        }
+        report
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Location of a chunk in the file, with the problem found in it, if any.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DBChunkSpan {
+    pub offset: usize,
+    pub length: usize,
+    pub uid: Option<u64>,
+    pub issue: Option<DBIssueKind>,
+}
+
+/// Walks every chunk of a file and classifies it, continuing past bad chunks
+/// instead of stopping at the first error like the iterators do.
+pub struct DBChecker<
+    'a,
+    T,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    serializer: DBSerializer<'a, T, BLOCK, C>,
+    previous: Option<u64>,
+    base: u64,
+    spans: Vec<DBChunkSpan>,
+    memory: Option<&'a DBMemory>,
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBChecker<'a, T, CACHE, BLOCK, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    fn inspect_chunk(&mut self, offset: usize, chunk: &[u8]) {
+        let base: u64 = self.base;
+        let uid: Option<u64> = self.serializer.chunk_uid(chunk, base).ok().map(Into::into);
+        self.base = uid.unwrap_or(base);
+
+        // Padding never fills a whole block, and compact chunks have none
+        let mut spill: usize = BLOCK;
+        if C::ENCODING.compact {
+            spill = 1;
+        }
+
+        let result: Result<(DBItemEntry<T, C>, usize), DBError> =
+            self.serializer.deserialize_with_remainder(chunk, base);
+        let issue: Option<DBIssueKind> = match (result, self.previous) {
+            (Err(DBError::AuthenticationFailed { .. }), _) => {
+                Some(DBIssueKind::AuthenticationFailed)
+            }
+            (Err(_), _) => Some(DBIssueKind::UndecodablePayload),
+            (Ok((_, remainder)), _) if remainder >= spill => Some(DBIssueKind::MissingEOE),
+            (Ok((entry, _)), Some(previous)) if entry.uid.into() <= previous => {
+                Some(DBIssueKind::NonMonotonicUID { previous })
+            }
+            (Ok((entry, _)), _) => {
+                self.previous = Some(entry.uid.into());
+                None
+            }
+        };
+
+        let length: usize = chunk.len();
+        self.spans.push(DBChunkSpan {
+            offset,
+            length,
+            uid,
+            issue,
+        });
+    }
+
+    fn push_tail(&mut self, file: &mut FileBox, offset: usize) -> Result<(), DBError> {
+        file.seek(offset)?;
+        let mut buffer: [u8; 16 * BLOCK_SIZE] = [0; 16 * BLOCK_SIZE];
+        let mut length: usize = 0;
+        loop {
+            let read: usize = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            length += read;
+        }
+
+        if length > 0 {
+            let issue: Option<DBIssueKind> = Some(DBIssueKind::TruncatedTail);
+            self.spans.push(DBChunkSpan {
+                offset,
+                length,
+                uid: None,
+                issue,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBChecker<'a, T, CACHE, BLOCK, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    pub fn new() -> Self {
+        let serializer: DBSerializer<'a, T, BLOCK, C> = DBSerializer::new();
+        let previous: Option<u64> = None;
+        let base: u64 = 0;
+        let spans: Vec<DBChunkSpan> = Vec::new();
+        let memory: Option<&DBMemory> = None;
+        Self {
+            serializer,
+            previous,
+            base,
+            spans,
+            memory,
+        }
+    }
+
+    /// Checks buffered chunks against `memory`, a chunk over the budget
+    /// stops the scan with `DBError::MemoryBudgetExceeded`.
+    pub fn with_memory(mut self, memory: &'a DBMemory) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Opens the sealed entries of an encrypted database with `cipher`.
+    pub fn with_cipher(mut self, cipher: Option<DBCipher>) -> Self {
+        self.serializer.cipher = cipher;
+        self
+    }
+
+    /// Scans a plain database file from the first chunk, in file order.
+    pub fn scan_file(mut self, file: &mut FileBox) -> Result<Vec<DBChunkSpan>, DBError> {
+        let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+        if let Some(memory) = self.memory {
+            db_stream.set_memory(memory);
+        }
+
+        let mut offset: usize = db_stream.get_position();
+        loop {
+            match db_stream.iter_chunk() {
+                Ok(chunk) => self.inspect_chunk(offset, &chunk),
+                Err(DBError::EndOfFileStream | DBError::InvalidData { .. }) => break,
+                Err(error) => return Err(error),
+            }
+            offset = db_stream.get_position();
+        }
+        drop(db_stream);
+
+        self.push_tail(file, offset)?;
+        Ok(self.spans)
+    }
+
+    /// Scans a capped database file in ring order, from oldest to newest.
+    pub fn scan_ring(
+        mut self,
+        file: &mut FileBox,
+        mut ring: DBRingCursor,
+    ) -> Result<Vec<DBChunkSpan>, DBError> {
+        let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::new(file);
+        if let Some(memory) = self.memory {
+            db_stream.set_memory(memory);
+        }
+
+        let mut torn: Option<usize> = None;
+        while let Some((offset, chunk)) = ring.next_located_chunk(&mut db_stream) {
+            match chunk {
+                Ok(chunk) => self.inspect_chunk(offset, &chunk),
+                Err(error @ DBError::MemoryBudgetExceeded { .. }) => return Err(error),
+                Err(_) => torn = Some(offset),
+            }
+        }
+        drop(db_stream);
+
+        if let Some(offset) = torn {
+            self.push_tail(file, offset)?;
+        }
+        Ok(self.spans)
+    }
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Default
+    for DBChecker<'a, T, CACHE, BLOCK, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads the bytes covered by a span.
+pub fn read_span(file: &mut FileBox, span: &DBChunkSpan) -> Result<Vec<u8>, DBError> {
+    file.seek(span.offset)?;
+    let mut buffer: Vec<u8> = alloc::vec![0; span.length];
+    let mut length: usize = 0;
+    while length < buffer.len() {
+        let read: usize = file.read(&mut buffer[length..])?;
+        if read == 0 {
+            return Err(DBError::EndOfFileStream);
+        }
+        length += read;
+    }
+    Ok(buffer)
+}
@@ -0,0 +1,346 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::compact::decode_varint;
+use crate::compact::encode_varint;
+use crate::compact::varint_size;
+use crate::error::DBError;
+use crate::format::FORMAT_FIELDS_SIZE;
+use crate::traits::FileBox;
+use crate::BLOCK_SIZE;
+
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+
+use chacha20poly1305::aead::AeadInPlace;
+use chacha20poly1305::ChaCha20Poly1305;
+use chacha20poly1305::Error as AeadError;
+use chacha20poly1305::KeyInit;
+use chacha20poly1305::Nonce;
+use chacha20poly1305::Tag;
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+
+pub const CIPHER_MAGIC: [u8; BLOCK_SIZE] = [0xC2, 0xB5, 0x63, 0x62];
+/// Bytes of the cipher fields, which follow the format fields.
+pub const CIPHER_FIELDS_SIZE: usize = 7 * BLOCK_SIZE + 2 * TAG_SIZE;
+const NONCE_COUNTER_SIZE: usize = 8;
+/// Largest plaintext ChaCha20-Poly1305 seals under one nonce.
+const SEAL_LIMIT: u64 = (1 << 38) - 64;
+// Nonces of entries end in zeros, so these never collide with them
+const CHECK_NONCE: [u8; NONCE_SIZE] = [0xFF; NONCE_SIZE];
+const COUNTER_NONCE_SUFFIX: [u8; 4] = [1, 0, 0, 0];
+const CHECK_AAD: &[u8] = b"micro-db key check";
+const COUNTER_AAD: &[u8] = b"micro-db nonce counter";
+const SUBKEY_DOMAIN: [u8; 4] = *b"mdbk";
+const FILE_DOMAIN: [u8; 4] = *b"mdbf";
+
+/// Random value from the randomness the standard library seeds its hash
+/// maps with, 0 without it.
+fn random_u64() -> u64 {
+    #[cfg(feature = "std")]
+    {
+        use std::collections::hash_map::RandomState;
+        use std::hash::BuildHasher;
+
+        RandomState::new().hash_one(0u8)
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        0
+    }
+}
+
+/// Key an encrypted database is opened with. `salt` is recorded in files
+/// created with the key and must differ between them, such as a device
+/// serial number followed by a file counter. Files that already exist keep
+/// the salt they were created with.
+#[derive(Clone)]
+pub struct DBKey {
+    key: [u8; KEY_SIZE],
+    salt: u64,
+}
+
+impl Debug for DBKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("DBKey(..)")
+    }
+}
+
+impl DBKey {
+    pub fn new(key: [u8; KEY_SIZE], salt: u64) -> Self {
+        Self { key, salt }
+    }
+
+    /// Key with a salt drawn from the randomness the standard library seeds
+    /// its hash maps with.
+    #[cfg(feature = "std")]
+    pub fn with_random_salt(key: [u8; KEY_SIZE]) -> Self {
+        Self::new(key, random_u64())
+    }
+}
+
+/// Cipher of a single file, keyed with a subkey derived from a `DBKey`, the
+/// salt of the file and a random file id, so that files sharing a key never
+/// share nonces, not even a file created again with the same salt.
+///
+/// Payloads are sealed with ChaCha20-Poly1305 into the payload length as a
+/// varint, the nonce counter as a Little Endian `u64`, the ciphertext and the
+/// tag. The nonce is the counter followed by four zero bytes, and the UID of
+/// the entry as a Little Endian `u64` is the associated data, so a sealed
+/// payload only opens under the UID it was sealed with.
+#[derive(Clone, Copy)]
+pub struct DBCipher {
+    key: [u8; KEY_SIZE],
+}
+
+impl Debug for DBCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("DBCipher(..)")
+    }
+}
+
+impl DBCipher {
+    fn nonce(counter: u64) -> [u8; NONCE_SIZE] {
+        let mut nonce: [u8; NONCE_SIZE] = [0; NONCE_SIZE];
+        nonce[..NONCE_COUNTER_SIZE].copy_from_slice(&counter.to_le_bytes());
+        nonce
+    }
+
+    /// Keystream of `key` under a nonce made of `domain` and `value`.
+    fn subkey(key: &[u8; KEY_SIZE], domain: [u8; 4], value: u64) -> [u8; KEY_SIZE] {
+        let mut nonce: [u8; NONCE_SIZE] = [0; NONCE_SIZE];
+        nonce[..4].copy_from_slice(&domain);
+        nonce[4..].copy_from_slice(&value.to_le_bytes());
+
+        // Zeros encrypt to the keystream, and 32 bytes are far below
+        // `SEAL_LIMIT`
+        let mut subkey: [u8; KEY_SIZE] = [0; KEY_SIZE];
+        let _tag: Option<[u8; TAG_SIZE]> = Self::seal_in_place(key, &nonce, &[], &mut subkey);
+        subkey
+    }
+
+    fn aead(key: &[u8; KEY_SIZE]) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(key.into())
+    }
+
+    /// Encrypts `bytes` in place and returns the tag, `None` above
+    /// `SEAL_LIMIT` bytes.
+    fn seal_in_place(
+        key: &[u8; KEY_SIZE],
+        nonce: &[u8; NONCE_SIZE],
+        aad: &[u8],
+        bytes: &mut [u8],
+    ) -> Option<[u8; TAG_SIZE]> {
+        let nonce: &Nonce = Nonce::from_slice(nonce);
+        let aead: ChaCha20Poly1305 = Self::aead(key);
+        let tag: Result<Tag, AeadError> = aead.encrypt_in_place_detached(nonce, aad, bytes);
+        tag.ok().map(Into::into)
+    }
+}
+
+impl DBCipher {
+    pub fn derive(key: &DBKey, salt: u64, file_id: u64) -> Self {
+        let salted: [u8; KEY_SIZE] = Self::subkey(&key.key, SUBKEY_DOMAIN, salt);
+        let subkey: [u8; KEY_SIZE] = Self::subkey(&salted, FILE_DOMAIN, file_id);
+        Self { key: subkey }
+    }
+
+    /// Tag recorded in the file to tell a wrong key apart from damaged entries.
+    pub fn check_tag(&self) -> [u8; TAG_SIZE] {
+        let tag: Option<[u8; TAG_SIZE]> =
+            Self::seal_in_place(&self.key, &CHECK_NONCE, CHECK_AAD, &mut []);
+        tag.unwrap_or_default()
+    }
+
+    /// Tag recorded next to the nonce counter, so an edited counter fails
+    /// instead of handing out nonces that were already used.
+    pub fn counter_tag(&self, counter: u64) -> [u8; TAG_SIZE] {
+        let mut nonce: [u8; NONCE_SIZE] = Self::nonce(counter);
+        nonce[NONCE_COUNTER_SIZE..].copy_from_slice(&COUNTER_NONCE_SUFFIX);
+        let tag: Option<[u8; TAG_SIZE]> =
+            Self::seal_in_place(&self.key, &nonce, COUNTER_AAD, &mut []);
+        tag.unwrap_or_default()
+    }
+
+    /// Size of the sealed payload for `length` bytes.
+    pub const fn sealed_size(length: usize) -> usize {
+        varint_size(length as u64) + NONCE_COUNTER_SIZE + length + TAG_SIZE
+    }
+
+    /// Appends `bytes` of the entry `uid` sealed with nonce `counter`, which
+    /// must not have been used in the file before. Fails with
+    /// `DBError::EntryTooLarge` above the limit of the cipher, about 256 GiB.
+    pub fn seal(
+        &self,
+        counter: u64,
+        uid: u64,
+        bytes: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), DBError> {
+        encode_varint(bytes.len() as u64, buffer);
+        buffer.extend(counter.to_le_bytes());
+
+        let start: usize = buffer.len();
+        buffer.extend(bytes);
+        let nonce: [u8; NONCE_SIZE] = Self::nonce(counter);
+        let tag: Option<[u8; TAG_SIZE]> =
+            Self::seal_in_place(&self.key, &nonce, &uid.to_le_bytes(), &mut buffer[start..]);
+        let tag: [u8; TAG_SIZE] = tag.ok_or(DBError::EntryTooLarge {
+            size: bytes.len(),
+            capacity: usize::try_from(SEAL_LIMIT).unwrap_or(usize::MAX),
+        })?;
+        buffer.extend(tag);
+        Ok(())
+    }
+
+    /// Opens the sealed payload of the entry `uid` at the start of `payload`,
+    /// returning the plaintext and the bytes the sealed payload took. `None`
+    /// when it was altered, sealed for another UID or with another key, or
+    /// announces more than `limit` bytes.
+    pub fn open(&self, payload: &[u8], uid: u64, limit: usize) -> Option<(Vec<u8>, usize)> {
+        let (length, size) = decode_varint(payload)?;
+        let length: usize = usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= limit)?;
+        let end: usize = Self::sealed_size(length);
+        let sealed: &[u8] = payload.get(size..end)?;
+
+        let (counter, sealed) = sealed.split_at(NONCE_COUNTER_SIZE);
+        let (ciphertext, tag) = sealed.split_at(length);
+        let mut counter_bytes: [u8; NONCE_COUNTER_SIZE] = [0; NONCE_COUNTER_SIZE];
+        counter_bytes.copy_from_slice(counter);
+
+        let nonce: [u8; NONCE_SIZE] = Self::nonce(u64::from_le_bytes(counter_bytes));
+        let nonce: &Nonce = Nonce::from_slice(&nonce);
+        let mut plaintext: Vec<u8> = ciphertext.to_vec();
+        let aead: ChaCha20Poly1305 = Self::aead(&self.key);
+        let tag: &Tag = Tag::from_slice(tag);
+        let opened: Result<(), AeadError> =
+            aead.decrypt_in_place_detached(nonce, &uid.to_le_bytes(), &mut plaintext, tag);
+        opened.ok()?;
+        Some((plaintext, end))
+    }
+}
+
+/// Cipher fields of an encrypted file, stored after the format fields as
+/// the cipher magic, the salt, the file id, the next unused nonce counter,
+/// its tag and the key check tag. The fields are zero until a key is first
+/// set.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DBCipherHeader {
+    pub salt: u64,
+    /// Random per file where the standard library is available, 0 otherwise.
+    pub file_id: u64,
+    pub next_nonce: u64,
+    pub nonce_check: [u8; TAG_SIZE],
+    pub check: [u8; TAG_SIZE],
+}
+
+impl DBCipherHeader {
+    const OFFSET: usize = FORMAT_FIELDS_SIZE;
+    const NEXT_NONCE_OFFSET: usize = Self::OFFSET + 5 * BLOCK_SIZE;
+
+    fn read_fully(file: &mut FileBox, buffer: &mut [u8]) -> Result<usize, DBError> {
+        let mut length: usize = 0;
+        while length < buffer.len() {
+            let read: usize = file.read(&mut buffer[length..])?;
+            if read == 0 {
+                break;
+            }
+            length += read;
+        }
+        Ok(length)
+    }
+
+    fn read_u64(buffer: &[u8]) -> u64 {
+        let mut bytes: [u8; 8] = [0; 8];
+        bytes.copy_from_slice(&buffer[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn read_tag(buffer: &[u8]) -> [u8; TAG_SIZE] {
+        let mut tag: [u8; TAG_SIZE] = [0; TAG_SIZE];
+        tag.copy_from_slice(&buffer[..TAG_SIZE]);
+        tag
+    }
+}
+
+impl DBCipherHeader {
+    pub fn new(key: &DBKey) -> Self {
+        let file_id: u64 = random_u64();
+        let cipher: DBCipher = DBCipher::derive(key, key.salt, file_id);
+        Self {
+            salt: key.salt,
+            file_id,
+            next_nonce: 0,
+            nonce_check: cipher.counter_tag(0),
+            check: cipher.check_tag(),
+        }
+    }
+
+    /// Cipher of the file for `key`, failing with
+    /// `DBError::AuthenticationFailed` when it is not the key of the file.
+    pub fn cipher(&self, key: &DBKey) -> Result<DBCipher, DBError> {
+        let cipher: DBCipher = DBCipher::derive(key, self.salt, self.file_id);
+        if cipher.check_tag() != self.check {
+            return Err(DBError::AuthenticationFailed { uid: None });
+        }
+        Ok(cipher)
+    }
+
+    /// Returns `None` while no key has been set.
+    pub fn read(file: &mut FileBox) -> Result<Option<Self>, DBError> {
+        let mut buffer: [u8; CIPHER_FIELDS_SIZE] = [0; CIPHER_FIELDS_SIZE];
+        file.seek(Self::OFFSET)?;
+        let length: usize = Self::read_fully(file, &mut buffer)?;
+        if length < CIPHER_FIELDS_SIZE || buffer[..BLOCK_SIZE] != CIPHER_MAGIC {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            salt: Self::read_u64(&buffer[BLOCK_SIZE..]),
+            file_id: Self::read_u64(&buffer[3 * BLOCK_SIZE..]),
+            next_nonce: Self::read_u64(&buffer[5 * BLOCK_SIZE..]),
+            nonce_check: Self::read_tag(&buffer[7 * BLOCK_SIZE..]),
+            check: Self::read_tag(&buffer[7 * BLOCK_SIZE + TAG_SIZE..]),
+        }))
+    }
+
+    pub fn write(&self, file: &mut FileBox) -> Result<(), DBError> {
+        let mut buffer: [u8; CIPHER_FIELDS_SIZE] = [0; CIPHER_FIELDS_SIZE];
+        buffer[..BLOCK_SIZE].copy_from_slice(&CIPHER_MAGIC);
+        buffer[BLOCK_SIZE..3 * BLOCK_SIZE].copy_from_slice(&self.salt.to_le_bytes());
+        buffer[3 * BLOCK_SIZE..5 * BLOCK_SIZE].copy_from_slice(&self.file_id.to_le_bytes());
+        buffer[5 * BLOCK_SIZE..7 * BLOCK_SIZE].copy_from_slice(&self.next_nonce.to_le_bytes());
+        buffer[7 * BLOCK_SIZE..7 * BLOCK_SIZE + TAG_SIZE].copy_from_slice(&self.nonce_check);
+        buffer[7 * BLOCK_SIZE + TAG_SIZE..].copy_from_slice(&self.check);
+
+        file.seek(Self::OFFSET)?;
+        file.write_all(&buffer)
+    }
+
+    /// Reserves `count` nonces and returns the first. The counter and its tag
+    /// are written before any entry uses them, so a torn entry write never
+    /// leads to reuse, and a counter that does not match its tag fails with
+    /// `DBError::AuthenticationFailed` instead of handing out used nonces.
+    pub fn reserve(file: &mut FileBox, cipher: &DBCipher, count: usize) -> Result<u64, DBError> {
+        let header: DBCipherHeader = Self::read(file)?.ok_or(DBError::MissingKey)?;
+        if cipher.counter_tag(header.next_nonce) != header.nonce_check {
+            return Err(DBError::AuthenticationFailed { uid: None });
+        }
+        // Never reached in practice, but a wrapped counter would reuse nonces
+        let next_nonce: Option<u64> = header.next_nonce.checked_add(count as u64);
+        let next_nonce: u64 = next_nonce.ok_or(DBError::UIDOverflow)?;
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(2 * BLOCK_SIZE + TAG_SIZE);
+        buffer.extend(next_nonce.to_le_bytes());
+        buffer.extend(cipher.counter_tag(next_nonce));
+        file.seek(Self::NEXT_NONCE_OFFSET)?;
+        file.write_all(&buffer)?;
+        Ok(header.next_nonce)
+    }
+}
@@ -0,0 +1,104 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+/// Bytes a `u64` varint takes at most.
+pub const VARINT_MAX: usize = 10;
+
+/// Bytes `value` takes as a varint.
+pub const fn varint_size(value: u64) -> usize {
+    let bits: usize = (u64::BITS - value.leading_zeros()) as usize;
+    if bits == 0 {
+        return 1;
+    }
+    bits.div_ceil(7)
+}
+
+/// Appends `value` as an LEB128 varint, seven bits per byte with the high
+/// bit set on every byte but the last.
+pub fn encode_varint(mut value: u64, buffer: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Decodes the varint at the start of `bytes`, returning it with the number
+/// of bytes it took. `None` when it is truncated or does not fit a `u64`.
+pub fn decode_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    for (idx, byte) in bytes.iter().take(VARINT_MAX).enumerate() {
+        let bits: u64 = (byte & 0x7F) as u64;
+        if idx == VARINT_MAX - 1 && bits > 1 {
+            return None;
+        }
+
+        value |= bits << (7 * idx);
+        if byte & 0x80 == 0 {
+            return Some((value, idx + 1));
+        }
+    }
+    None
+}
+
+/// Header of a compact chunk, the UID as the distance from the UID of the
+/// chunk before it followed by the payload length, both as varints. The
+/// first chunk of a file counts from 0.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DBCompactHeader {
+    pub delta: u64,
+    pub length: usize,
+    /// Bytes taken by the header itself.
+    pub size: usize,
+}
+
+impl DBCompactHeader {
+    pub fn new(delta: u64, length: usize) -> Self {
+        let size: usize = varint_size(delta) + varint_size(length as u64);
+        Self {
+            delta,
+            length,
+            size,
+        }
+    }
+
+    /// Bytes taken by the header and the payload.
+    pub fn chunk_size(&self) -> usize {
+        self.size + self.length
+    }
+
+    pub fn encode(&self, buffer: &mut Vec<u8>) {
+        encode_varint(self.delta, buffer);
+        encode_varint(self.length as u64, buffer);
+    }
+
+    /// Decodes the header at the start of `bytes`. `None` when it is
+    /// malformed or announces a payload above `limit`, which no writer
+    /// produces.
+    pub fn decode(bytes: &[u8], limit: usize) -> Option<Self> {
+        let (delta, delta_size) = decode_varint(bytes)?;
+        let (length, length_size) = decode_varint(&bytes[delta_size..])?;
+        let length: usize = usize::try_from(length)
+            .ok()
+            .filter(|length| *length <= limit)?;
+
+        let size: usize = delta_size + length_size;
+        Some(Self {
+            delta,
+            length,
+            size,
+        })
+    }
+
+    /// Copies `chunk` with its UID distance replaced by `delta`, used when
+    /// the chunks before it are removed.
+    pub fn rebase(chunk: &[u8], delta: u64) -> Option<Vec<u8>> {
+        let header: DBCompactHeader = Self::decode(chunk, usize::MAX)?;
+        let payload: &[u8] = chunk.get(header.size..header.chunk_size())?;
+
+        let mut buffer: Vec<u8> = Vec::new();
+        Self::new(delta, header.length).encode(&mut buffer);
+        buffer.extend(payload);
+        Some(buffer)
+    }
+}
@@ -0,0 +1,241 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::compact::encode_varint;
+use crate::compact::VARINT_MAX;
+
+use bincode::de::read::Reader;
+use bincode::error::DecodeError;
+
+/// Bytes a match can reach back, the decompressor keeps this many bytes.
+pub const WINDOW_SIZE: usize = 256;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = MIN_MATCH + u8::MAX as usize;
+
+/// First byte of a compressed payload, saying how the entry was stored.
+pub const METHOD_STORED: u8 = 0;
+pub const METHOD_LZSS: u8 = 1;
+
+fn match_length(input: &[u8], from: usize, position: usize) -> usize {
+    let limit: usize = MAX_MATCH.min(input.len() - position);
+    let mut length: usize = 0;
+    while length < limit && input[from + length] == input[position + length] {
+        length += 1;
+    }
+    length
+}
+
+/// Longest earlier match for the bytes at `position`, as the distance back
+/// and the length.
+fn find_match(input: &[u8], position: usize) -> Option<(usize, usize)> {
+    let start: usize = position.saturating_sub(WINDOW_SIZE);
+    let mut best: Option<(usize, usize)> = None;
+    for from in (start..position).rev() {
+        let length: usize = match_length(input, from, position);
+        if length >= MIN_MATCH && best.is_none_or(|(_, best)| length > best) {
+            best = Some((position - from, length));
+            if length == MAX_MATCH {
+                break;
+            }
+        }
+    }
+    best
+}
+
+fn compress_lzss(input: &[u8]) -> Vec<u8> {
+    let mut buffer: Vec<u8> = Vec::with_capacity(1 + VARINT_MAX + input.len());
+    buffer.push(METHOD_LZSS);
+    encode_varint(input.len() as u64, &mut buffer);
+
+    let mut flags_at: usize = 0;
+    let mut flag_bit: u8 = 8;
+    let mut position: usize = 0;
+    while position < input.len() {
+        if flag_bit == 8 {
+            flags_at = buffer.len();
+            buffer.push(0);
+            flag_bit = 0;
+        }
+
+        match find_match(input, position) {
+            Some((distance, length)) => {
+                buffer[flags_at] |= 1 << flag_bit;
+                buffer.push((distance - 1) as u8);
+                buffer.push((length - MIN_MATCH) as u8);
+                position += length;
+            }
+            None => {
+                buffer.push(input[position]);
+                position += 1;
+            }
+        }
+        flag_bit += 1;
+    }
+    buffer
+}
+
+/// Compresses an encoded entry into a payload for `DBDecompressor`. Entries
+/// that do not shrink are stored as they are behind `METHOD_STORED`.
+///
+/// The payload is a method byte followed, for `METHOD_LZSS`, by the decoded
+/// length as a varint and groups of up to 8 items behind a flag byte, least
+/// significant bit first. A clear bit is a literal byte, a set bit a match of
+/// two bytes: the distance back minus 1 and the length minus 3.
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let compressed: Vec<u8> = compress_lzss(bytes);
+    if compressed.len() <= bytes.len() {
+        return compressed;
+    }
+
+    let mut buffer: Vec<u8> = Vec::with_capacity(1 + bytes.len());
+    buffer.push(METHOD_STORED);
+    buffer.extend(bytes);
+    buffer
+}
+
+/// Reader decompressing a payload written by `compress` as it is read, with
+/// no more memory than the window. Payloads announcing more than `limit`
+/// decoded bytes fail with `DecodeError::LimitExceeded`.
+pub struct DBDecompressor<R> {
+    reader: R,
+    limit: usize,
+    window: [u8; WINDOW_SIZE],
+    /// Decoded length, `None` until the payload header is read.
+    length: Option<usize>,
+    stored: bool,
+    written: usize,
+    consumed: usize,
+    flags: u8,
+    flag_bits: u8,
+    distance: usize,
+    copying: usize,
+}
+
+impl<R: Reader> DBDecompressor<R> {
+    fn read_input(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        self.reader.read(bytes)?;
+        self.consumed += bytes.len();
+        Ok(())
+    }
+
+    fn read_input_byte(&mut self) -> Result<u8, DecodeError> {
+        let mut byte: [u8; 1] = [0];
+        self.read_input(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_length(&mut self) -> Result<usize, DecodeError> {
+        let mut length: u64 = 0;
+        for idx in 0..VARINT_MAX {
+            let byte: u8 = self.read_input_byte()?;
+            length |= ((byte & 0x7F) as u64) << (7 * idx);
+            if byte & 0x80 == 0 {
+                return usize::try_from(length)
+                    .map_err(|_| DecodeError::Other("invalid compressed entry length"));
+            }
+        }
+        Err(DecodeError::Other("invalid compressed entry length"))
+    }
+
+    fn read_header(&mut self) -> Result<usize, DecodeError> {
+        if let Some(length) = self.length {
+            return Ok(length);
+        }
+
+        let length: usize = match self.read_input_byte()? {
+            METHOD_STORED => {
+                self.stored = true;
+                usize::MAX
+            }
+            METHOD_LZSS => self.read_length()?,
+            _ => return Err(DecodeError::Other("unknown compression method")),
+        };
+        if !self.stored && length > self.limit {
+            return Err(DecodeError::LimitExceeded);
+        }
+        self.length = Some(length);
+        Ok(length)
+    }
+
+    fn start_item(&mut self) -> Result<Option<u8>, DecodeError> {
+        if self.flag_bits == 0 {
+            self.flags = self.read_input_byte()?;
+            self.flag_bits = 8;
+        }
+        let is_match: bool = self.flags & 1 == 1;
+        self.flags >>= 1;
+        self.flag_bits -= 1;
+
+        if !is_match {
+            return Ok(Some(self.read_input_byte()?));
+        }
+
+        let mut pair: [u8; 2] = [0; 2];
+        self.read_input(&mut pair)?;
+        self.distance = pair[0] as usize + 1;
+        self.copying = pair[1] as usize + MIN_MATCH;
+        if self.distance > self.written {
+            return Err(DecodeError::Other("invalid compressed entry match"));
+        }
+        Ok(None)
+    }
+
+    fn next_byte(&mut self, length: usize, additional: usize) -> Result<u8, DecodeError> {
+        if self.written == length {
+            return Err(DecodeError::UnexpectedEnd { additional });
+        }
+
+        let byte: u8 = match self.copying {
+            0 => match self.start_item()? {
+                Some(byte) => byte,
+                None => return self.next_byte(length, additional),
+            },
+            _ => {
+                self.copying -= 1;
+                self.window[(self.written - self.distance) % WINDOW_SIZE]
+            }
+        };
+        self.window[self.written % WINDOW_SIZE] = byte;
+        self.written += 1;
+        Ok(byte)
+    }
+}
+
+impl<R: Reader> DBDecompressor<R> {
+    pub fn new(reader: R, limit: usize) -> Self {
+        Self {
+            reader,
+            limit,
+            window: [0; WINDOW_SIZE],
+            length: None,
+            stored: false,
+            written: 0,
+            consumed: 0,
+            flags: 0,
+            flag_bits: 0,
+            distance: 0,
+            copying: 0,
+        }
+    }
+
+    /// Payload bytes read so far.
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+}
+
+impl<R: Reader> Reader for DBDecompressor<R> {
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        let length: usize = self.read_header()?;
+        if self.stored {
+            return self.read_input(bytes);
+        }
+
+        let total: usize = bytes.len();
+        for (idx, byte) in bytes.iter_mut().enumerate() {
+            let additional: usize = total - idx;
+            *byte = self.next_byte(length, additional)?;
+        }
+        Ok(())
+    }
+}
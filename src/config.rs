@@ -0,0 +1,403 @@
+use crate::DECODE_LIMIT;
+
+use core::fmt;
+use core::fmt::Debug;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::hash::Hash;
+use core::hash::Hasher;
+use core::marker::PhantomData;
+
+use bincode::config::standard;
+use bincode::config::BigEndian;
+use bincode::config::Config;
+use bincode::config::Configuration;
+use bincode::config::Fixint;
+use bincode::config::Limit;
+use bincode::config::LittleEndian;
+use bincode::config::Varint;
+
+/// Bytes the envelope of an encrypted entry takes at most, the length varint,
+/// the nonce counter and the tag, see `DBCipher`.
+const SEALED_OVERHEAD: usize = 10 + 8 + 16;
+
+/// Configuration used for entries unless a database is given another one.
+pub type DBStandardConfig = DBBincodeConfig<LittleEndian, Varint, DECODE_LIMIT>;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DBEndian {
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DBIntEncoding {
+    Varint,
+    Fixint,
+}
+
+/// How entries are encoded, as recorded in the format header.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DBEncoding {
+    pub endian: DBEndian,
+    pub int_encoding: DBIntEncoding,
+    /// Largest number of bytes a single entry may take, encoded or decoded.
+    pub limit: usize,
+    /// Bytes of each UID, 4 or 8.
+    pub uid_size: usize,
+    /// Chunks use the compact layout, see `DBCompact`.
+    pub compact: bool,
+    /// Entries are compressed, see `DBCompressed`.
+    pub compressed: bool,
+    /// Entries are encrypted, see `DBEncrypted`.
+    pub encrypted: bool,
+}
+
+impl DBEncoding {
+    pub const STANDARD: DBEncoding = DBEncoding {
+        endian: DBEndian::Little,
+        int_encoding: DBIntEncoding::Varint,
+        limit: DECODE_LIMIT,
+        uid_size: 4,
+        compact: false,
+        compressed: false,
+        encrypted: false,
+    };
+
+    /// Largest payload a chunk may hold, the limit with the byte in front of
+    /// a compressed entry and the envelope of an encrypted one.
+    pub const fn payload_limit(&self) -> usize {
+        let mut limit: usize = self.limit;
+        if self.compressed {
+            limit += 1;
+        }
+        if self.encrypted {
+            limit += SEALED_OVERHEAD;
+        }
+        limit
+    }
+
+    pub const fn is_standard(&self) -> bool {
+        matches!(self.endian, DBEndian::Little)
+            && matches!(self.int_encoding, DBIntEncoding::Varint)
+            && self.limit == DECODE_LIMIT
+            && self.uid_size == 4
+            && !self.compact
+            && !self.compressed
+            && !self.encrypted
+    }
+}
+
+impl Display for DBEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let endian: &str = match self.endian {
+            DBEndian::Little => "little endian",
+            DBEndian::Big => "big endian",
+        };
+        let int_encoding: &str = match self.int_encoding {
+            DBIntEncoding::Varint => "varint",
+            DBIntEncoding::Fixint => "fixint",
+        };
+        let layout: &str = match self.compact {
+            true => "compact",
+            false => "block",
+        };
+        let compression: &str = match self.compressed {
+            true => ", compressed",
+            false => "",
+        };
+        let encryption: &str = match self.encrypted {
+            true => ", encrypted",
+            false => "",
+        };
+        write!(
+            f,
+            "{endian} {int_encoding} with a {} byte limit and {}-bit UIDs in {layout} chunks{compression}{encryption}",
+            self.limit,
+            self.uid_size * 8
+        )
+    }
+}
+
+/// Integer type of entry UIDs. UIDs are stored Little Endian at the start
+/// of the first block of each chunk.
+pub trait DBUid: Copy + Eq + Ord + Hash + Debug + Display + Default + Into<u64> + 'static {
+    const SIZE: usize;
+
+    /// Returns `None` when `value` is outside the UID range.
+    fn from_u64(value: u64) -> Option<Self>;
+
+    fn checked_next(self) -> Option<Self>;
+
+    fn write_le(self, buffer: &mut [u8]);
+
+    /// Reads the UID from the first `SIZE` bytes of `buffer`.
+    fn read_le(buffer: &[u8]) -> Self;
+}
+
+impl DBUid for u32 {
+    const SIZE: usize = 4;
+
+    fn from_u64(value: u64) -> Option<Self> {
+        u32::try_from(value).ok()
+    }
+
+    fn checked_next(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn write_le(self, buffer: &mut [u8]) {
+        buffer[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(buffer: &[u8]) -> Self {
+        let mut bytes: [u8; 4] = [0; 4];
+        bytes.copy_from_slice(&buffer[..Self::SIZE]);
+        u32::from_le_bytes(bytes)
+    }
+}
+
+impl DBUid for u64 {
+    const SIZE: usize = 8;
+
+    fn from_u64(value: u64) -> Option<Self> {
+        Some(value)
+    }
+
+    fn checked_next(self) -> Option<Self> {
+        self.checked_add(1)
+    }
+
+    fn write_le(self, buffer: &mut [u8]) {
+        buffer[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+    }
+
+    fn read_le(buffer: &[u8]) -> Self {
+        let mut bytes: [u8; 8] = [0; 8];
+        bytes.copy_from_slice(&buffer[..Self::SIZE]);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// Byte order of a bincode configuration.
+pub trait DBEndianConfig: Copy {
+    const ENDIAN: DBEndian;
+
+    fn apply<I, L>(config: Configuration<LittleEndian, I, L>) -> Configuration<Self, I, L>;
+}
+
+impl DBEndianConfig for LittleEndian {
+    const ENDIAN: DBEndian = DBEndian::Little;
+
+    fn apply<I, L>(config: Configuration<LittleEndian, I, L>) -> Configuration<Self, I, L> {
+        config
+    }
+}
+
+impl DBEndianConfig for BigEndian {
+    const ENDIAN: DBEndian = DBEndian::Big;
+
+    fn apply<I, L>(config: Configuration<LittleEndian, I, L>) -> Configuration<Self, I, L> {
+        config.with_big_endian()
+    }
+}
+
+/// Integer encoding of a bincode configuration.
+pub trait DBIntConfig: Copy {
+    const INT_ENCODING: DBIntEncoding;
+
+    fn apply<E, L>(config: Configuration<E, Varint, L>) -> Configuration<E, Self, L>;
+}
+
+impl DBIntConfig for Varint {
+    const INT_ENCODING: DBIntEncoding = DBIntEncoding::Varint;
+
+    fn apply<E, L>(config: Configuration<E, Varint, L>) -> Configuration<E, Self, L> {
+        config
+    }
+}
+
+impl DBIntConfig for Fixint {
+    const INT_ENCODING: DBIntEncoding = DBIntEncoding::Fixint;
+
+    fn apply<E, L>(config: Configuration<E, Varint, L>) -> Configuration<E, Self, L> {
+        config.with_fixed_int_encoding()
+    }
+}
+
+/// Bincode configuration of a database, see `DBBincodeConfig`.
+pub trait DBConfig: Debug + Clone + Copy + Eq + Hash + Default {
+    type Config: Config;
+    type UID: DBUid;
+    const ENCODING: DBEncoding;
+
+    fn config() -> Self::Config;
+}
+
+/// Encodes entries with byte order `E`, integer encoding `I` and a limit of
+/// `LIMIT` bytes per entry, such as `DBBincodeConfig<BigEndian, Fixint, 4096>`.
+/// The limit keeps a malformed entry from claiming an arbitrary amount of
+/// memory while it is decoded.
+pub struct DBBincodeConfig<E = LittleEndian, I = Varint, const LIMIT: usize = DECODE_LIMIT> {
+    marker: PhantomData<(E, I)>,
+}
+
+impl<E, I, const LIMIT: usize> Debug for DBBincodeConfig<E, I, LIMIT>
+where
+    E: DBEndianConfig,
+    I: DBIntConfig,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let encoding: DBEncoding = DBEncoding {
+            endian: E::ENDIAN,
+            int_encoding: I::INT_ENCODING,
+            limit: LIMIT,
+            uid_size: 4,
+            compact: false,
+            compressed: false,
+            encrypted: false,
+        };
+        write!(f, "DBBincodeConfig({})", encoding)
+    }
+}
+
+impl<E, I, const LIMIT: usize> Clone for DBBincodeConfig<E, I, LIMIT> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E, I, const LIMIT: usize> Copy for DBBincodeConfig<E, I, LIMIT> {}
+
+impl<E, I, const LIMIT: usize> PartialEq for DBBincodeConfig<E, I, LIMIT> {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<E, I, const LIMIT: usize> Eq for DBBincodeConfig<E, I, LIMIT> {}
+
+impl<E, I, const LIMIT: usize> Hash for DBBincodeConfig<E, I, LIMIT> {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl<E, I, const LIMIT: usize> Default for DBBincodeConfig<E, I, LIMIT> {
+    fn default() -> Self {
+        let marker: PhantomData<(E, I)> = PhantomData;
+        Self { marker }
+    }
+}
+
+impl<E, I, const LIMIT: usize> DBConfig for DBBincodeConfig<E, I, LIMIT>
+where
+    E: DBEndianConfig,
+    I: DBIntConfig,
+    Configuration<E, I, Limit<LIMIT>>: Config,
+{
+    type Config = Configuration<E, I, Limit<LIMIT>>;
+    type UID = u32;
+    const ENCODING: DBEncoding = DBEncoding {
+        endian: E::ENDIAN,
+        int_encoding: I::INT_ENCODING,
+        limit: LIMIT,
+        uid_size: 4,
+        compact: false,
+        compressed: false,
+        encrypted: false,
+    };
+
+    fn config() -> Self::Config {
+        I::apply(E::apply(standard().with_limit::<LIMIT>()))
+    }
+}
+
+/// Numbers the entries of `C` with `u64` UIDs instead of `u32`, for logs
+/// that would run out of 32-bit UIDs. The UID must fit in the first block of
+/// a chunk, so blocks need to be at least 8 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DBWideUID<C = DBStandardConfig> {
+    marker: PhantomData<C>,
+}
+
+impl<C: DBConfig> DBConfig for DBWideUID<C> {
+    type Config = C::Config;
+    type UID = u64;
+    const ENCODING: DBEncoding = DBEncoding {
+        uid_size: 8,
+        ..C::ENCODING
+    };
+
+    fn config() -> Self::Config {
+        C::config()
+    }
+}
+
+/// Stores the entries of `C` in compact chunks instead of aligned blocks.
+/// A compact chunk is the UID as the distance from the UID of the chunk
+/// before it and the payload length, both as varints, followed by the
+/// payload without padding. Small entries take a fraction of the space, at
+/// the cost of chunks no longer starting on block boundaries. Capped
+/// databases need aligned blocks and return `DBError::Unsupported`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DBCompact<C = DBStandardConfig> {
+    marker: PhantomData<C>,
+}
+
+impl<C: DBConfig> DBConfig for DBCompact<C> {
+    type Config = C::Config;
+    type UID = C::UID;
+    const ENCODING: DBEncoding = DBEncoding {
+        compact: true,
+        ..C::ENCODING
+    };
+
+    fn config() -> Self::Config {
+        C::config()
+    }
+}
+
+/// Compresses each entry of `C` with a small LZSS using a 256-byte window,
+/// which suits records repeating the same bytes, such as sensor readings with
+/// long runs of identical values. Decoding needs no more memory than the
+/// window. Entries that would not shrink are stored as they are, each
+/// payload starts with a byte saying which, see `compress::compress`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DBCompressed<C = DBStandardConfig> {
+    marker: PhantomData<C>,
+}
+
+impl<C: DBConfig> DBConfig for DBCompressed<C> {
+    type Config = C::Config;
+    type UID = C::UID;
+    const ENCODING: DBEncoding = DBEncoding {
+        compressed: true,
+        ..C::ENCODING
+    };
+
+    fn config() -> Self::Config {
+        C::config()
+    }
+}
+
+/// Encrypts each entry of `C` with ChaCha20-Poly1305 under a key set with
+/// `Database::set_key`, so a stolen device gives away neither the entries nor
+/// a way to alter them unnoticed. Altered entries, and entries read with the
+/// wrong key, fail with `DBError::AuthenticationFailed`. UIDs and the chunk
+/// layout stay in the clear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DBEncrypted<C = DBStandardConfig> {
+    marker: PhantomData<C>,
+}
+
+impl<C: DBConfig> DBConfig for DBEncrypted<C> {
+    type Config = C::Config;
+    type UID = C::UID;
+    const ENCODING: DBEncoding = DBEncoding {
+        encrypted: true,
+        ..C::ENCODING
+    };
+
+    fn config() -> Self::Config {
+        C::config()
+    }
+}
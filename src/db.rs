@@ -1,4 +1,11 @@
+use crate::capped;
+use crate::check;
+use crate::cipher;
+use crate::compact;
+use crate::config;
 use crate::error;
+use crate::format;
+use crate::memory;
 use crate::serializer;
 use crate::stream;
 use crate::structures;
@@ -10,221 +17,1357 @@ extern crate alloc;
 use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 
+use core::borrow::Borrow;
 use core::hash::Hash;
 use core::marker::PhantomData;
+use core::ops::Range;
 
+use capped::DBCapacity;
+use capped::DBRingCursor;
+use capped::DBRingHeader;
+use check::read_span;
+use check::DBCheckReport;
+use check::DBChecker;
+use check::DBChunkSpan;
+use cipher::DBCipher;
+use cipher::DBCipherHeader;
+use cipher::DBKey;
+use compact::DBCompactHeader;
+use config::DBConfig;
+use config::DBStandardConfig;
+use config::DBUid;
 use error::DBError;
+use format::DBFormatHeader;
+use format::FORMAT_FIELDS_SIZE;
+use memory::DBMemory;
+use memory::DBMemoryStats;
 use serializer::DBSerializer;
 use serializer::UIDSerializer;
 use stream::DBFileStream;
 use structures::DBChunkIterator;
 use structures::DBEntry;
+use structures::DBItemEntry;
 use structures::DBIterator;
+use structures::DBSliceIterator;
 use traits::CPathBox;
 use traits::CPathTrait;
 use traits::FileBox;
-
 use traits::OpenFileBox;
 
 use bincode::Decode;
 use bincode::Encode;
 
-pub struct Database<'a, T>
-where
+/// Entries `add_entries_iter` holds in memory at a time.
+pub const APPEND_BATCH: usize = 64;
+
+/// UID the next entry takes after `Database::clear`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DBClearPolicy {
+    /// Numbers entries from 0 again.
+    ResetUids,
+    /// Continues after the last UID. Plain files record it as the UID of
+    /// their first entry in the format header, files without a header
+    /// return `DBError::Unsupported`.
+    KeepUids,
+}
+
+/// Entries taken out by `Database::remove_where` or `remove_range`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DBRemoved<U> {
+    /// Number of removed entries.
+    pub count: usize,
+    /// UIDs the removed entries had before the others were numbered again.
+    pub uids: Vec<U>,
+}
+
+/// Database of `T::Item` entries, read through a stream cache of `CACHE`
+/// bytes and stored in blocks of `BLOCK` bytes. `BLOCK` must be a multiple of
+/// 4. Entries are encoded with `C`, see `DBBincodeConfig`. The block size and
+/// encoding are recorded in the file when they differ from the defaults.
+/// Encrypted databases need a key, see `set_key`.
+pub struct Database<
+    'a,
+    T,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
+    C: DBConfig,
 {
     pub path: CPathBox,
     pub open: OpenFileBox,
     pub file: Option<(FileBox, [bool; 3])>,
+    pub capacity: Option<DBCapacity>,
+    pub read_only: bool,
+    pub memory: DBMemory,
+    pub cipher: Option<DBCipher>,
     pub marker: PhantomData<&'a T>,
+    pub config: PhantomData<C>,
 }
 
-impl<'a, T> Database<'a, T>
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Database<'a, T, CACHE, BLOCK, C>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
-    fn get_uid_from_chunk(chunk: Option<Vec<u8>>) -> u32 {
-        if let Some(chunk) = chunk {
-            let first_block: &[u8] = &chunk[..BLOCK_SIZE];
-            let uid: u32 = UIDSerializer::new().deserialize_uid(first_block).unwrap();
-            return uid + 1;
+    /// UID after the one in `block`, or `first` for an empty file.
+    fn get_uid_from_block(block: Option<[u8; BLOCK]>, first: u64) -> Result<C::UID, DBError> {
+        if let Some(block) = block {
+            let uid: C::UID = UIDSerializer::new().deserialize_uid(&block)?;
+            return uid.checked_next().ok_or(DBError::UIDOverflow);
+        }
+        C::UID::from_u64(first).ok_or(DBError::UIDOverflow)
+    }
+
+    /// Gives the compact chunk starting `data` the distance of `uid` from 0,
+    /// which the first chunk of a file counts from.
+    fn first_chunk(data: Vec<u8>, uid: C::UID) -> Result<Vec<u8>, DBError> {
+        if !C::ENCODING.compact || uid == C::UID::default() {
+            return Ok(data);
+        }
+
+        let header: Option<DBCompactHeader> = DBCompactHeader::decode(&data, usize::MAX);
+        let size: usize = header.map(|header| header.chunk_size()).unwrap_or(0);
+        let chunk: Option<Vec<u8>> = data
+            .get(..size)
+            .and_then(|chunk| DBCompactHeader::rebase(chunk, uid.into()));
+        let mut bytes: Vec<u8> = chunk.ok_or(DBError::InvalidData { offset: None })?;
+        bytes.extend(&data[size..]);
+        Ok(bytes)
+    }
+
+    /// Claims the encoded size of `items` before anything is serialized, so
+    /// an oversized write fails without touching the file.
+    fn claim_items<'b, I>(
+        memory: &DBMemory,
+        serializer: &DBSerializer<'_, T, BLOCK, C>,
+        items: I,
+    ) -> Result<(), DBError>
+    where
+        I: Iterator<Item = &'b T::Item>,
+        T::Item: 'b,
+    {
+        if memory.budget().is_none() {
+            return Ok(());
+        }
+
+        let mut size: usize = 0;
+        for item in items {
+            size += serializer.serialized_size(item)?;
+        }
+        memory.claim(size)
+    }
+
+    fn get_ring_header(
+        file: &mut FileBox,
+        capacity: &DBCapacity,
+    ) -> Result<DBRingHeader<BLOCK, C>, DBError> {
+        // The ring relies on chunks that carry their own UID
+        if C::ENCODING.compact {
+            return Err(DBError::Unsupported);
+        }
+
+        match DBRingHeader::read(file)? {
+            Some(header) => Ok(header),
+            None => DBRingHeader::new(capacity),
+        }
+    }
+
+    /// Cipher entries are sealed with, failing with `DBError::MissingKey`
+    /// while an encrypted database has no key.
+    fn cipher(&self) -> Result<Option<DBCipher>, DBError> {
+        if C::ENCODING.encrypted && self.cipher.is_none() {
+            return Err(DBError::MissingKey);
+        }
+        Ok(self.cipher)
+    }
+
+    /// Serializer sealing `count` entries with nonces reserved in `file`.
+    fn sealing_serializer<'b>(
+        file: &mut FileBox,
+        cipher: Option<DBCipher>,
+        count: usize,
+    ) -> Result<DBSerializer<'b, T, BLOCK, C>, DBError> {
+        let db_serializer: DBSerializer<'b, T, BLOCK, C> = DBSerializer::new();
+        match cipher {
+            Some(db_cipher) => {
+                let nonce: u64 = DBCipherHeader::reserve(file, &db_cipher, count)?;
+                Ok(db_serializer.with_cipher(cipher, nonce))
+            }
+            None => Ok(db_serializer),
+        }
+    }
+
+    /// Copies `chunk` numbered `uid`, the chunk before it being `uid - 1`
+    /// unless it is the `first` chunk of the file.
+    fn renumber_chunk(
+        chunk: &[u8],
+        uid: C::UID,
+        first: bool,
+        offset: usize,
+    ) -> Result<Vec<u8>, DBError> {
+        if !C::ENCODING.compact {
+            let mut bytes: Vec<u8> = chunk.to_vec();
+            let block: [u8; BLOCK] = UIDSerializer::new().serialize_uid(uid);
+            bytes[..BLOCK].copy_from_slice(&block);
+            return Ok(bytes);
+        }
+
+        // The first chunk counts from 0 and the others from the chunk before
+        let delta: u64 = match first {
+            true => uid.into(),
+            false => 1,
+        };
+        let bytes: Option<Vec<u8>> = DBCompactHeader::rebase(chunk, delta);
+        bytes.ok_or(DBError::InvalidData {
+            offset: Some(offset),
+        })
+    }
+
+    /// Copies `chunk` of entry `uid` renumbered to `next_uid`, see
+    /// `renumber_chunk`. Sealed payloads are bound to their UID, so they are
+    /// sealed again under the nonces reserved for `db_serializer`.
+    fn move_chunk(
+        db_serializer: &DBSerializer<'_, T, BLOCK, C>,
+        chunk: &[u8],
+        uid: C::UID,
+        next_uid: C::UID,
+        first: bool,
+        offset: usize,
+    ) -> Result<Vec<u8>, DBError> {
+        if C::ENCODING.encrypted && uid != next_uid {
+            let bytes: Vec<u8> = db_serializer.reseal(chunk, uid, next_uid)?;
+            return Self::renumber_chunk(&bytes, next_uid, first, offset);
         }
-        0
+        Self::renumber_chunk(chunk, next_uid, first, offset)
+    }
+
+    /// Chunks of `file` with their UIDs, claiming a buffer for the largest.
+    /// Fails with `DBError::InvalidData` when `check` would report an issue.
+    fn scan_uids(
+        file: &mut FileBox,
+        memory: &DBMemory,
+        cipher: Option<DBCipher>,
+    ) -> Result<(Vec<DBChunkSpan>, Vec<C::UID>), DBError> {
+        let checker: DBChecker<'_, T, CACHE, BLOCK, C> =
+            DBChecker::new().with_memory(memory).with_cipher(cipher);
+        let spans: Vec<DBChunkSpan> = checker.scan_file(file)?;
+        let mut uids: Vec<C::UID> = Vec::with_capacity(spans.len());
+        for span in spans.iter() {
+            let uid: Option<C::UID> = span.uid.and_then(C::UID::from_u64);
+            match (span.issue, uid) {
+                (None, Some(uid)) => uids.push(uid),
+                _ => {
+                    let offset: Option<usize> = Some(span.offset);
+                    return Err(DBError::InvalidData { offset });
+                }
+            }
+        }
+
+        let largest: usize = spans.iter().map(|span| span.length).max().unwrap_or(0);
+        memory.claim(largest)?;
+        Ok((spans, uids))
+    }
+
+    /// Writes the chunks whose UID `keep` accepts to `scratch`, numbered
+    /// from the first UID. Returns the number of left out chunks.
+    fn write_retained<F: FnMut(C::UID) -> bool>(
+        file: &mut FileBox,
+        scratch: &mut FileBox,
+        db_serializer: &DBSerializer<'_, T, BLOCK, C>,
+        spans: &[DBChunkSpan],
+        uids: Vec<C::UID>,
+        mut keep: F,
+    ) -> Result<usize, DBError> {
+        let mut next_uid: C::UID = uids.first().copied().unwrap_or_default();
+        let mut first: bool = true;
+        let mut removed: usize = 0;
+        for (span, uid) in spans.iter().zip(uids) {
+            if !keep(uid) {
+                removed += 1;
+                continue;
+            }
+
+            let bytes: Vec<u8> = read_span(file, span)?;
+            let bytes: Vec<u8> =
+                Self::move_chunk(db_serializer, &bytes, uid, next_uid, first, span.offset)?;
+            scratch.write_all(&bytes)?;
+            first = false;
+            next_uid = next_uid.checked_next().ok_or(DBError::UIDOverflow)?;
+        }
+        Ok(removed)
+    }
+
+    /// Writes the chunks of `file` to `scratch`, encoding the item `update`
+    /// returns in place of an entry and copying the others. Returns the
+    /// number of replaced entries.
+    fn write_updated<F>(
+        file: &mut FileBox,
+        scratch: &mut FileBox,
+        db_serializer: &DBSerializer<'_, T, BLOCK, C>,
+        memory: &DBMemory,
+        spans: &[DBChunkSpan],
+        uids: Vec<C::UID>,
+        mut update: F,
+    ) -> Result<usize, DBError>
+    where
+        F: FnMut(DBEntry<T::Item, C::UID>) -> Result<Option<T::Item>, DBError>,
+    {
+        let mut base: u64 = 0;
+        let mut updated: usize = 0;
+        for (span, uid) in spans.iter().zip(uids) {
+            let bytes: Vec<u8> = read_span(file, span)?;
+            let (entry, _) = db_serializer.deserialize_with_remainder(&bytes, base)?;
+            base = uid.into();
+
+            let item: T::Item = match update(entry)? {
+                Some(item) => item,
+                None => {
+                    scratch.write_all(&bytes)?;
+                    continue;
+                }
+            };
+            Self::claim_items(memory, db_serializer, core::iter::once(&item))?;
+            let data: Vec<u8> = db_serializer.serialize(uid, &item)?;
+            memory.record(data.len());
+            scratch.write_all(&data)?;
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    /// Writes the header of the file and the chunks `write` produces to
+    /// `scratch`, then renames it over the file, see `rewrite_by_uid`. A
+    /// rewrite that changed nothing removes `scratch` and keeps the file.
+    fn rewrite_with<W>(&mut self, scratch: &dyn CPathTrait, write: W) -> Result<usize, DBError>
+    where
+        W: FnOnce(
+            &mut FileBox,
+            &mut FileBox,
+            &DBSerializer<'_, T, BLOCK, C>,
+            &DBMemory,
+            &[DBChunkSpan],
+            Vec<C::UID>,
+        ) -> Result<usize, DBError>,
+    {
+        self.check_writable()?;
+        if self.capacity.is_some() {
+            return Err(DBError::Unsupported);
+        }
+
+        self.open_file([true, true, false])?;
+        let mut open: OpenFileBox = self.open.boxed();
+        let cipher: Option<DBCipher> = self.cipher()?;
+        let changed: Result<usize, DBError> = match &mut self.file {
+            Some((file, _)) => {
+                if DBRingHeader::<BLOCK, C>::detect(file)?.is_some() {
+                    return Err(DBError::Unsupported);
+                }
+
+                // Nonces are reserved in the file before its header is copied
+                let (spans, uids) = Self::scan_uids(file, &self.memory, cipher)?;
+                let db_serializer: DBSerializer<'_, T, BLOCK, C> =
+                    Self::sealing_serializer(file, cipher, spans.len())?;
+
+                open.reset();
+                open.read(true);
+                open.write(true);
+                open.create(true);
+                open.truncate(true);
+                let mut scratch_file: FileBox = open.open(scratch)?;
+                let length: usize = DBFormatHeader::<BLOCK, C>::start(file)?;
+                let header: DBChunkSpan = DBChunkSpan {
+                    offset: 0,
+                    length,
+                    uid: None,
+                    issue: None,
+                };
+                let changed: Result<usize, DBError> = read_span(file, &header)
+                    .and_then(|bytes| scratch_file.write_all(&bytes))
+                    .and_then(|_| {
+                        write(
+                            file,
+                            &mut scratch_file,
+                            &db_serializer,
+                            &self.memory,
+                            &spans,
+                            uids,
+                        )
+                    })
+                    .and_then(|changed| scratch_file.sync().map(|_| changed));
+                scratch_file.close().and(changed)
+            }
+            None => return Err(DBError::FailedToRetrieveIterator),
+        };
+
+        if let Ok(0) = changed {
+            open.remove(scratch)?;
+            return Ok(0);
+        }
+        self.close_file()?;
+        let result: Result<usize, DBError> =
+            changed.and_then(|changed| open.rename(scratch, &*self.path).map(|_| changed));
+        if result.is_err() {
+            let _ = open.remove(scratch);
+        }
+        result
+    }
+
+    /// Single pass behind `retain_by_uid`. With `decode` set, `keep` also
+    /// gets the entry of each chunk, see `remove_where`.
+    fn retain_chunks<F>(&mut self, decode: bool, mut keep: F) -> Result<usize, DBError>
+    where
+        F: FnMut(C::UID, Option<DBItemEntry<T, C>>) -> bool,
+    {
+        self.check_writable()?;
+        if self.capacity.is_some() {
+            return Err(DBError::Unsupported);
+        }
+
+        self.open_file([true, true, false])?;
+        let cipher: Option<DBCipher> = self.cipher()?;
+        if let Some((file, _)) = &mut self.file {
+            if DBRingHeader::<BLOCK, C>::detect(file)?.is_some() {
+                return Err(DBError::Unsupported);
+            }
+
+            let (spans, uids) = Self::scan_uids(file, &self.memory, cipher)?;
+            let db_serializer: DBSerializer<'_, T, BLOCK, C> =
+                Self::sealing_serializer(file, cipher, spans.len())?;
+
+            let start: usize = DBFormatHeader::<BLOCK, C>::start(file)?;
+            let mut position: usize = start;
+            let mut next_uid: C::UID = uids.first().copied().unwrap_or_default();
+            let mut base: u64 = 0;
+            let mut removed: usize = 0;
+            let mut failed: Option<DBError> = None;
+            for (span, uid) in spans.iter().zip(uids) {
+                let mut bytes: Option<Vec<u8>> = None;
+                let mut entry: Option<DBItemEntry<T, C>> = None;
+                if decode && failed.is_none() {
+                    let chunk: Vec<u8> = read_span(file, span)?;
+                    match db_serializer.deserialize_with_remainder(&chunk, base) {
+                        Ok((decoded, _)) => entry = Some(decoded),
+                        // Checked chunks decode, whatever does not is kept
+                        Err(error) => failed = Some(error),
+                    }
+                    bytes = Some(chunk);
+                }
+                base = uid.into();
+                if failed.is_none() && !keep(uid, entry) {
+                    removed += 1;
+                    continue;
+                }
+
+                // Kept chunks never grow, so they never overwrite unread ones
+                if span.offset != position || uid != next_uid {
+                    let bytes: Vec<u8> = match bytes {
+                        Some(bytes) => bytes,
+                        None => read_span(file, span)?,
+                    };
+                    let first: bool = position == start;
+                    let bytes: Vec<u8> = Self::move_chunk(
+                        &db_serializer,
+                        &bytes,
+                        uid,
+                        next_uid,
+                        first,
+                        span.offset,
+                    )?;
+                    file.seek(position)?;
+                    file.write_all(&bytes)?;
+                    position += bytes.len();
+                } else {
+                    position += span.length;
+                }
+                next_uid = next_uid.checked_next().ok_or(DBError::UIDOverflow)?;
+            }
+
+            file.set_len(position)?;
+            return match failed {
+                Some(error) => Err(error),
+                None => Ok(removed),
+            };
+        }
+        Err(DBError::FailedToRetrieveIterator)
+    }
+
+    /// Appends `items` to the ring, returning the UIDs they were given.
+    fn add_capped_entries<I>(
+        &mut self,
+        capacity: &DBCapacity,
+        items: I,
+    ) -> Result<Range<C::UID>, DBError>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T::Item>,
+    {
+        self.open_file([true, true, true])?;
+        let cipher: Option<DBCipher> = self.cipher()?;
+        if let Some((file, _)) = &mut self.file {
+            let mut header: DBRingHeader<BLOCK, C> = Self::get_ring_header(file, capacity)?;
+            let start: Option<C::UID> = C::UID::from_u64(header.next_uid);
+            let start: C::UID = start.ok_or(DBError::UIDOverflow)?;
+
+            for item in items {
+                let item: &T::Item = item.borrow();
+                let db_serializer: DBSerializer<'_, T, BLOCK, C> =
+                    Self::sealing_serializer(file, cipher, 1)?;
+                Self::claim_items(&self.memory, &db_serializer, core::iter::once(item))?;
+                let uid: Option<C::UID> = C::UID::from_u64(header.next_uid);
+                let uid: C::UID = uid.ok_or(DBError::UIDOverflow)?;
+                let data: Vec<u8> = db_serializer.serialize(uid, item)?;
+                self.memory.record(data.len());
+                header.append(file, &data)?;
+            }
+
+            let end: Option<C::UID> = C::UID::from_u64(header.next_uid);
+            let end: C::UID = end.ok_or(DBError::UIDOverflow)?;
+            return Ok(start..end);
+        }
+        Err(DBError::FailedToRetrieveIterator)
     }
 }
 
-impl<'a, T> Database<'a, T>
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Database<'a, T, CACHE, BLOCK, C>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
+    /// Creates a database without touching storage, the file is opened on
+    /// first use. Use `open` or `create` to validate the file up front.
     pub fn new(path: &dyn CPathTrait, open: OpenFileBox) -> Self {
         let path: CPathBox = path.boxed();
         let file: Option<(FileBox, [bool; 3])> = None;
+        let capacity: Option<DBCapacity> = None;
+        let read_only: bool = false;
+        let memory: DBMemory = DBMemory::new(None);
+        let cipher: Option<DBCipher> = None;
         let marker: PhantomData<&T> = PhantomData;
+        let config: PhantomData<C> = PhantomData;
         Database {
             path,
             open,
             file,
+            capacity,
+            read_only,
+            memory,
+            cipher,
             marker,
+            config,
         }
     }
 
+    /// Creates a capped database that evicts its oldest entries once the
+    /// capacity is reached. The limits are recorded in the file on the first
+    /// write, and an existing file keeps the limits it was created with.
+    pub fn new_capped(path: &dyn CPathTrait, open: OpenFileBox, capacity: DBCapacity) -> Self {
+        let mut database: Database<'a, T, CACHE, BLOCK, C> = Self::new(path, open);
+        database.capacity = Some(capacity);
+        database
+    }
+
+    /// Creates a new database, failing with `DBError::AlreadyExists` if the
+    /// file is already present.
+    pub fn create(path: &dyn CPathTrait, open: OpenFileBox) -> Result<Self, DBError> {
+        Self::new(path, open).create_file()
+    }
+
+    /// Creates a new capped database and records its limits in the file.
+    pub fn create_capped(
+        path: &dyn CPathTrait,
+        open: OpenFileBox,
+        capacity: DBCapacity,
+    ) -> Result<Self, DBError> {
+        Self::new_capped(path, open, capacity).create_file()
+    }
+
+    /// Opens an existing database, failing with `DBError::NotFound` if the
+    /// file is missing. Capped databases are detected from their header.
+    pub fn open(path: &dyn CPathTrait, open: OpenFileBox) -> Result<Self, DBError> {
+        let mut database: Database<'a, T, CACHE, BLOCK, C> = Self::new(path, open);
+        database.open_file([true, true, false])?;
+        database.validate()?;
+        Ok(database)
+    }
+
+    /// Opens an existing database or creates an empty one if it is missing.
+    pub fn open_or_create(path: &dyn CPathTrait, open: OpenFileBox) -> Result<Self, DBError> {
+        let mut database: Database<'a, T, CACHE, BLOCK, C> = Self::new(path, open);
+        database.open_file([true, true, true])?;
+        database.validate()?;
+        Ok(database)
+    }
+
+    /// Opens an existing database or creates a capped one if it is missing,
+    /// an existing file keeps the limits it was created with.
+    pub fn open_or_create_capped(
+        path: &dyn CPathTrait,
+        open: OpenFileBox,
+        capacity: DBCapacity,
+    ) -> Result<Self, DBError> {
+        let mut database: Database<'a, T, CACHE, BLOCK, C> = Self::new_capped(path, open, capacity);
+        database.open_file([true, true, true])?;
+        database.validate()?;
+        Ok(database)
+    }
+
+    /// Opens an existing database without ever requesting write access,
+    /// mutating methods return `DBError::ReadOnly`.
+    pub fn open_read_only(path: &dyn CPathTrait, open: OpenFileBox) -> Result<Self, DBError> {
+        let mut database: Database<'a, T, CACHE, BLOCK, C> = Self::new(path, open);
+        database.read_only = true;
+        database.open_file([true, false, false])?;
+        database.validate()?;
+        Ok(database)
+    }
+
+    /// Returns the first entry whose `value` equals `query`. Fails at the
+    /// first entry that does not read, see `DBIterator::try_next`.
     pub fn query<Q: PartialEq, V: Fn(&T::Item) -> &Q>(
         &mut self,
         value: V,
         query: Q,
-    ) -> Result<DBEntry<T::Item>, DBError> {
-        let iterator: DBIterator<'_, BTreeSet<<T as IntoIterator>::Item>> = self.get_iterator()?;
+    ) -> Result<DBEntry<T::Item, C::UID>, DBError> {
+        let mut iterator: DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C> =
+            self.get_iterator()?;
 
-        for entry in iterator.into_iter() {
-            if let Ok(entry) = entry {
-                if value(&entry.item) == &query {
-                    return Ok(entry);
-                }
+        while let Some(entry) = iterator.try_next()? {
+            if value(&entry.item) == &query {
+                return Ok(entry);
             }
         }
 
-        Err(DBError::EntryNotFound)
+        Err(DBError::EntryNotFound { uid: None })
     }
 
+    /// Fails at the first entry that does not read, like `query`.
     pub fn contains(&mut self, item: &T::Item) -> Result<bool, DBError> {
-        let iterator: DBIterator<'_, BTreeSet<<T as IntoIterator>::Item>> = self.get_iterator()?;
+        let mut iterator: DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C> =
+            self.get_iterator()?;
 
-        for entry in iterator.into_iter() {
-            if let Ok(entry) = entry {
-                if &entry.item == item {
-                    return Ok(true);
-                }
+        while let Some(entry) = iterator.try_next()? {
+            if &entry.item == item {
+                return Ok(true);
             }
         }
 
         Ok(false)
     }
 
-    pub fn get_by_uid(&mut self, uid: u32) -> Result<DBEntry<T::Item>, DBError> {
-        let iterator: DBIterator<'_, BTreeSet<<T as IntoIterator>::Item>> = self.get_iterator()?;
+    /// Fails at the first entry that does not read, like `query`.
+    pub fn get_by_uid(&mut self, uid: C::UID) -> Result<DBEntry<T::Item, C::UID>, DBError> {
+        let mut iterator: DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C> =
+            self.get_iterator()?;
 
-        for entry in iterator.into_iter() {
-            if let Ok(entry) = entry {
-                if entry.uid == uid {
-                    return Ok(entry);
-                }
+        while let Some(entry) = iterator.try_next()? {
+            if entry.uid == uid {
+                return Ok(entry);
             }
         }
 
-        Err(DBError::EntryNotFound)
+        Err(DBError::EntryNotFound {
+            uid: Some(uid.into()),
+        })
     }
 
-    pub fn remove_by_uid(&mut self, uid: u32) -> Result<(), DBError> {
-        self.open([true, true, false]);
+    /// Counts the entries by walking their chunks, without buffering or
+    /// decoding them. Capped databases read the count from their header. A
+    /// chunk the file ends inside fails with `DBError::EndOfFileStream`.
+    pub fn count(&mut self) -> Result<usize, DBError> {
+        self.open_file([true, false, false])?;
+        let capacity: Option<DBCapacity> = self.capacity;
         if let Some((file, _)) = &mut self.file {
-            let mut db_stream: DBFileStream<CACHE_SIZE> = DBFileStream::new(file);
-            for _ in 0..uid {
-                db_stream.iter_chunk()?;
+            if let Some(capacity) = capacity {
+                let header: DBRingHeader<BLOCK, C> = Self::get_ring_header(file, &capacity)?;
+                return Ok(header.count as usize);
+            }
+
+            let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+            let mut count: usize = 0;
+            while db_stream.pass_chunk()? {
+                count += 1;
+            }
+            return Ok(count);
+        }
+        Err(DBError::FailedToRetrieveIterator)
+    }
+
+    pub fn count_where<P: FnMut(&T::Item) -> bool>(
+        &mut self,
+        mut predicate: P,
+    ) -> Result<usize, DBError> {
+        self.fold(0, |count, entry| {
+            count + usize::from(predicate(&entry.item))
+        })
+    }
+
+    /// Returns the entry with the smallest key, the first one on ties.
+    pub fn min_by_key<K: Ord, F: FnMut(&T::Item) -> K>(
+        &mut self,
+        mut key: F,
+    ) -> Result<Option<DBItemEntry<T, C>>, DBError> {
+        let min: Option<(K, DBItemEntry<T, C>)> = self.fold(None, |min, entry| {
+            let found: K = key(&entry.item);
+            match min {
+                Some((min_key, min)) if min_key <= found => Some((min_key, min)),
+                _ => Some((found, entry)),
             }
+        })?;
+        Ok(min.map(|(_, entry)| entry))
+    }
+
+    /// Returns the entry with the largest key, the last one on ties.
+    pub fn max_by_key<K: Ord, F: FnMut(&T::Item) -> K>(
+        &mut self,
+        mut key: F,
+    ) -> Result<Option<DBItemEntry<T, C>>, DBError> {
+        let max: Option<(K, DBItemEntry<T, C>)> = self.fold(None, |max, entry| {
+            let found: K = key(&entry.item);
+            match max {
+                Some((max_key, max)) if max_key > found => Some((max_key, max)),
+                _ => Some((found, entry)),
+            }
+        })?;
+        Ok(max.map(|(_, entry)| entry))
+    }
+
+    /// Folds every entry into `init` in a single pass, failing at the first
+    /// entry that does not read, see `DBIterator::try_next`.
+    pub fn fold<B, F: FnMut(B, DBEntry<T::Item, C::UID>) -> B>(
+        &mut self,
+        init: B,
+        mut f: F,
+    ) -> Result<B, DBError> {
+        let mut accumulator: B = init;
+        let mut iterator: DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C> =
+            self.get_iterator()?;
+        while let Some(entry) = iterator.try_next()? {
+            accumulator = f(accumulator, entry);
+        }
+        Ok(accumulator)
+    }
+
+    pub fn remove_by_uid(&mut self, uid: C::UID) -> Result<(), DBError> {
+        self.check_writable()?;
+        if self.capacity.is_some() {
+            return Err(DBError::Unsupported);
+        }
+
+        // The entries after it are renumbered, so their payloads are sealed
+        // again for their new UIDs
+        if C::ENCODING.encrypted {
+            let removed: usize = self.retain_by_uid(|other| other != uid)?;
+            if removed == 0 {
+                let uid: Option<u64> = Some(uid.into());
+                return Err(DBError::EntryNotFound { uid });
+            }
+            return Ok(());
+        }
+
+        self.open_file([true, true, false])?;
+        if let Some((file, _)) = &mut self.file {
+            // UIDs count up from the first one without gaps
+            let first: u64 = DBFormatHeader::<BLOCK, C>::read_first_uid(file)?;
+            let position: Option<u64> = uid.into().checked_sub(first);
+            let position: u64 = position.ok_or(DBError::EntryNotFound {
+                uid: Some(uid.into()),
+            })?;
+            let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+            db_stream.set_memory(&self.memory);
+            let result: Result<(), DBError> = (0..position)
+                .try_for_each(|_| db_stream.skip_chunk().map(|_| ()))
+                .and_then(|_| db_stream.remove_chunk());
 
-            db_stream.remove_chunk()?;
+            // A file that ends before the entry does not hold it
+            match result {
+                Err(DBError::EndOfFileStream) => {
+                    let uid: Option<u64> = Some(uid.into());
+                    return Err(DBError::EntryNotFound { uid });
+                }
+                result => result?,
+            }
         }
         Ok(())
     }
 
     pub fn add_entry(&mut self, item: &T::Item) -> Result<(), DBError> {
-        self.open([true, true, true]);
+        self.check_writable()?;
+        if let Some(capacity) = self.capacity {
+            self.add_capped_entries(&capacity, core::iter::once(item))?;
+            return Ok(());
+        }
+
+        self.open_file([true, true, true])?;
+        let cipher: Option<DBCipher> = self.cipher()?;
         if let Some((file, _)) = &mut self.file {
-            let mut db_stream: DBFileStream<CACHE_SIZE> = DBFileStream::new(file);
-            let db_serializer: DBSerializer<'_, T> = DBSerializer::new();
+            let db_serializer: DBSerializer<'_, T, BLOCK, C> =
+                Self::sealing_serializer(file, cipher, 1)?;
+            let first: u64 = DBFormatHeader::<BLOCK, C>::read_first_uid(file)?;
+            let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
 
-            let last_chunk: Option<Vec<u8>> = db_stream.last_chunk();
-            let uid: u32 = Self::get_uid_from_chunk(last_chunk);
+            Self::claim_items(&self.memory, &db_serializer, core::iter::once(item))?;
+            let last_block: Option<[u8; BLOCK]> = db_stream.last_uid_block()?;
+            let uid: C::UID = Self::get_uid_from_block(last_block, first)?;
 
-            let data: Vec<u8> = db_serializer.serialize(uid, item).unwrap();
-            db_stream.append_end(&data);
+            let mut data: Vec<u8> = db_serializer.serialize(uid, item)?;
+            if last_block.is_none() {
+                data = Self::first_chunk(data, uid)?;
+            }
+            self.memory.record(data.len());
+            db_stream.append_end(&data)?;
         }
         Ok(())
     }
 
     pub fn add_entries(&mut self, items: BTreeSet<T::Item>) -> Result<(), DBError> {
-        self.open([true, true, true]);
+        self.check_writable()?;
+        if let Some(capacity) = self.capacity {
+            self.add_capped_entries(&capacity, items.iter())?;
+            return Ok(());
+        }
+
+        self.open_file([true, true, true])?;
+        let cipher: Option<DBCipher> = self.cipher()?;
         if let Some((file, _)) = &mut self.file {
-            let mut db_stream: DBFileStream<CACHE_SIZE> = DBFileStream::new(file);
-            let db_serializer: DBSerializer<'_, T> = DBSerializer::new();
+            let db_serializer: DBSerializer<'_, T, BLOCK, C> =
+                Self::sealing_serializer(file, cipher, items.len())?;
+            let first: u64 = DBFormatHeader::<BLOCK, C>::read_first_uid(file)?;
+            let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
 
-            let last_chunk: Option<Vec<u8>> = db_stream.last_chunk();
-            let uid: u32 = Self::get_uid_from_chunk(last_chunk);
+            Self::claim_items(&self.memory, &db_serializer, items.iter())?;
+            let last_block: Option<[u8; BLOCK]> = db_stream.last_uid_block()?;
+            let uid: C::UID = Self::get_uid_from_block(last_block, first)?;
 
-            let data: Vec<u8> = db_serializer.serialize_items(uid, items).unwrap();
-            db_stream.append_end(&data);
+            let mut data: Vec<u8> = db_serializer.serialize_items(uid, items)?;
+            if last_block.is_none() && !data.is_empty() {
+                data = Self::first_chunk(data, uid)?;
+            }
+            self.memory.record(data.len());
+            db_stream.append_end(&data)?;
         }
         Ok(())
     }
 
-    pub fn get_iterator(&mut self) -> Result<DBIterator<'_, BTreeSet<T::Item>>, DBError> {
-        self.open([true, false, false]);
+    /// Adds `items` in their order, keeping duplicates, and returns the UIDs
+    /// they were given. Items are encoded and written `APPEND_BATCH` at a
+    /// time, so memory stays bounded however many there are. A failed write
+    /// keeps the entries written before it.
+    pub fn add_entries_iter<I: IntoIterator<Item = T::Item>>(
+        &mut self,
+        items: I,
+    ) -> Result<Range<C::UID>, DBError> {
+        self.check_writable()?;
+        if let Some(capacity) = self.capacity {
+            return self.add_capped_entries(&capacity, items);
+        }
+
+        self.open_file([true, true, true])?;
+        let cipher: Option<DBCipher> = self.cipher()?;
+        let mut items: I::IntoIter = items.into_iter();
+        // UID of the next entry and offset of the last chunk, once known
+        let mut next: Option<(C::UID, usize)> = None;
+        let mut start: Option<C::UID> = None;
+        let mut empty: bool = false;
+        if let Some((file, _)) = &mut self.file {
+            loop {
+                let batch: Vec<T::Item> = items.by_ref().take(APPEND_BATCH).collect();
+                if batch.is_empty() && next.is_some() {
+                    break;
+                }
+
+                let db_serializer: DBSerializer<'_, T, BLOCK, C> =
+                    Self::sealing_serializer(file, cipher, batch.len())?;
+                let first: u64 = DBFormatHeader::<BLOCK, C>::read_first_uid(file)?;
+                let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+                let (mut uid, mut last): (C::UID, usize) = match next {
+                    Some((uid, last)) => {
+                        db_stream.seek(last)?;
+                        (uid, last)
+                    }
+                    None => {
+                        let last_block: Option<[u8; BLOCK]> = db_stream.last_uid_block()?;
+                        empty = last_block.is_none();
+                        (Self::get_uid_from_block(last_block, first)?, 0)
+                    }
+                };
+                start = start.or(Some(uid));
+
+                for item in &batch {
+                    Self::claim_items(&self.memory, &db_serializer, core::iter::once(item))?;
+                    let mut data: Vec<u8> = db_serializer.serialize(uid, item)?;
+                    if empty {
+                        data = Self::first_chunk(data, uid)?;
+                        empty = false;
+                    }
+                    uid = uid.checked_next().ok_or(DBError::UIDOverflow)?;
+                    self.memory.record(data.len());
+                    db_stream.append_end(&data)?;
+                    last = db_stream.get_position() - data.len();
+                }
+                next = Some((uid, last));
+            }
+        }
+
+        match (start, next) {
+            (Some(start), Some((end, _))) => Ok(start..end),
+            _ => Err(DBError::FailedToRetrieveIterator),
+        }
+    }
+
+    pub fn get_iterator(
+        &mut self,
+    ) -> Result<DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C>, DBError> {
+        self.open_file([true, false, false])?;
+        let capacity: Option<DBCapacity> = self.capacity;
+        let cipher: Option<DBCipher> = self.cipher()?;
+        if let Some((file, _)) = &mut self.file {
+            if let Some(capacity) = capacity {
+                let header: DBRingHeader<BLOCK, C> = Self::get_ring_header(file, &capacity)?;
+                let ring: DBRingCursor = DBRingCursor::new(&header);
+                let iterator: DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C> =
+                    DBIterator::from_ring(file, ring);
+                return Ok(iterator.with_memory(&self.memory).with_cipher(cipher));
+            }
+
+            let iterator: DBIterator<'_, BTreeSet<T::Item>, CACHE, BLOCK, C> =
+                DBIterator::from_file(file)?;
+            return Ok(iterator.with_memory(&self.memory).with_cipher(cipher));
+        }
+        Err(DBError::FailedToRetrieveIterator)
+    }
+
+    pub fn get_chunk_iterator(&mut self) -> Result<DBChunkIterator<'_, CACHE, BLOCK, C>, DBError> {
+        self.open_file([true, false, false])?;
+        let capacity: Option<DBCapacity> = self.capacity;
+        if let Some((file, _)) = &mut self.file {
+            if let Some(capacity) = capacity {
+                let header: DBRingHeader<BLOCK, C> = Self::get_ring_header(file, &capacity)?;
+                let ring: DBRingCursor = DBRingCursor::new(&header);
+                return Ok(DBChunkIterator::from_ring(file, ring).with_memory(&self.memory));
+            }
+
+            let iterator: DBChunkIterator<'_, CACHE, BLOCK, C> = DBChunkIterator::from_file(file)?;
+            return Ok(iterator.with_memory(&self.memory));
+        }
+        Err(DBError::FailedToRetrieveIterator)
+    }
+
+    /// Iterates over borrowed chunks, which requires a file backend that
+    /// exposes its contents in memory such as `MmapFile`.
+    pub fn get_slice_iterator(&mut self) -> Result<DBSliceIterator<'_, BLOCK, C>, DBError> {
+        self.open_file([true, false, false])?;
+        let capacity: Option<DBCapacity> = self.capacity;
         if let Some((file, _)) = &mut self.file {
-            let iterator: DBIterator<'_, BTreeSet<<T as IntoIterator>::Item>> =
-                DBIterator::<BTreeSet<T::Item>>::from_file(file);
-            return Ok(iterator);
+            let mut ring: Option<DBRingCursor> = None;
+            if let Some(capacity) = capacity {
+                let header: DBRingHeader<BLOCK, C> = Self::get_ring_header(file, &capacity)?;
+                ring = Some(DBRingCursor::new(&header));
+            }
+
+            let file: &FileBox = file;
+            let buffer: &[u8] = file.as_bytes().ok_or(DBError::Unsupported)?;
+            if let Some(ring) = ring {
+                return Ok(DBSliceIterator::from_ring(buffer, ring));
+            }
+            return Ok(DBSliceIterator::from_slice(buffer));
         }
         Err(DBError::FailedToRetrieveIterator)
     }
 
-    pub fn get_chunk_iterator(&mut self) -> Result<DBChunkIterator<'_>, DBError> {
-        self.open([true, false, false]);
+    /// Walks the whole file and reports structural problems, without
+    /// stopping at the first bad chunk.
+    pub fn check(&mut self) -> Result<DBCheckReport, DBError> {
+        self.open_file([true, false, false])?;
+        let capacity: Option<DBCapacity> = self.capacity;
+        let cipher: Option<DBCipher> = self.cipher()?;
         if let Some((file, _)) = &mut self.file {
-            let iterator: DBChunkIterator<'_> = DBChunkIterator::from_file(file);
-            return Ok(iterator);
+            let checker: DBChecker<'_, T, CACHE, BLOCK, C> = DBChecker::new()
+                .with_memory(&self.memory)
+                .with_cipher(cipher);
+            let header: Option<DBRingHeader<BLOCK, C>> = DBRingHeader::detect(file)?;
+            let spans: Vec<DBChunkSpan> = match (header, capacity) {
+                (Some(header), _) => checker.scan_ring(file, DBRingCursor::new(&header))?,
+                (None, Some(_)) => Vec::new(),
+                (None, None) => checker.scan_file(file)?,
+            };
+            return Ok(DBCheckReport::from_spans(&spans));
         }
         Err(DBError::FailedToRetrieveIterator)
     }
+
+    /// Moves every chunk that `check` reports into the quarantine file,
+    /// compacts the remaining chunks and truncates a torn tail. Quarantined
+    /// chunks are appended to the quarantine file unchanged. Capped databases
+    /// return `DBError::Unsupported`.
+    pub fn repair(&mut self, quarantine: &dyn CPathTrait) -> Result<DBCheckReport, DBError> {
+        self.check_writable()?;
+        if self.capacity.is_some() {
+            return Err(DBError::Unsupported);
+        }
+
+        self.open_file([true, true, false])?;
+        let mut open: OpenFileBox = self.open.boxed();
+        let cipher: Option<DBCipher> = self.cipher()?;
+        if let Some((file, _)) = &mut self.file {
+            if DBRingHeader::<BLOCK, C>::detect(file)?.is_some() {
+                return Err(DBError::Unsupported);
+            }
+
+            let checker: DBChecker<'_, T, CACHE, BLOCK, C> = DBChecker::new()
+                .with_memory(&self.memory)
+                .with_cipher(cipher);
+            let spans: Vec<DBChunkSpan> = checker.scan_file(file)?;
+            let mut report: DBCheckReport = DBCheckReport::from_spans(&spans);
+            if report.is_clean() {
+                return Ok(report);
+            }
+
+            // Every span is read into a buffer, fail before anything moves
+            let largest: usize = spans.iter().map(|span| span.length).max().unwrap_or(0);
+            self.memory.claim(largest)?;
+
+            open.reset();
+            open.write(true);
+            open.append(true);
+            open.create(true);
+            let mut quarantine_file: FileBox = open.open(quarantine)?;
+
+            let mut position: usize = DBFormatHeader::<BLOCK, C>::start(file)?;
+            let mut base: u64 = 0;
+            for span in spans.iter() {
+                let mut bytes: Vec<u8> = read_span(file, span)?;
+                if span.issue.is_some() {
+                    quarantine_file.write_all(&bytes)?;
+                    report.quarantined += bytes.len();
+                    continue;
+                }
+
+                // Compact chunks count from the last chunk that stays
+                if let (true, Some(uid)) = (C::ENCODING.compact, span.uid) {
+                    let chunk: Option<Vec<u8>> = DBCompactHeader::rebase(&bytes, uid - base);
+                    let offset: Option<usize> = Some(span.offset);
+                    bytes = chunk.ok_or(DBError::InvalidData { offset })?;
+                    base = uid;
+                }
+
+                if span.offset != position {
+                    file.seek(position)?;
+                    file.write_all(&bytes)?;
+                }
+                position += bytes.len();
+            }
+
+            quarantine_file.close()?;
+            file.set_len(position)?;
+            return Ok(report);
+        }
+        Err(DBError::FailedToRetrieveIterator)
+    }
+
+    /// Sets the key of an encrypted database. A file without a key takes
+    /// `key` and its salt, otherwise the key must be the one the file was
+    /// created with, or this fails with `DBError::AuthenticationFailed`.
+    /// Databases that are not encrypted return `DBError::Unsupported`.
+    pub fn set_key(&mut self, key: &DBKey) -> Result<(), DBError> {
+        if !C::ENCODING.encrypted {
+            return Err(DBError::Unsupported);
+        }
+
+        let writable: bool = !self.read_only;
+        self.open_file([true, writable, writable])?;
+        if let Some((file, _)) = &mut self.file {
+            let header: DBCipherHeader = match DBCipherHeader::read(file)? {
+                Some(header) => header,
+                None if writable => {
+                    let header: DBCipherHeader = DBCipherHeader::new(key);
+                    header.write(file)?;
+                    header
+                }
+                None if Self::is_empty(file)? => DBCipherHeader::new(key),
+                None => {
+                    let offset: Option<usize> = Some(FORMAT_FIELDS_SIZE);
+                    return Err(DBError::InvalidData { offset });
+                }
+            };
+
+            self.cipher = Some(header.cipher(key)?);
+        }
+        Ok(())
+    }
+
+    /// Keeps the entries whose UID `keep` accepts and removes the others in a
+    /// single pass, moving the kept chunks forward and numbering them from the
+    /// first UID like `remove_by_uid` does. Returns the number of removed
+    /// entries.
+    /// Fails with `DBError::InvalidData` before anything moves when the file
+    /// has an issue that `check` would report, and capped databases return
+    /// `DBError::Unsupported`.
+    pub fn retain_by_uid<F: FnMut(C::UID) -> bool>(
+        &mut self,
+        mut keep: F,
+    ) -> Result<usize, DBError> {
+        self.retain_chunks(false, |uid, _| keep(uid))
+    }
+
+    /// Keeps the entries whose UID `keep` accepts like `retain_by_uid`, but
+    /// writes them to `scratch` and renames it over the file once it is
+    /// complete, so a torn rewrite leaves the file as it was. Backends that
+    /// cannot rename return `DBError::Unsupported` and keep the file.
+    pub fn rewrite_by_uid<F: FnMut(C::UID) -> bool>(
+        &mut self,
+        scratch: &dyn CPathTrait,
+        keep: F,
+    ) -> Result<usize, DBError> {
+        self.rewrite_with(scratch, |file, scratch, db_serializer, _, spans, uids| {
+            Self::write_retained(file, scratch, db_serializer, spans, uids, keep)
+        })
+    }
+
+    /// Replaces each entry that `update` returns an item for, keeping its
+    /// UID, and copies the others. The file is rewritten to `scratch` and
+    /// renamed like in `rewrite_by_uid`, and is left as is when `update`
+    /// returns no item or fails. Returns the number of replaced entries.
+    pub fn rewrite_entries<F>(
+        &mut self,
+        scratch: &dyn CPathTrait,
+        update: F,
+    ) -> Result<usize, DBError>
+    where
+        F: FnMut(DBEntry<T::Item, C::UID>) -> Result<Option<T::Item>, DBError>,
+    {
+        self.rewrite_with(
+            scratch,
+            |file, scratch, db_serializer, memory, spans, uids| {
+                Self::write_updated(file, scratch, db_serializer, memory, spans, uids, update)
+            },
+        )
+    }
+
+    /// Removes the entries matching `predicate` in a single pass like
+    /// `retain_by_uid`, deciding on each entry as the pass reaches it.
+    /// Returns the removed entries with the UIDs they had. Entries that
+    /// `check` would report stop the removal before anything moves.
+    pub fn remove_where<P: FnMut(&T::Item) -> bool>(
+        &mut self,
+        mut predicate: P,
+    ) -> Result<DBRemoved<C::UID>, DBError> {
+        let mut uids: Vec<C::UID> = Vec::new();
+        let count: usize = self.retain_chunks(true, |uid, entry| match entry {
+            Some(entry) if predicate(&entry.item) => {
+                uids.push(uid);
+                false
+            }
+            _ => true,
+        })?;
+        Ok(DBRemoved { count, uids })
+    }
+
+    /// Removes the entries with a UID in `uids` in a single pass, see
+    /// `retain_by_uid`, and returns them with the UIDs they had.
+    pub fn remove_range(&mut self, uids: Range<C::UID>) -> Result<DBRemoved<C::UID>, DBError> {
+        let mut removed: Vec<C::UID> = Vec::new();
+        let count: usize = self.retain_by_uid(|uid| {
+            if uids.contains(&uid) {
+                removed.push(uid);
+                return false;
+            }
+            true
+        })?;
+        Ok(DBRemoved {
+            count,
+            uids: removed,
+        })
+    }
+
+    /// Removes every entry by truncating the file after its format header,
+    /// which keeps the nonce counter of encrypted files. Plain files record
+    /// the UID the next entry takes in their format header, files with the
+    /// default format get one only to keep a UID other than 0.
+    pub fn clear(&mut self, policy: DBClearPolicy) -> Result<(), DBError> {
+        self.check_writable()?;
+        let capacity: Option<DBCapacity> = self.capacity;
+        let size: usize = DBFormatHeader::<BLOCK, C>::SIZE;
+
+        self.open_file([true, true, false])?;
+        if let Some((file, _)) = &mut self.file {
+            if Self::is_empty(file)? {
+                // An empty plain file may still continue from a kept UID
+                if capacity.is_none()
+                    && policy == DBClearPolicy::ResetUids
+                    && DBFormatHeader::<BLOCK, C>::read(file)?.is_some()
+                {
+                    file.set_len(size)?;
+                    DBFormatHeader::<BLOCK, C>::write_first_uid(file, 0)?;
+                }
+                return Ok(());
+            }
+
+            let header: Option<DBRingHeader<BLOCK, C>> = match capacity {
+                Some(capacity) => Some(Self::get_ring_header(file, &capacity)?),
+                None => DBRingHeader::<BLOCK, C>::detect(file)?,
+            };
+            match (capacity, header) {
+                (Some(_), Some(header)) => {
+                    let mut cleared: DBRingHeader<BLOCK, C> = DBRingHeader::new(&header.limits())?;
+                    if policy == DBClearPolicy::KeepUids {
+                        cleared.next_uid = header.next_uid;
+                    }
+                    file.set_len(size)?;
+                    cleared.write(file)?;
+                }
+                (None, None) => {
+                    let mut next_uid: u64 = 0;
+                    if policy == DBClearPolicy::KeepUids {
+                        let first: u64 = DBFormatHeader::<BLOCK, C>::read_first_uid(file)?;
+                        let mut db_stream: DBFileStream<CACHE, BLOCK, C> =
+                            DBFileStream::open(file)?;
+                        let last_block: Option<[u8; BLOCK]> = db_stream.last_uid_block()?;
+                        next_uid = Self::get_uid_from_block(last_block, first)?.into();
+                    }
+                    file.set_len(size)?;
+                    DBFormatHeader::<BLOCK, C>::write_first_uid(file, next_uid)?;
+                }
+                _ => return Err(DBError::Unsupported),
+            }
+            return Ok(());
+        }
+        Err(DBError::FailedToRetrieveIterator)
+    }
+
+    /// Closes the database and deletes its file through
+    /// `OpenFileTrait::remove`.
+    pub fn destroy(mut self) -> Result<(), DBError> {
+        self.check_writable()?;
+        self.close_file()?;
+        self.open.remove(self.path.as_ref())
+    }
+
+    /// Limits transient buffers to `budget` bytes, operations that would need
+    /// more return `DBError::MemoryBudgetExceeded`. `None` removes the limit.
+    pub fn set_memory_budget(&mut self, budget: Option<usize>) {
+        self.memory.set_budget(budget);
+    }
+
+    /// Reports the stream cache size and the largest transient buffer used
+    /// since the database was created.
+    pub fn memory_stats(&self) -> DBMemoryStats {
+        DBMemoryStats {
+            cache_size: CACHE,
+            block_size: BLOCK,
+            peak_transient: self.memory.peak(),
+            budget: self.memory.budget(),
+        }
+    }
+
+    /// Closes the underlying file, reporting errors that dropping the
+    /// database would otherwise discard.
+    pub fn close(mut self) -> Result<(), DBError> {
+        self.close_file()
+    }
 }
 
-impl<'a, T> Database<'a, T>
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Database<'a, T, CACHE, BLOCK, C>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
-    fn open(&mut self, rwc: [bool; 3]) {
+    fn create_file(mut self) -> Result<Self, DBError> {
+        match self.open_file([true, false, false]) {
+            Ok(()) => {
+                self.close_file()?;
+                return Err(DBError::AlreadyExists);
+            }
+            Err(DBError::NotFound) => {}
+            Err(error) => return Err(error),
+        }
+
+        self.open_file([true, true, true])?;
+        self.validate()?;
+        Ok(self)
+    }
+
+    fn is_empty(file: &mut FileBox) -> Result<bool, DBError> {
+        let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        let start: usize = DBFormatHeader::<BLOCK, C>::start(file)?;
+        file.seek(start)?;
+        let length: usize = file.read(&mut block)?;
+        Ok(length == 0)
+    }
+
+    /// Checks that the open file is a database of the expected kind, adopts
+    /// the limits of a capped file and writes the header of a new one.
+    fn validate(&mut self) -> Result<(), DBError> {
+        let mut capacity: Option<DBCapacity> = self.capacity;
+        if capacity.is_some() && C::ENCODING.compact {
+            return Err(DBError::Unsupported);
+        }
+
+        if let Some((file, rwc)) = &mut self.file {
+            let header: Option<DBRingHeader<BLOCK, C>> = DBRingHeader::detect(file)?;
+            if let Some(header) = header {
+                capacity = Some(header.limits());
+            } else if Self::is_empty(file)? {
+                if let (Some(capacity), true) = (capacity, rwc[1]) {
+                    DBRingHeader::<BLOCK, C>::new(&capacity)?.write(file)?;
+                }
+            } else if capacity.is_some() {
+                let offset: Option<usize> = Some(DBFormatHeader::<BLOCK, C>::start(file)?);
+                return Err(DBError::InvalidData { offset });
+            } else {
+                let mut db_stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+                db_stream.skip_chunk()?;
+            }
+        }
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    fn check_writable(&self) -> Result<(), DBError> {
+        if self.read_only {
+            return Err(DBError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    fn open_file(&mut self, rwc: [bool; 3]) -> Result<(), DBError> {
         if let Some((_, file_rwc)) = self.file.as_ref() {
             if file_rwc == &rwc {
-                return;
+                return Ok(());
             }
-            self.close();
+            self.close_file()?;
         }
-        let file: FileBox = self.get_file_from_rwc(&rwc);
+        let mut file: FileBox = self.get_file_from_rwc(&rwc)?;
+        DBFormatHeader::<BLOCK, C>::check(&mut file, rwc[1])?;
         self.file = Some((file, rwc));
+        Ok(())
     }
 
-    fn close(&mut self) {
+    fn close_file(&mut self) -> Result<(), DBError> {
         if let Some((file, _)) = self.file.take() {
-            let _ = file.close();
-            self.file = None;
+            file.close()?;
         }
+        Ok(())
     }
 
-    fn get_file_from_rwc(&mut self, rwc: &[bool; 3]) -> FileBox {
-        if rwc[0] {
-            self.open.read(true);
-        }
-        if rwc[1] {
-            self.open.write(true);
-        }
-        if rwc[2] {
-            self.open.create(true);
-        }
-        let file: FileBox = self.open.open(&*self.path).unwrap();
-        file
+    fn get_file_from_rwc(&mut self, rwc: &[bool; 3]) -> Result<FileBox, DBError> {
+        self.open.reset();
+        self.open.read(rwc[0]);
+        self.open.write(rwc[1]);
+        self.open.create(rwc[2]);
+        self.open.open(&*self.path)
     }
 }
 
-impl<'a, T> Drop for Database<'a, T>
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Drop
+    for Database<'a, T, CACHE, BLOCK, C>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
     fn drop(&mut self) {
-        self.close();
+        let _ = self.close_file();
     }
 }
@@ -1,15 +1,244 @@
+#[cfg(any(feature = "std", feature = "alloc"))]
 extern crate alloc;
+#[cfg(any(feature = "std", feature = "alloc"))]
 use alloc::string::String;
 
+use crate::config::DBEncoding;
+
+use core::fmt;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+use bincode::error::DecodeError;
+use bincode::error::EncodeError;
+
+/// Message of a `DBError::IOError`. Without an allocator, storage backends
+/// can only report static messages.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub type DBErrorMessage = String;
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+pub type DBErrorMessage = &'static str;
+
+/// Broad classification of a `DBError`, so callers can react to the cause
+/// of a failure without matching on every variant.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DBErrorKind {
+    /// The file or the requested entry does not exist.
+    NotFound,
+    /// The file already exists.
+    AlreadyExists,
+    /// No space is left, either on the storage or in the UID range.
+    StorageFull,
+    /// The stored data is truncated or malformed.
+    Corrupt,
+    /// The database or the underlying storage cannot be written.
+    ReadOnly,
+    /// The operation is not supported by this database or backend.
+    Unsupported,
+    /// The caller passed data that cannot be stored.
+    InvalidInput,
+    /// The operation needs more memory than the database may use.
+    OutOfMemory,
+    /// Any other failure.
+    Other,
+}
+
 #[derive(Debug)]
 pub enum DBError {
-    SerializeError,
-    DeserializeError,
+    SerializeError(EncodeError),
+    DeserializeError {
+        uid: u64,
+        error: DecodeError,
+    },
     UIDSerializeError,
-    UIDDeserializeError,
-    InvalidData,
-    EntryNotFound,
+    UIDDeserializeError(DecodeError),
+    /// `offset` is the byte position in the file where the malformed data
+    /// was found, when it is known.
+    InvalidData {
+        offset: Option<usize>,
+    },
+    EntryNotFound {
+        uid: Option<u64>,
+    },
     FailedToRetrieveIterator,
-    IOError(String),
+    IOError {
+        kind: DBErrorKind,
+        message: DBErrorMessage,
+    },
     EndOfFileStream,
+    EntryTooLarge {
+        size: usize,
+        capacity: usize,
+    },
+    /// A capped database was given a byte capacity above `limit`.
+    CapacityTooLarge {
+        capacity: usize,
+        limit: usize,
+    },
+    UIDOverflow,
+    /// The file was written with `found`-byte blocks, but opened as a
+    /// database with `expected`-byte blocks.
+    BlockSizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// A buffer of `size` bytes would exceed the memory budget of the
+    /// database.
+    MemoryBudgetExceeded {
+        size: usize,
+        budget: usize,
+    },
+    /// The file was written with the `found` encoding, but opened as a
+    /// database with the `expected` encoding.
+    EncodingMismatch {
+        expected: DBEncoding,
+        found: DBEncoding,
+    },
+    /// The format header holds encoding `flags` unknown to this version,
+    /// the file was written by a newer format.
+    UnsupportedFormat {
+        flags: u32,
+    },
+    /// An encrypted entry, or the key check or nonce counter of an encrypted
+    /// file when `uid` is `None`, did not authenticate. The data was altered
+    /// or the key is wrong.
+    AuthenticationFailed {
+        uid: Option<u64>,
+    },
+    /// The database is encrypted and no key was set.
+    MissingKey,
+    /// Collection `id` of a catalog was added with an item type of another
+    /// schema id.
+    TypeMismatch {
+        id: u32,
+    },
+    Unsupported,
+    NotFound,
+    AlreadyExists,
+    ReadOnly,
+    /// Another handle holds a conflicting lock on the database file.
+    Locked,
+}
+
+impl DBError {
+    pub fn kind(&self) -> DBErrorKind {
+        match self {
+            DBError::NotFound | DBError::EntryNotFound { .. } => DBErrorKind::NotFound,
+            DBError::AlreadyExists => DBErrorKind::AlreadyExists,
+            DBError::UIDOverflow => DBErrorKind::StorageFull,
+            DBError::DeserializeError { .. }
+            | DBError::UIDDeserializeError(_)
+            | DBError::InvalidData { .. }
+            | DBError::EndOfFileStream
+            | DBError::AuthenticationFailed { .. } => DBErrorKind::Corrupt,
+            DBError::ReadOnly => DBErrorKind::ReadOnly,
+            DBError::Unsupported | DBError::UnsupportedFormat { .. } => DBErrorKind::Unsupported,
+            DBError::SerializeError(_)
+            | DBError::UIDSerializeError
+            | DBError::EntryTooLarge { .. }
+            | DBError::CapacityTooLarge { .. }
+            | DBError::BlockSizeMismatch { .. }
+            | DBError::EncodingMismatch { .. }
+            | DBError::MissingKey
+            | DBError::TypeMismatch { .. } => DBErrorKind::InvalidInput,
+            DBError::MemoryBudgetExceeded { .. } => DBErrorKind::OutOfMemory,
+            DBError::IOError { kind, .. } => *kind,
+            DBError::FailedToRetrieveIterator | DBError::Locked => DBErrorKind::Other,
+        }
+    }
+}
+
+impl Display for DBErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let description: &str = match self {
+            DBErrorKind::NotFound => "not found",
+            DBErrorKind::AlreadyExists => "already exists",
+            DBErrorKind::StorageFull => "storage full",
+            DBErrorKind::Corrupt => "corrupt data",
+            DBErrorKind::ReadOnly => "read-only",
+            DBErrorKind::Unsupported => "unsupported",
+            DBErrorKind::InvalidInput => "invalid input",
+            DBErrorKind::OutOfMemory => "out of memory",
+            DBErrorKind::Other => "other error",
+        };
+        f.write_str(description)
+    }
+}
+
+impl Display for DBError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DBError::SerializeError(error) => write!(f, "failed to serialize entry: {}", error),
+            DBError::DeserializeError { uid, error } => {
+                write!(f, "failed to deserialize entry {}: {}", uid, error)
+            }
+            DBError::UIDSerializeError => f.write_str("failed to serialize UID"),
+            DBError::UIDDeserializeError(error) => {
+                write!(f, "failed to deserialize UID: {}", error)
+            }
+            DBError::InvalidData {
+                offset: Some(offset),
+            } => write!(f, "invalid data at byte {}", offset),
+            DBError::InvalidData { offset: None } => f.write_str("invalid data"),
+            DBError::EntryNotFound { uid: Some(uid) } => write!(f, "entry {} not found", uid),
+            DBError::EntryNotFound { uid: None } => f.write_str("entry not found"),
+            DBError::FailedToRetrieveIterator => f.write_str("failed to retrieve iterator"),
+            DBError::IOError { kind, message } => write!(f, "I/O error ({}): {}", kind, message),
+            DBError::EndOfFileStream => f.write_str("unexpected end of file"),
+            DBError::EntryTooLarge { size, capacity } => write!(
+                f,
+                "entry of {} bytes exceeds the capacity of {} bytes",
+                size, capacity
+            ),
+            DBError::CapacityTooLarge { capacity, limit } => write!(
+                f,
+                "capacity of {} bytes exceeds the limit of {} bytes",
+                capacity, limit
+            ),
+            DBError::UIDOverflow => f.write_str("no UIDs left"),
+            DBError::BlockSizeMismatch { expected, found } => write!(
+                f,
+                "file uses {}-byte blocks, expected {}-byte blocks",
+                found, expected
+            ),
+            DBError::MemoryBudgetExceeded { size, budget } => write!(
+                f,
+                "buffer of {} bytes exceeds the memory budget of {} bytes",
+                size, budget
+            ),
+            DBError::EncodingMismatch { expected, found } => {
+                write!(f, "file is encoded as {}, expected {}", found, expected)
+            }
+            DBError::UnsupportedFormat { flags } => {
+                write!(f, "file uses unknown encoding flags {:#x}", flags)
+            }
+            DBError::AuthenticationFailed { uid: Some(uid) } => {
+                write!(f, "entry {} failed authentication", uid)
+            }
+            DBError::AuthenticationFailed { uid: None } => {
+                f.write_str("key or nonce counter does not match the database")
+            }
+            DBError::MissingKey => f.write_str("database is encrypted and no key was set"),
+            DBError::TypeMismatch { id } => {
+                write!(f, "collection {} holds items of another type", id)
+            }
+            DBError::Unsupported => f.write_str("operation not supported"),
+            DBError::NotFound => f.write_str("database file not found"),
+            DBError::AlreadyExists => f.write_str("database file already exists"),
+            DBError::ReadOnly => f.write_str("database is read-only"),
+            DBError::Locked => f.write_str("database file is locked by another handle"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DBError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DBError::SerializeError(error) => Some(error),
+            DBError::DeserializeError { error, .. } => Some(error),
+            DBError::UIDDeserializeError(error) => Some(error),
+            _ => None,
+        }
+    }
 }
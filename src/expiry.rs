@@ -0,0 +1,163 @@
+extern crate alloc;
+use alloc::collections::BTreeSet;
+
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::db::Database;
+use crate::error::DBError;
+use crate::iterators::DBIterator;
+use crate::structures::DBEntry;
+use crate::traits::ClockBox;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+use core::hash::Hash;
+
+use bincode::Decode;
+use bincode::Encode;
+
+/// Record of an expiring file, `expires` being the clock time the item
+/// expires at, if any.
+#[derive(Encode, Decode, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DBExpiringRecord<T> {
+    pub expires: Option<u64>,
+    pub item: T,
+}
+
+impl<T> DBExpiringRecord<T> {
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires, Some(expires) if expires <= now)
+    }
+}
+
+/// Database of the records of a `DBExpiring`.
+pub type DBExpiringDatabase<'a, T, const CACHE: usize, const BLOCK: usize, C> =
+    Database<'a, BTreeSet<DBExpiringRecord<T>>, CACHE, BLOCK, C>;
+
+/// Entries that expire after a time to live measured by `clock`, stored as
+/// `DBExpiringRecord` entries of `database`. Expired entries are skipped by
+/// iterators and lookups and stay in the file until `purge_expired`. UIDs
+/// are those of the file, so skipping an entry leaves a gap.
+pub struct DBExpiring<
+    'a,
+    T,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    T: Encode + Decode + Hash + Eq,
+    C: DBConfig,
+{
+    pub database: DBExpiringDatabase<'a, T, CACHE, BLOCK, C>,
+    pub clock: ClockBox,
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBExpiring<'a, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq + Clone,
+{
+    pub fn new(database: DBExpiringDatabase<'a, T, CACHE, BLOCK, C>, clock: ClockBox) -> Self {
+        Self { database, clock }
+    }
+
+    /// Adds `item` without an expiry.
+    pub fn add_entry(&mut self, item: &T) -> Result<(), DBError> {
+        let item: T = item.clone();
+        let expires: Option<u64> = None;
+        self.database.add_entry(&DBExpiringRecord { expires, item })
+    }
+
+    /// Adds `item` expiring `ttl` clock units from now.
+    pub fn add_entry_with_ttl(&mut self, item: &T, ttl: u64) -> Result<(), DBError> {
+        let item: T = item.clone();
+        let expires: Option<u64> = Some(self.clock.now().saturating_add(ttl));
+        self.database.add_entry(&DBExpiringRecord { expires, item })
+    }
+
+    /// Iterates over the entries that had not expired when it was created.
+    pub fn get_iterator(&mut self) -> Result<DBExpiringIterator<'_, T, CACHE, BLOCK, C>, DBError> {
+        let now: u64 = self.clock.now();
+        let iterator: DBIterator<'_, BTreeSet<DBExpiringRecord<T>>, CACHE, BLOCK, C> =
+            self.database.get_iterator()?;
+        Ok(DBExpiringIterator::new(iterator, now))
+    }
+
+    /// Returns the entry, or `DBError::EntryNotFound` once it expired.
+    pub fn get_by_uid(&mut self, uid: C::UID) -> Result<DBEntry<T, C::UID>, DBError> {
+        let now: u64 = self.clock.now();
+        let entry: DBEntry<DBExpiringRecord<T>, C::UID> = self.database.get_by_uid(uid)?;
+        if entry.item.is_expired(now) {
+            return Err(DBError::EntryNotFound {
+                uid: Some(uid.into()),
+            });
+        }
+        Ok(DBEntry::new(entry.uid, entry.item.item))
+    }
+
+    /// Drops the expired entries in a single pass and numbers the rest again,
+    /// see `Database::retain_by_uid`. Returns the number of dropped entries.
+    pub fn purge_expired(&mut self) -> Result<usize, DBError> {
+        let now: u64 = self.clock.now();
+        let mut expired: BTreeSet<u64> = BTreeSet::new();
+        for entry in self.database.get_iterator()? {
+            let entry: DBEntry<DBExpiringRecord<T>, C::UID> = entry?;
+            if entry.item.is_expired(now) {
+                expired.insert(entry.uid.into());
+            }
+        }
+        if expired.is_empty() {
+            return Ok(0);
+        }
+        self.database
+            .retain_by_uid(|uid| !expired.contains(&uid.into()))
+    }
+
+    /// Closes the underlying database, see `Database::close`.
+    pub fn close(self) -> Result<(), DBError> {
+        self.database.close()
+    }
+}
+
+/// Iterates over the entries of a `DBExpiring` that had not expired at `now`.
+pub struct DBExpiringIterator<'s, T, const CACHE: usize, const BLOCK: usize, C>
+where
+    T: Encode + Decode + Hash + Eq,
+    C: DBConfig,
+{
+    iterator: DBIterator<'s, BTreeSet<DBExpiringRecord<T>>, CACHE, BLOCK, C>,
+    pub now: u64,
+}
+
+impl<'s, T, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBExpiringIterator<'s, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq,
+{
+    pub fn new(
+        iterator: DBIterator<'s, BTreeSet<DBExpiringRecord<T>>, CACHE, BLOCK, C>,
+        now: u64,
+    ) -> Self {
+        Self { iterator, now }
+    }
+}
+
+impl<'s, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBExpiringIterator<'s, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq,
+{
+    type Item = Result<DBEntry<T, C::UID>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for entry in self.iterator.by_ref() {
+            let entry: DBEntry<DBExpiringRecord<T>, C::UID> = match entry {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+            if !entry.item.is_expired(self.now) {
+                return Some(Ok(DBEntry::new(entry.uid, entry.item.item)));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,300 @@
+use crate::cipher::CIPHER_FIELDS_SIZE;
+use crate::config::DBConfig;
+use crate::config::DBEncoding;
+use crate::config::DBEndian;
+use crate::config::DBIntEncoding;
+use crate::config::DBStandardConfig;
+use crate::error::DBError;
+use crate::traits::FileBox;
+use crate::BLOCK_SIZE;
+
+use core::marker::PhantomData;
+
+pub const FORMAT_MAGIC: [u8; BLOCK_SIZE] = [0xC2, 0xB5, 0x66, 0x62];
+/// Bytes of the format fields, the cipher fields of encrypted files follow.
+pub const FORMAT_FIELDS_SIZE: usize = 6 * BLOCK_SIZE;
+const FIRST_UID_OFFSET: usize = 4 * BLOCK_SIZE;
+const FLAG_BIG_ENDIAN: u32 = 1;
+const FLAG_FIXINT: u32 = 1 << 1;
+const FLAG_WIDE_UID: u32 = 1 << 2;
+const FLAG_COMPACT: u32 = 1 << 3;
+const FLAG_COMPRESSED: u32 = 1 << 4;
+const FLAG_ENCRYPTED: u32 = 1 << 5;
+/// Set by every header holding the first UID, shorter headers lack it.
+const FLAG_FIRST_UID: u32 = 1 << 6;
+const FLAGS_KNOWN: u32 = FLAG_BIG_ENDIAN
+    | FLAG_FIXINT
+    | FLAG_WIDE_UID
+    | FLAG_COMPACT
+    | FLAG_COMPRESSED
+    | FLAG_ENCRYPTED
+    | FLAG_FIRST_UID;
+
+/// Block size and encoding a file was written with.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DBFormat {
+    pub block_size: usize,
+    pub encoding: DBEncoding,
+}
+
+/// Format header for blocks of `B` bytes and entries encoded with `C`.
+///
+/// Files with a block size other than the default, or with an encoding
+/// other than `DBStandardConfig`, start with a header holding the format
+/// magic, the block size, the encoding flags and the decode limit, each as a
+/// Little Endian `u32`, and the UID of the first entry as a Little Endian
+/// `u64`, padded to a whole block. Files with the defaults have no header,
+/// which keeps them readable by earlier versions and by `HeaplessDatabase`,
+/// until `write_first_uid` records a first UID other than 0 in one.
+pub struct DBFormatHeader<const B: usize, C = DBStandardConfig> {
+    marker: PhantomData<C>,
+}
+
+impl<const B: usize, C: DBConfig> DBFormatHeader<B, C> {
+    /// Bytes taken by the header, entries and the ring header follow it.
+    pub const SIZE: usize = {
+        assert!(
+            B >= BLOCK_SIZE && B.is_multiple_of(BLOCK_SIZE),
+            "block size must be a non-zero multiple of 4"
+        );
+        assert!(
+            B >= C::ENCODING.uid_size,
+            "block size must be at least the UID size"
+        );
+        if B == BLOCK_SIZE && C::ENCODING.is_standard() {
+            0
+        } else if C::ENCODING.encrypted {
+            (FORMAT_FIELDS_SIZE + CIPHER_FIELDS_SIZE).next_multiple_of(B)
+        } else {
+            FORMAT_FIELDS_SIZE.next_multiple_of(B)
+        }
+    };
+
+    fn read_fully(file: &mut FileBox, buffer: &mut [u8]) -> Result<usize, DBError> {
+        let mut length: usize = 0;
+        while length < buffer.len() {
+            let read: usize = file.read(&mut buffer[length..])?;
+            if read == 0 {
+                break;
+            }
+            length += read;
+        }
+        Ok(length)
+    }
+
+    /// Bytes taken by the header of a file starting with `head`.
+    fn header_size(head: &[u8]) -> usize {
+        match Self::SIZE {
+            0 if head.len() >= FORMAT_FIELDS_SIZE && head[..BLOCK_SIZE] == FORMAT_MAGIC => {
+                FORMAT_FIELDS_SIZE
+            }
+            size => size,
+        }
+    }
+
+    fn write_header(file: &mut FileBox, size: usize, first_uid: u64) -> Result<(), DBError> {
+        let encoding: DBEncoding = C::ENCODING;
+        let limit: u32 = u32::try_from(encoding.limit).unwrap_or(u32::MAX);
+        let fields: [u32; 3] = [B as u32, Self::encode_flags(&encoding), limit];
+
+        let mut buffer: [u8; FORMAT_FIELDS_SIZE] = [0; FORMAT_FIELDS_SIZE];
+        buffer[..BLOCK_SIZE].copy_from_slice(&FORMAT_MAGIC);
+        for (idx, field) in fields.iter().enumerate() {
+            let start: usize = (idx + 1) * BLOCK_SIZE;
+            buffer[start..start + BLOCK_SIZE].copy_from_slice(&field.to_le_bytes());
+        }
+        buffer[FIRST_UID_OFFSET..].copy_from_slice(&first_uid.to_le_bytes());
+
+        file.seek(0)?;
+        file.write_all(&buffer)?;
+        for _ in 0..(size - FORMAT_FIELDS_SIZE) / BLOCK_SIZE {
+            file.write_all(&[0; BLOCK_SIZE])?;
+        }
+        Ok(())
+    }
+
+    fn read_field(buffer: &[u8; FORMAT_FIELDS_SIZE], idx: usize) -> u32 {
+        let mut field: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        field.copy_from_slice(&buffer[idx * BLOCK_SIZE..(idx + 1) * BLOCK_SIZE]);
+        u32::from_le_bytes(field)
+    }
+
+    /// Fails with `DBError::UnsupportedFormat` for flags this version does
+    /// not know, written by a newer format, and for headers written before
+    /// the first UID was recorded.
+    fn decode_encoding(flags: u32, limit: u32) -> Result<DBEncoding, DBError> {
+        if flags & !FLAGS_KNOWN != 0 || flags & FLAG_FIRST_UID == 0 {
+            return Err(DBError::UnsupportedFormat { flags });
+        }
+
+        let endian: DBEndian = match flags & FLAG_BIG_ENDIAN {
+            0 => DBEndian::Little,
+            _ => DBEndian::Big,
+        };
+        let int_encoding: DBIntEncoding = match flags & FLAG_FIXINT {
+            0 => DBIntEncoding::Varint,
+            _ => DBIntEncoding::Fixint,
+        };
+        let limit: usize = limit as usize;
+        let uid_size: usize = match flags & FLAG_WIDE_UID {
+            0 => 4,
+            _ => 8,
+        };
+        let compact: bool = flags & FLAG_COMPACT != 0;
+        let compressed: bool = flags & FLAG_COMPRESSED != 0;
+        let encrypted: bool = flags & FLAG_ENCRYPTED != 0;
+        Ok(DBEncoding {
+            endian,
+            int_encoding,
+            limit,
+            uid_size,
+            compact,
+            compressed,
+            encrypted,
+        })
+    }
+
+    fn encode_flags(encoding: &DBEncoding) -> u32 {
+        let mut flags: u32 = FLAG_FIRST_UID;
+        if encoding.endian == DBEndian::Big {
+            flags |= FLAG_BIG_ENDIAN;
+        }
+        if encoding.int_encoding == DBIntEncoding::Fixint {
+            flags |= FLAG_FIXINT;
+        }
+        if encoding.uid_size == 8 {
+            flags |= FLAG_WIDE_UID;
+        }
+        if encoding.compact {
+            flags |= FLAG_COMPACT;
+        }
+        if encoding.compressed {
+            flags |= FLAG_COMPRESSED;
+        }
+        if encoding.encrypted {
+            flags |= FLAG_ENCRYPTED;
+        }
+        flags
+    }
+}
+
+impl<const B: usize, C: DBConfig> DBFormatHeader<B, C> {
+    /// Returns the format recorded in the file, the default format for a
+    /// file without a header, and `None` for an empty file. Headers with
+    /// unknown encoding flags fail with `DBError::UnsupportedFormat`.
+    pub fn read(file: &mut FileBox) -> Result<Option<DBFormat>, DBError> {
+        let mut buffer: [u8; FORMAT_FIELDS_SIZE] = [0; FORMAT_FIELDS_SIZE];
+        file.seek(0)?;
+        let length: usize = Self::read_fully(file, &mut buffer)?;
+
+        if length == 0 {
+            return Ok(None);
+        }
+        if length < FORMAT_FIELDS_SIZE || buffer[..BLOCK_SIZE] != FORMAT_MAGIC {
+            let block_size: usize = BLOCK_SIZE;
+            let encoding: DBEncoding = DBEncoding::STANDARD;
+            return Ok(Some(DBFormat {
+                block_size,
+                encoding,
+            }));
+        }
+
+        let block_size: usize = Self::read_field(&buffer, 1) as usize;
+        let flags: u32 = Self::read_field(&buffer, 2);
+        let limit: u32 = Self::read_field(&buffer, 3);
+        let encoding: DBEncoding = Self::decode_encoding(flags, limit)?;
+        Ok(Some(DBFormat {
+            block_size,
+            encoding,
+        }))
+    }
+
+    pub fn write(file: &mut FileBox) -> Result<(), DBError> {
+        if Self::SIZE == 0 {
+            return Ok(());
+        }
+        Self::write_header(file, Self::SIZE, 0)
+    }
+
+    /// Returns the byte offset of the first chunk, after the header. Only
+    /// files with the defaults may have no header.
+    pub fn start(file: &mut FileBox) -> Result<usize, DBError> {
+        if Self::SIZE != 0 {
+            return Ok(Self::SIZE);
+        }
+
+        let mut buffer: [u8; FORMAT_FIELDS_SIZE] = [0; FORMAT_FIELDS_SIZE];
+        file.seek(0)?;
+        let length: usize = Self::read_fully(file, &mut buffer)?;
+        Ok(Self::header_size(&buffer[..length]))
+    }
+
+    /// Returns the byte offset of the first chunk of a file held in `buffer`,
+    /// see `start`.
+    pub fn slice_start(buffer: &[u8]) -> usize {
+        Self::header_size(buffer)
+    }
+
+    /// Returns the UID the first entry of the file takes, 0 for a file
+    /// without a header.
+    pub fn read_first_uid(file: &mut FileBox) -> Result<u64, DBError> {
+        if Self::start(file)? == 0 {
+            return Ok(0);
+        }
+
+        let mut buffer: [u8; 8] = [0; 8];
+        file.seek(FIRST_UID_OFFSET)?;
+        match Self::read_fully(file, &mut buffer)? {
+            8 => Ok(u64::from_le_bytes(buffer)),
+            _ => Err(DBError::InvalidData {
+                offset: Some(FIRST_UID_OFFSET),
+            }),
+        }
+    }
+
+    /// Records `uid` as the UID of the first entry. A file without a header
+    /// must be empty, it gets one unless `uid` is 0.
+    pub fn write_first_uid(file: &mut FileBox, uid: u64) -> Result<(), DBError> {
+        if Self::start(file)? == 0 {
+            return match uid {
+                0 => Ok(()),
+                _ => Self::write_header(file, FORMAT_FIELDS_SIZE, uid),
+            };
+        }
+
+        file.seek(FIRST_UID_OFFSET)?;
+        file.write_all(&uid.to_le_bytes())
+    }
+
+    /// Fails with `DBError::BlockSizeMismatch` or `DBError::EncodingMismatch`
+    /// when the file was written with another format. An empty file gets a
+    /// header when it is `writable`.
+    pub fn check(file: &mut FileBox, writable: bool) -> Result<(), DBError> {
+        let format: DBFormat = match Self::read(file)? {
+            Some(format) => format,
+            None if writable => return Self::write(file),
+            None => return Ok(()),
+        };
+
+        if format.block_size != B {
+            let found: usize = format.block_size;
+            return Err(DBError::BlockSizeMismatch { expected: B, found });
+        }
+
+        let expected: DBEncoding = C::ENCODING;
+        let found: DBEncoding = format.encoding;
+        // A limit above the `u32` range is recorded as `u32::MAX`
+        let limit: usize = expected.limit.min(u32::MAX as usize);
+        if found.endian != expected.endian
+            || found.int_encoding != expected.int_encoding
+            || found.limit != limit
+            || found.uid_size != expected.uid_size
+            || found.compact != expected.compact
+            || found.compressed != expected.compressed
+            || found.encrypted != expected.encrypted
+        {
+            return Err(DBError::EncodingMismatch { expected, found });
+        }
+        Ok(())
+    }
+}
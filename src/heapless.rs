@@ -0,0 +1,381 @@
+use crate::error::DBError;
+use crate::error::DBErrorKind;
+use crate::error::DBErrorMessage;
+use crate::structures::DBEntry;
+use crate::traits::StorageTrait;
+use crate::BLOCK_SIZE;
+use crate::DECODE_LIMIT;
+use crate::EOE_BLOCK;
+
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use bincode::config::standard;
+use bincode::config::*;
+use bincode::decode_from_slice;
+use bincode::encode_into_slice;
+use bincode::error::DecodeError;
+use bincode::error::EncodeError;
+use bincode::Decode;
+use bincode::Encode;
+
+/// Storage over a caller-provided buffer, such as a `static` array. The
+/// database can never grow past the end of the buffer.
+pub struct DBSliceStorage<'a> {
+    buffer: &'a mut [u8],
+    length: usize,
+    position: usize,
+}
+
+impl<'a> DBSliceStorage<'a> {
+    /// `length` is the number of bytes at the start of `buffer` that already
+    /// hold database contents, zero for a new database.
+    pub fn new(buffer: &'a mut [u8], length: usize) -> Self {
+        let length: usize = length.min(buffer.len());
+        let position: usize = 0;
+        Self {
+            buffer,
+            length,
+            position,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.length]
+    }
+
+    fn storage_full(&self) -> DBError {
+        let kind: DBErrorKind = DBErrorKind::StorageFull;
+        let message: DBErrorMessage = DBErrorMessage::from("storage buffer is full");
+        DBError::IOError { kind, message }
+    }
+}
+
+impl<'a> StorageTrait for DBSliceStorage<'a> {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, DBError> {
+        let start: usize = self.position.min(self.length);
+        let end: usize = start.saturating_add(buffer.len()).min(self.length);
+        let length: usize = end - start;
+
+        buffer[..length].copy_from_slice(&self.buffer[start..end]);
+        self.position = end;
+        Ok(length)
+    }
+
+    fn write_all(&mut self, buffer: &[u8]) -> Result<(), DBError> {
+        let end: usize = self.position.saturating_add(buffer.len());
+        if end > self.buffer.len() {
+            return Err(self.storage_full());
+        }
+
+        self.buffer[self.position..end].copy_from_slice(buffer);
+        self.position = end;
+        self.length = self.length.max(end);
+        Ok(())
+    }
+
+    fn seek(&mut self, position: usize) -> Result<usize, DBError> {
+        self.position = position;
+        Ok(position)
+    }
+
+    fn set_len(&mut self, size: usize) -> Result<(), DBError> {
+        if size > self.buffer.len() {
+            return Err(self.storage_full());
+        }
+        if size > self.length {
+            self.buffer[self.length..size].fill(0);
+        }
+        self.length = size;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), DBError> {
+        Ok(())
+    }
+}
+
+/// Location of a complete chunk, and how many payload bytes it holds.
+struct DBChunkBounds {
+    uid_block: [u8; BLOCK_SIZE],
+    payload: usize,
+    length: usize,
+}
+
+/// Database that never allocates. It owns its storage directly and decodes
+/// every chunk into a buffer provided by the caller, so it works on targets
+/// without an allocator. Files are compatible with `Database`.
+pub struct HeaplessDatabase<S, T>
+where
+    S: StorageTrait,
+    T: Encode + Decode + Hash + Eq,
+{
+    pub storage: S,
+    /// Byte offset after the last complete chunk.
+    pub end: usize,
+    /// `None` once the UID range is exhausted.
+    pub next_uid: Option<u32>,
+    /// Set when bytes of an incomplete chunk follow `end`, they are cut off
+    /// before the next write.
+    pub torn: bool,
+    pub marker: PhantomData<T>,
+}
+
+impl<S, T> HeaplessDatabase<S, T>
+where
+    S: StorageTrait,
+    T: Encode + Decode + Hash + Eq,
+{
+    fn read_block(storage: &mut S) -> Result<Option<[u8; BLOCK_SIZE]>, DBError> {
+        let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        let mut length: usize = 0;
+        while length < BLOCK_SIZE {
+            let read: usize = storage.read(&mut block[length..])?;
+            if read == 0 {
+                return Ok(None);
+            }
+            length += read;
+        }
+        Ok(Some(block))
+    }
+
+    /// Copies the payload of the chunk at `position` into `buffer`, as far as
+    /// it fits. Returns `None` when no complete chunk starts at `position`.
+    fn read_chunk(
+        storage: &mut S,
+        position: usize,
+        buffer: &mut [u8],
+    ) -> Result<Option<DBChunkBounds>, DBError> {
+        storage.seek(position)?;
+        let uid_block: [u8; BLOCK_SIZE] = match Self::read_block(storage)? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+
+        let mut payload: usize = 0;
+        loop {
+            let block: [u8; BLOCK_SIZE] = match Self::read_block(storage)? {
+                Some(block) => block,
+                None => return Ok(None),
+            };
+            if block == EOE_BLOCK {
+                break;
+            }
+
+            if let Some(target) = buffer.get_mut(payload..payload + BLOCK_SIZE) {
+                target.copy_from_slice(&block);
+            } else if let Some(target) = buffer.get_mut(payload..) {
+                // Only padding can be cut off, decoding finds out otherwise
+                let length: usize = target.len();
+                target.copy_from_slice(&block[..length]);
+            }
+            payload += BLOCK_SIZE;
+        }
+
+        let length: usize = payload + 2 * BLOCK_SIZE;
+        Ok(Some(DBChunkBounds {
+            uid_block,
+            payload,
+            length,
+        }))
+    }
+
+    fn decode_chunk(bounds: &DBChunkBounds, buffer: &[u8]) -> Result<DBEntry<T>, DBError> {
+        // Padding may be missing from a buffer cut to the exact payload size
+        if bounds.payload > buffer.len() + BLOCK_SIZE - 1 {
+            let size: usize = bounds.payload;
+            let capacity: usize = buffer.len();
+            return Err(DBError::EntryTooLarge { size, capacity });
+        }
+
+        let uid: u32 = u32::from_le_bytes(bounds.uid_block);
+        let length: usize = bounds.payload.min(buffer.len());
+        let config: Configuration<LittleEndian, Varint, Limit<DECODE_LIMIT>> =
+            standard().with_limit::<DECODE_LIMIT>();
+        let item: Result<(T, usize), DecodeError> = decode_from_slice(&buffer[..length], config);
+        match item {
+            Ok((item, _)) => Ok(DBEntry::new(uid, item)),
+            Err(error) => Err(DBError::DeserializeError {
+                uid: uid.into(),
+                error,
+            }),
+        }
+    }
+
+    fn write_chunk(&mut self, uid: u32, payload: &[u8]) -> Result<usize, DBError> {
+        if self.torn {
+            self.storage.set_len(self.end)?;
+            self.torn = false;
+        }
+
+        let full: usize = payload.len() - payload.len() % BLOCK_SIZE;
+        self.storage.seek(self.end)?;
+        self.storage.write_all(&uid.to_le_bytes())?;
+        self.storage.write_all(&payload[..full])?;
+
+        let mut length: usize = full;
+        if full < payload.len() {
+            let mut block: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+            block[..payload.len() - full].copy_from_slice(&payload[full..]);
+            self.storage.write_all(&block)?;
+            length += BLOCK_SIZE;
+        }
+
+        self.storage.write_all(&EOE_BLOCK)?;
+        self.storage.flush()?;
+        Ok(length + 2 * BLOCK_SIZE)
+    }
+}
+
+impl<S, T> HeaplessDatabase<S, T>
+where
+    S: StorageTrait,
+    T: Encode + Decode + Hash + Eq,
+{
+    /// Scans the storage to find where the next chunk goes and which UID it
+    /// gets. An incomplete chunk at the end is treated as a torn write.
+    pub fn open(mut storage: S) -> Result<Self, DBError> {
+        let mut end: usize = 0;
+        let mut last_uid: Option<u32> = None;
+        while let Some(bounds) = Self::read_chunk(&mut storage, end, &mut [])? {
+            last_uid = Some(u32::from_le_bytes(bounds.uid_block));
+            end += bounds.length;
+        }
+
+        storage.seek(end)?;
+        let torn: bool = storage.read(&mut [0; 1])? > 0;
+        let next_uid: Option<u32> = match last_uid {
+            Some(uid) => uid.checked_add(1),
+            None => Some(0),
+        };
+        Ok(Self {
+            storage,
+            end,
+            next_uid,
+            torn,
+            marker: PhantomData,
+        })
+    }
+
+    /// Appends an entry, encoding it into `buffer` first. Returns its UID.
+    pub fn add_entry(&mut self, item: &T, buffer: &mut [u8]) -> Result<u32, DBError> {
+        let uid: u32 = self.next_uid.ok_or(DBError::UIDOverflow)?;
+        let config: Configuration = standard();
+        let length: Result<usize, EncodeError> = encode_into_slice(item, &mut *buffer, config);
+        let length: usize = match length {
+            Ok(length) => length,
+            Err(error) => return Err(DBError::SerializeError(error)),
+        };
+
+        let result: Result<usize, DBError> = self.write_chunk(uid, &buffer[..length]);
+        match result {
+            Ok(length) => {
+                self.end += length;
+                self.next_uid = uid.checked_add(1);
+                Ok(uid)
+            }
+            Err(error) => {
+                self.torn = true;
+                Err(error)
+            }
+        }
+    }
+
+    /// Iterates over the entries, decoding each one into `buffer`.
+    pub fn get_iterator<'b>(&'b mut self, buffer: &'b mut [u8]) -> DBHeaplessIterator<'b, S, T> {
+        DBHeaplessIterator::new(self, buffer)
+    }
+
+    pub fn get_by_uid(&mut self, uid: u32, buffer: &mut [u8]) -> Result<DBEntry<T>, DBError> {
+        for entry in self.get_iterator(buffer).flatten() {
+            if entry.uid == uid {
+                return Ok(entry);
+            }
+        }
+
+        Err(DBError::EntryNotFound {
+            uid: Some(uid.into()),
+        })
+    }
+
+    pub fn contains(&mut self, item: &T, buffer: &mut [u8]) -> Result<bool, DBError> {
+        for entry in self.get_iterator(buffer).flatten() {
+            if &entry.item == item {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Flushes and hands back the storage.
+    pub fn into_storage(mut self) -> Result<S, DBError> {
+        self.storage.flush()?;
+        Ok(self.storage)
+    }
+}
+
+pub struct DBHeaplessIterator<'b, S, T>
+where
+    S: StorageTrait,
+    T: Encode + Decode + Hash + Eq,
+{
+    db: &'b mut HeaplessDatabase<S, T>,
+    buffer: &'b mut [u8],
+    position: usize,
+}
+
+impl<'b, S, T> DBHeaplessIterator<'b, S, T>
+where
+    S: StorageTrait,
+    T: Encode + Decode + Hash + Eq,
+{
+    pub fn new(db: &'b mut HeaplessDatabase<S, T>, buffer: &'b mut [u8]) -> Self {
+        let position: usize = 0;
+        Self {
+            db,
+            buffer,
+            position,
+        }
+    }
+}
+
+impl<'b, S, T> Iterator for DBHeaplessIterator<'b, S, T>
+where
+    S: StorageTrait,
+    T: Encode + Decode + Hash + Eq,
+{
+    type Item = Result<DBEntry<T>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.db.end {
+            return None;
+        }
+
+        let storage: &mut S = &mut self.db.storage;
+        let bounds: Result<Option<DBChunkBounds>, DBError> =
+            HeaplessDatabase::<S, T>::read_chunk(storage, self.position, self.buffer);
+        match bounds {
+            Ok(Some(bounds)) => {
+                self.position += bounds.length;
+                Some(HeaplessDatabase::<S, T>::decode_chunk(&bounds, self.buffer))
+            }
+            Ok(None) => {
+                self.position = self.db.end;
+                None
+            }
+            Err(error) => {
+                self.position = self.db.end;
+                Some(Err(error))
+            }
+        }
+    }
+}
@@ -1,17 +1,25 @@
 use crate::error::DBError;
+use crate::error::DBErrorKind;
 use crate::traits::CPathBox;
 use crate::traits::CPathTrait;
+use crate::traits::ClockBox;
+use crate::traits::ClockTrait;
 use crate::traits::FileTrait;
 use crate::traits::OpenFileBox;
 use crate::traits::OpenFileTrait;
+use crate::traits::StorageTrait;
 
 use std::fs::File;
 use std::fs::OpenOptions;
+use std::fs::TryLockError;
 use std::io::prelude::*;
 use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 impl FileTrait for File {
     fn read(&mut self, buffer: &mut [u8]) -> Result<usize, DBError> {
@@ -67,12 +75,79 @@ impl FileTrait for File {
     fn close(self: Box<Self>) -> Result<(), DBError> {
         Ok(())
     }
+
+    fn sync(&mut self) -> Result<(), DBError> {
+        let result: Result<(), Error> = self.sync_all();
+        if result.is_ok() {
+            return Ok(());
+        }
+
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
 }
 
-impl Into<DBError> for std::io::Error {
-    fn into(self) -> DBError {
-        let string: String = self.to_string();
-        DBError::IOError(string)
+impl StorageTrait for File {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, DBError> {
+        <Self as FileTrait>::read(self, buffer)
+    }
+
+    fn write_all(&mut self, buffer: &[u8]) -> Result<(), DBError> {
+        <Self as FileTrait>::write_all(self, buffer)
+    }
+
+    fn seek(&mut self, position: usize) -> Result<usize, DBError> {
+        <Self as FileTrait>::seek(self, position)
+    }
+
+    fn set_len(&mut self, size: usize) -> Result<(), DBError> {
+        <Self as FileTrait>::set_len(self, size)
+    }
+
+    fn flush(&mut self) -> Result<(), DBError> {
+        let result: Result<(), Error> = <Self as Write>::flush(self);
+        if result.is_ok() {
+            return Ok(());
+        }
+
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
+}
+
+impl From<std::io::Error> for DBError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            ErrorKind::NotFound => return DBError::NotFound,
+            ErrorKind::AlreadyExists => return DBError::AlreadyExists,
+            _ => {}
+        }
+        let kind: DBErrorKind = match error.kind() {
+            ErrorKind::StorageFull | ErrorKind::WriteZero => DBErrorKind::StorageFull,
+            ErrorKind::OutOfMemory => DBErrorKind::OutOfMemory,
+            ErrorKind::InvalidData | ErrorKind::UnexpectedEof => DBErrorKind::Corrupt,
+            ErrorKind::PermissionDenied | ErrorKind::ReadOnlyFilesystem => DBErrorKind::ReadOnly,
+            ErrorKind::Unsupported => DBErrorKind::Unsupported,
+            ErrorKind::InvalidInput => DBErrorKind::InvalidInput,
+            _ => DBErrorKind::Other,
+        };
+        let message: String = error.to_string();
+        DBError::IOError { kind, message }
+    }
+}
+
+/// Takes an advisory lock on `file` until it is closed, exclusive or
+/// shared, failing with `DBError::Locked` while another handle holds a
+/// conflicting lock.
+pub fn lock_file(file: &File, exclusive: bool) -> Result<(), DBError> {
+    let result: Result<(), TryLockError> = match exclusive {
+        true => file.try_lock(),
+        false => file.try_lock_shared(),
+    };
+    match result {
+        Ok(()) => Ok(()),
+        Err(TryLockError::WouldBlock) => Err(DBError::Locked),
+        Err(TryLockError::Error(error)) => Err(error.into()),
     }
 }
 
@@ -151,6 +226,22 @@ impl OpenFileTrait for OpenFile {
         let db_error: DBError = result.unwrap_err().into();
         Err(db_error)
     }
+    fn remove(&self, path: &dyn CPathTrait) -> Result<(), DBError> {
+        let result: Result<(), Error> = std::fs::remove_file(path.as_str());
+        if result.is_ok() {
+            return Ok(());
+        }
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
+    fn rename(&self, from: &dyn CPathTrait, to: &dyn CPathTrait) -> Result<(), DBError> {
+        let result: Result<(), Error> = std::fs::rename(from.as_str(), to.as_str());
+        if result.is_ok() {
+            return Ok(());
+        }
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
 }
 
 #[derive(Clone)]
@@ -174,3 +265,23 @@ impl CPathTrait for CPath {
         Box::new(self.clone())
     }
 }
+
+/// Clock reading milliseconds since the Unix epoch from the system time.
+#[derive(Clone)]
+pub struct SystemClock;
+
+impl SystemClock {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> ClockBox {
+        Box::new(SystemClock)
+    }
+}
+
+impl ClockTrait for SystemClock {
+    fn now(&self) -> u64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_millis() as u64,
+            Err(_) => 0,
+        }
+    }
+}
@@ -0,0 +1,407 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::capped;
+use crate::cipher;
+use crate::config;
+use crate::error;
+use crate::memory;
+use crate::serializer;
+use crate::stream;
+use crate::structures;
+use crate::traits;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+use capped::DBRingCursor;
+use cipher::DBCipher;
+use config::DBConfig;
+use config::DBStandardConfig;
+use error::DBError;
+use memory::DBMemory;
+use serializer::DBSerializer;
+use stream::DBFileStream;
+use stream::DBSliceStream;
+use structures::DBEntry;
+use structures::DBItemEntry;
+use traits::FileBox;
+
+use core::hash::Hash;
+
+use bincode::Decode;
+use bincode::Encode;
+
+fn split_mapped(file: &mut FileBox) -> Result<&[u8], &mut FileBox> {
+    if file.as_bytes().is_none() {
+        return Err(file);
+    }
+    let file: &FileBox = file;
+    Ok(file.as_bytes().unwrap_or_default())
+}
+
+// The stream keeps its cache inline, boxing it would move the cache to the heap.
+#[allow(clippy::large_enum_variant)]
+enum DBChunkSource<'a, const CACHE: usize, const BLOCK: usize, C> {
+    Stream(DBFileStream<'a, CACHE, BLOCK, C>, Option<DBRingCursor>),
+    Slice(DBSliceIterator<'a, BLOCK, C>),
+}
+
+pub struct DBIterator<
+    'a,
+    T,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    source: DBChunkSource<'a, CACHE, BLOCK, C>,
+    serializer: DBSerializer<'a, T, BLOCK, C>,
+    memory: Option<&'a DBMemory>,
+    // UID of the last slice, compact chunks count from it
+    base: u64,
+    // Set once a chunk could not be read, nothing after it can be found
+    done: bool,
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBIterator<'a, T, CACHE, BLOCK, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    fn from_parts(
+        source: DBChunkSource<'a, CACHE, BLOCK, C>,
+        serializer: DBSerializer<'a, T, BLOCK, C>,
+    ) -> Self {
+        let memory: Option<&DBMemory> = None;
+        let base: u64 = 0;
+        let done: bool = false;
+        Self {
+            source,
+            serializer,
+            memory,
+            base,
+            done,
+        }
+    }
+
+    fn from_source(source: DBChunkSource<'a, CACHE, BLOCK, C>) -> Self {
+        Self::from_parts(source, DBSerializer::new())
+    }
+
+    fn deserialize_slice(&mut self, chunk: &[u8]) -> Result<DBEntry<T::Item, C::UID>, DBError> {
+        let base: u64 = self.base;
+        if let Ok(uid) = self.serializer.chunk_uid(chunk, base) {
+            self.base = uid.into();
+        }
+
+        if let Some(memory) = self.memory {
+            memory.claim(chunk.len())?;
+        }
+        let (entry, _) = self.serializer.deserialize_with_remainder(chunk, base)?;
+        Ok(entry)
+    }
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBIterator<'a, T, CACHE, BLOCK, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    pub fn new(
+        stream: DBFileStream<'a, CACHE, BLOCK, C>,
+        serializer: DBSerializer<'a, T, BLOCK, C>,
+    ) -> Self {
+        let source: DBChunkSource<'_, CACHE, BLOCK, C> = DBChunkSource::Stream(stream, None);
+        Self::from_parts(source, serializer)
+    }
+
+    /// Reads chunks as borrowed slices when the file exposes its contents
+    /// in memory, and through a cached stream otherwise.
+    pub fn from_file(file: &'a mut FileBox) -> Result<Self, DBError> {
+        match split_mapped(file) {
+            Ok(buffer) => Ok(Self::from_slice(buffer)),
+            Err(file) => {
+                let stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+                Ok(Self::from_source(DBChunkSource::Stream(stream, None)))
+            }
+        }
+    }
+
+    pub fn from_ring(file: &'a mut FileBox, ring: DBRingCursor) -> Self {
+        match split_mapped(file) {
+            Ok(buffer) => {
+                let slices: DBSliceIterator<'_, BLOCK, C> =
+                    DBSliceIterator::from_ring(buffer, ring);
+                Self::from_source(DBChunkSource::Slice(slices))
+            }
+            Err(file) => {
+                let stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::new(file);
+                Self::from_source(DBChunkSource::Stream(stream, Some(ring)))
+            }
+        }
+    }
+
+    pub fn from_slice(buffer: &'a [u8]) -> Self {
+        let slices: DBSliceIterator<'_, BLOCK, C> = DBSliceIterator::from_slice(buffer);
+        Self::from_source(DBChunkSource::Slice(slices))
+    }
+
+    /// Checks decoded entries against `memory` and records them.
+    pub fn with_memory(mut self, memory: &'a DBMemory) -> Self {
+        if let DBChunkSource::Stream(stream, _) = &mut self.source {
+            stream.set_memory(memory);
+        }
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Opens the sealed entries of an encrypted database with `cipher`.
+    pub fn with_cipher(mut self, cipher: Option<DBCipher>) -> Self {
+        self.serializer.cipher = cipher;
+        self
+    }
+
+    /// Byte offset of the next chunk.
+    pub fn position(&self) -> usize {
+        match &self.source {
+            DBChunkSource::Stream(stream, _) => stream.get_position(),
+            DBChunkSource::Slice(slices) => slices.position(),
+        }
+    }
+
+    /// Continues from the chunk at byte offset `position`, `base` being the
+    /// UID of the chunk before it. Offsets come from `position`, and capped
+    /// databases return `DBError::Unsupported`.
+    pub fn seek(&mut self, position: usize, base: u64) -> Result<(), DBError> {
+        match &mut self.source {
+            DBChunkSource::Stream(_, Some(_)) => return Err(DBError::Unsupported),
+            DBChunkSource::Stream(stream, None) => stream.seek_chunk(position, base)?,
+            DBChunkSource::Slice(slices) => slices.seek(position)?,
+        };
+        self.base = base;
+        self.done = false;
+        Ok(())
+    }
+
+    /// Whether the chunk at byte offset `position` is the last one in the
+    /// file, moving past it. Capped databases return `DBError::Unsupported`.
+    pub fn is_last(&mut self, position: usize) -> Result<bool, DBError> {
+        match &mut self.source {
+            DBChunkSource::Stream(_, Some(_)) => Err(DBError::Unsupported),
+            DBChunkSource::Stream(stream, None) => {
+                stream.seek(position)?;
+                Ok(stream.pass_chunk()? && !stream.pass_chunk()?)
+            }
+            DBChunkSource::Slice(slices) => {
+                slices.seek(position)?;
+                Ok(slices.try_next()?.is_some() && slices.try_next()?.is_none())
+            }
+        }
+    }
+
+    /// Like `next`, but only ends at the end of the file. A chunk the file
+    /// ends inside fails with `DBError::EndOfFileStream`.
+    pub fn try_next(&mut self) -> Result<Option<DBItemEntry<T, C>>, DBError> {
+        let (stream, ring) = match &mut self.source {
+            DBChunkSource::Stream(stream, ring) => (stream, ring),
+            DBChunkSource::Slice(slices) => {
+                let chunk: Option<&[u8]> = slices.try_next()?;
+                return chunk.map(|chunk| self.deserialize_slice(chunk)).transpose();
+            }
+        };
+
+        if let Some(ring) = ring {
+            return ring.next_entry(stream, &self.serializer).transpose();
+        }
+
+        let position: usize = stream.get_position();
+        match self.serializer.deserialize_from_stream(stream) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(DBError::EndOfFileStream) => {
+                // The file is complete when no chunk starts where it ended
+                stream.seek(position)?;
+                match stream.pass_chunk()? {
+                    false => Ok(None),
+                    true => Err(DBError::EndOfFileStream),
+                }
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBIterator<'a, T, CACHE, BLOCK, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    type Item = Result<DBEntry<T::Item, C::UID>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (stream, ring) = match &mut self.source {
+            DBChunkSource::Stream(stream, ring) => (stream, ring),
+            DBChunkSource::Slice(slices) => {
+                let chunk: Result<&[u8], DBError> = slices.next()?;
+                return Some(chunk.and_then(|chunk| self.deserialize_slice(chunk)));
+            }
+        };
+
+        if let Some(ring) = ring {
+            return ring.next_entry(stream, &self.serializer);
+        }
+
+        if self.done {
+            return None;
+        }
+
+        let entry: Result<DBEntry<T::Item, C::UID>, DBError> =
+            self.serializer.deserialize_from_stream(stream);
+        match entry {
+            Err(DBError::EndOfFileStream) => return None,
+            // The chunk could not be delimited, like the slices end there
+            Err(DBError::InvalidData { .. } | DBError::IOError { .. }) => self.done = true,
+            _ => {}
+        }
+        Some(entry)
+    }
+}
+
+pub struct DBChunkIterator<
+    'a,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> {
+    stream: DBFileStream<'a, CACHE, BLOCK, C>,
+    ring: Option<DBRingCursor>,
+    // Set once a chunk could not be read, nothing after it can be found
+    done: bool,
+}
+
+impl<'a, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBChunkIterator<'a, CACHE, BLOCK, C> {
+    pub fn from_file(file: &'a mut FileBox) -> Result<Self, DBError> {
+        let stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::open(file)?;
+        let ring: Option<DBRingCursor> = None;
+        let done: bool = false;
+        Ok(Self { stream, ring, done })
+    }
+
+    pub fn from_ring(file: &'a mut FileBox, ring: DBRingCursor) -> Self {
+        let stream: DBFileStream<CACHE, BLOCK, C> = DBFileStream::new(file);
+        let ring: Option<DBRingCursor> = Some(ring);
+        let done: bool = false;
+        Self { stream, ring, done }
+    }
+
+    /// Checks buffered chunks against `memory` and records them.
+    pub fn with_memory(mut self, memory: &'a DBMemory) -> Self {
+        self.stream.set_memory(memory);
+        self
+    }
+}
+
+impl<'a, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBChunkIterator<'a, CACHE, BLOCK, C>
+{
+    type Item = Result<Vec<u8>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ring) = &mut self.ring {
+            return ring.next_chunk(&mut self.stream);
+        }
+
+        if self.done {
+            return None;
+        }
+
+        let chunk: Result<Vec<u8>, DBError> = self.stream.iter_chunk();
+        match chunk {
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(DBError::EndOfFileStream) => None,
+            // The chunk was skipped, the ones after it still read
+            Err(error @ DBError::MemoryBudgetExceeded { .. }) => Some(Err(error)),
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Iterates over the chunks of an in-memory database, such as a
+/// memory-mapped file, without copying them.
+pub struct DBSliceIterator<'a, const BLOCK: usize = BLOCK_SIZE, C = DBStandardConfig> {
+    stream: DBSliceStream<'a, BLOCK, C>,
+    ring: Option<DBRingCursor>,
+    // Set once a chunk could not be read, nothing after it can be found
+    done: bool,
+}
+
+impl<'a, const BLOCK: usize, C: DBConfig> DBSliceIterator<'a, BLOCK, C> {
+    pub fn from_slice(buffer: &'a [u8]) -> Self {
+        let stream: DBSliceStream<'_, BLOCK, C> = DBSliceStream::new(buffer);
+        let ring: Option<DBRingCursor> = None;
+        let done: bool = false;
+        Self { stream, ring, done }
+    }
+
+    pub fn from_ring(buffer: &'a [u8], ring: DBRingCursor) -> Self {
+        let mut iterator: DBSliceIterator<'a, BLOCK, C> = Self::from_slice(buffer);
+        iterator.ring = Some(ring);
+        iterator
+    }
+
+    /// Byte offset of the next chunk.
+    pub fn position(&self) -> usize {
+        self.stream.get_position()
+    }
+
+    /// Continues from the chunk at byte offset `position`, capped databases
+    /// return `DBError::Unsupported`.
+    pub fn seek(&mut self, position: usize) -> Result<usize, DBError> {
+        if self.ring.is_some() {
+            return Err(DBError::Unsupported);
+        }
+        self.done = false;
+        self.stream.seek(position)
+    }
+
+    /// Like `next`, but only ends at the end of the buffer and passes on the
+    /// error of a chunk that does not read.
+    pub fn try_next(&mut self) -> Result<Option<&'a [u8]>, DBError> {
+        if let Some(ring) = &mut self.ring {
+            return ring.next_slice(&mut self.stream).transpose();
+        }
+        if self.stream.at_end() {
+            return Ok(None);
+        }
+        self.stream.iter_chunk().map(Some)
+    }
+}
+
+impl<'a, const BLOCK: usize, C: DBConfig> Iterator for DBSliceIterator<'a, BLOCK, C> {
+    type Item = Result<&'a [u8], DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(ring) = &mut self.ring {
+            return ring.next_slice(&mut self.stream);
+        }
+
+        if self.done {
+            return None;
+        }
+
+        let chunk: Result<&[u8], DBError> = self.stream.iter_chunk();
+        match chunk {
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(DBError::EndOfFileStream) => None,
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::db::Database;
+use crate::error::DBError;
+use crate::iterators::DBIterator;
+use crate::structures::DBEntry;
+use crate::traits::CPathTrait;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+use core::hash::Hash;
+
+use bincode::Decode;
+use bincode::Encode;
+
+/// Record of a key-value file. The last record of a key decides its value.
+#[derive(Encode, Decode, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum DBKvRecord<K, V> {
+    Set { key: K, value: V },
+    Remove { key: K },
+}
+
+/// Database of the records of a key-value store.
+pub type DBKvDatabase<'a, K, V, const CACHE: usize, const BLOCK: usize, C> =
+    Database<'a, BTreeSet<DBKvRecord<K, V>>, CACHE, BLOCK, C>;
+
+/// Key-value store kept as a log of `DBKvRecord` entries in `database`.
+/// Every change appends a single record, so a torn write loses the change
+/// without touching the previous value. The first lookup reads the log into
+/// an index of every live value, which later changes keep up to date, and
+/// `iter` and `compact` hold every live key in memory. Records written
+/// through `database` directly are only seen after `reload`.
+pub struct KvStore<
+    'a,
+    K,
+    V,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    K: Encode + Decode + Hash + Ord + Clone,
+    V: Encode + Decode + Hash + Eq + Clone,
+    C: DBConfig,
+{
+    pub database: DBKvDatabase<'a, K, V, CACHE, BLOCK, C>,
+    /// Live value of every key, read from the log on the first lookup.
+    index: Option<BTreeMap<K, V>>,
+}
+
+impl<'a, K, V, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    KvStore<'a, K, V, CACHE, BLOCK, C>
+where
+    K: Encode + Decode + Hash + Ord + Clone,
+    V: Encode + Decode + Hash + Eq + Clone,
+{
+    /// UID of the last record of every key that currently has a value.
+    fn live_records(&mut self) -> Result<BTreeMap<K, u64>, DBError> {
+        let mut live: BTreeMap<K, u64> = BTreeMap::new();
+        for entry in self.database.get_iterator()? {
+            let entry: DBEntry<DBKvRecord<K, V>, C::UID> = entry?;
+            match entry.item {
+                DBKvRecord::Set { key, .. } => live.insert(key, entry.uid.into()),
+                DBKvRecord::Remove { key } => live.remove(&key),
+            };
+        }
+        Ok(live)
+    }
+
+    /// Index of the live values, reading the log the first time.
+    fn index(&mut self) -> Result<&mut BTreeMap<K, V>, DBError> {
+        if self.index.is_none() {
+            let mut index: BTreeMap<K, V> = BTreeMap::new();
+            for entry in self.database.get_iterator()? {
+                match entry?.item {
+                    DBKvRecord::Set { key, value } => index.insert(key, value),
+                    DBKvRecord::Remove { key } => index.remove(&key),
+                };
+            }
+            self.index = Some(index);
+        }
+        Ok(self.index.get_or_insert_with(BTreeMap::new))
+    }
+}
+
+impl<'a, K, V, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    KvStore<'a, K, V, CACHE, BLOCK, C>
+where
+    K: Encode + Decode + Hash + Ord + Clone,
+    V: Encode + Decode + Hash + Eq + Clone,
+{
+    pub fn new(database: DBKvDatabase<'a, K, V, CACHE, BLOCK, C>) -> Self {
+        let index: Option<BTreeMap<K, V>> = None;
+        Self { database, index }
+    }
+
+    pub fn get(&mut self, key: &K) -> Result<Option<V>, DBError> {
+        Ok(self.index()?.get(key).cloned())
+    }
+
+    pub fn set(&mut self, key: K, value: V) -> Result<(), DBError> {
+        let record: DBKvRecord<K, V> = DBKvRecord::Set { key, value };
+        self.database.add_entry(&record)?;
+        // An index that is not read yet picks the record up from the log
+        if let (Some(index), DBKvRecord::Set { key, value }) = (&mut self.index, record) {
+            index.insert(key, value);
+        }
+        Ok(())
+    }
+
+    /// Removes `key`, returning the value it had.
+    pub fn remove(&mut self, key: &K) -> Result<Option<V>, DBError> {
+        let value: Option<V> = self.get(key)?;
+        if value.is_some() {
+            let record: DBKvRecord<K, V> = DBKvRecord::Remove { key: key.clone() };
+            self.database.add_entry(&record)?;
+            self.index()?.remove(key);
+        }
+        Ok(value)
+    }
+
+    /// Drops the index, so the next lookup reads the log again.
+    pub fn reload(&mut self) {
+        self.index = None;
+    }
+
+    /// Iterates over the current value of every key, in the order they were
+    /// last set.
+    pub fn iter(&mut self) -> Result<DBKvIterator<'_, K, V, CACHE, BLOCK, C>, DBError> {
+        let live: BTreeMap<K, u64> = self.live_records()?;
+        let iterator: DBIterator<'_, BTreeSet<DBKvRecord<K, V>>, CACHE, BLOCK, C> =
+            self.database.get_iterator()?;
+        Ok(DBKvIterator::new(iterator, live))
+    }
+
+    /// Drops every record that no longer decides a value by writing the
+    /// others to `scratch` and renaming it over the file, see
+    /// `Database::rewrite_by_uid`. Returns the number of dropped records.
+    pub fn compact(&mut self, scratch: &dyn CPathTrait) -> Result<usize, DBError> {
+        let live: BTreeSet<u64> = self.live_records()?.into_values().collect();
+        self.database
+            .rewrite_by_uid(scratch, |uid| live.contains(&uid.into()))
+    }
+
+    /// Closes the underlying database, see `Database::close`.
+    pub fn close(self) -> Result<(), DBError> {
+        self.database.close()
+    }
+}
+
+/// Iterates over the live key-value pairs of a `KvStore`.
+pub struct DBKvIterator<'s, K, V, const CACHE: usize, const BLOCK: usize, C>
+where
+    K: Encode + Decode + Hash + Ord + Clone,
+    V: Encode + Decode + Hash + Eq + Clone,
+    C: DBConfig,
+{
+    iterator: DBIterator<'s, BTreeSet<DBKvRecord<K, V>>, CACHE, BLOCK, C>,
+    live: BTreeMap<K, u64>,
+}
+
+impl<'s, K, V, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBKvIterator<'s, K, V, CACHE, BLOCK, C>
+where
+    K: Encode + Decode + Hash + Ord + Clone,
+    V: Encode + Decode + Hash + Eq + Clone,
+{
+    pub fn new(
+        iterator: DBIterator<'s, BTreeSet<DBKvRecord<K, V>>, CACHE, BLOCK, C>,
+        live: BTreeMap<K, u64>,
+    ) -> Self {
+        Self { iterator, live }
+    }
+}
+
+impl<'s, K, V, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBKvIterator<'s, K, V, CACHE, BLOCK, C>
+where
+    K: Encode + Decode + Hash + Ord + Clone,
+    V: Encode + Decode + Hash + Eq + Clone,
+{
+    type Item = Result<(K, V), DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for entry in self.iterator.by_ref() {
+            let entry: DBEntry<DBKvRecord<K, V>, C::UID> = match entry {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+            if let DBKvRecord::Set { key, value } = entry.item {
+                if self.live.get(&key) == Some(&entry.uid.into()) {
+                    return Some(Ok((key, value)));
+                }
+            }
+        }
+        None
+    }
+}
@@ -1,30 +1,79 @@
-#![cfg_attr(feature = "embedded", no_std)]
-#![cfg_attr(feature = "embedded", no_main)]
+#![cfg_attr(feature = "no-std", no_std)]
+#![cfg_attr(feature = "no-std", no_main)]
 
-#[cfg(any(
-    all(feature = "no-std", not(feature = "alloc")),
-    all(not(feature = "no-std"), feature = "alloc")
-))]
+#[cfg(all(not(feature = "no-std"), feature = "alloc"))]
 compile_error!(
-    "Unable to build with no-std or alloc exclusively,
-    this crate requires both to be enabled if either are selected,
+    "Unable to build with alloc exclusively,
+    this crate requires no-std to be enabled as well,
     use 'embedded' feature instead."
 );
 
+#[cfg(all(feature = "no-std", feature = "std"))]
+compile_error!(
+    "Unable to build with both std and no-std,
+    disable default features when using 'embedded' or 'heapless'."
+);
+
 pub const BLOCK_SIZE: usize = 4;
 pub const CACHE_SIZE: usize = 2048;
 pub const EOE_BLOCK: [u8; BLOCK_SIZE] = [0xC2, 0xB5, 0x64, 0x62];
+pub const DECODE_LIMIT: usize = 16 * 1024 * 1024;
 
-pub mod db;
+/// EOE block for blocks of `B` bytes, the EOE bytes followed by zeros.
+pub const fn eoe_block<const B: usize>() -> [u8; B] {
+    let mut block: [u8; B] = [0; B];
+    let mut idx: usize = 0;
+    while idx < BLOCK_SIZE {
+        block[idx] = EOE_BLOCK[idx];
+        idx += 1;
+    }
+    block
+}
+
+pub mod config;
 pub mod error;
-pub mod serializer;
-pub mod stream;
+pub mod heapless;
 pub mod structures;
 pub mod traits;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod capped;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod catalog;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod check;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod cipher;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod compact;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod compress;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod db;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod expiry;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod format;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod iterators;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod kv;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod memory;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod schema;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod serializer;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod series;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub mod stream;
+
 pub use bincode;
 
 #[cfg(feature = "std")]
 pub mod impls;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 #[cfg(feature = "std")]
 pub mod utils;
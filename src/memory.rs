@@ -0,0 +1,69 @@
+use crate::error::DBError;
+
+use core::cell::Cell;
+
+/// Memory use of a database, see `Database::memory_stats`.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct DBMemoryStats {
+    /// Bytes of stream cache. Streams hold the cache inline, so it lives
+    /// wherever the database or iterator lives rather than on the heap.
+    pub cache_size: usize,
+    pub block_size: usize,
+    /// Largest transient buffer seen so far, such as an encoded entry, a
+    /// buffered chunk or the bytes decoded into a single entry.
+    pub peak_transient: usize,
+    pub budget: Option<usize>,
+}
+
+/// Tracks the transient buffers of a database and enforces an optional
+/// budget on them. The budget is checked against encoded sizes before the
+/// buffers are allocated. Decoded items can take more memory than their
+/// encoding, e.g. a `Vec<u64>` of small varints.
+#[derive(Debug, Default)]
+pub struct DBMemory {
+    budget: Option<usize>,
+    peak: Cell<usize>,
+}
+
+impl DBMemory {
+    pub fn new(budget: Option<usize>) -> Self {
+        let peak: Cell<usize> = Cell::new(0);
+        Self { budget, peak }
+    }
+
+    pub fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    pub fn peak(&self) -> usize {
+        self.peak.get()
+    }
+
+    pub fn reset_peak(&self) {
+        self.peak.set(0);
+    }
+
+    /// Records a buffer that was already allocated.
+    pub fn record(&self, size: usize) {
+        if size > self.peak.get() {
+            self.peak.set(size);
+        }
+    }
+
+    /// Checks that a buffer of `size` bytes fits the budget before it is
+    /// allocated, and records it when it does.
+    pub fn claim(&self, size: usize) -> Result<(), DBError> {
+        if let Some(budget) = self.budget {
+            if size > budget {
+                return Err(DBError::MemoryBudgetExceeded { size, budget });
+            }
+        }
+
+        self.record(size);
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::DBError;
+use crate::impls::lock_file;
+use crate::traits::CPathTrait;
+use crate::traits::FileBox;
+use crate::traits::FileTrait;
+use crate::traits::OpenFileBox;
+use crate::traits::OpenFileTrait;
+
+use std::fs::File;
+use std::io::Error;
+
+use memmap2::Mmap;
+
+/// Read-only file backend that maps the whole file into memory, so that
+/// iterators can hand out chunks as borrowed slices of the mapping.
+///
+/// The file holds a shared advisory lock for as long as it is mapped, which
+/// keeps out programs that take an exclusive lock before writing. `OpenFile`
+/// does not lock, so the file must not be changed while it is mapped.
+pub struct MmapFile {
+    map: Mmap,
+    position: usize,
+    file: File,
+}
+
+impl MmapFile {
+    /// Locks `file` shared and maps it, failing with `DBError::Locked` while
+    /// another handle holds an exclusive lock on it.
+    pub fn new(file: File) -> Result<Self, DBError> {
+        lock_file(&file, false)?;
+
+        // SAFETY: The mapping is only ever read, and the database requires
+        // exclusive access to its file, so it is not modified while mapped.
+        // The shared lock held until `file` is dropped also keeps out
+        // writers that lock the file.
+        let result: Result<Mmap, Error> = unsafe { Mmap::map(&file) };
+        if let Ok(map) = result {
+            let position: usize = 0;
+            return Ok(Self {
+                map,
+                position,
+                file,
+            });
+        }
+
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
+}
+
+impl FileTrait for MmapFile {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, DBError> {
+        let start: usize = self.position.min(self.map.len());
+        let end: usize = start.saturating_add(buffer.len()).min(self.map.len());
+        let length: usize = end - start;
+
+        buffer[..length].copy_from_slice(&self.map[start..end]);
+        self.position = end;
+        Ok(length)
+    }
+
+    fn write(&mut self, _buffer: &[u8]) -> Result<usize, DBError> {
+        Err(DBError::Unsupported)
+    }
+
+    fn write_all(&mut self, _buffer: &[u8]) -> Result<(), DBError> {
+        Err(DBError::Unsupported)
+    }
+
+    fn seek(&mut self, position: usize) -> Result<usize, DBError> {
+        self.position = position;
+        Ok(position)
+    }
+
+    fn set_len(&self, _size: usize) -> Result<(), DBError> {
+        Err(DBError::Unsupported)
+    }
+
+    fn close(self: Box<Self>) -> Result<(), DBError> {
+        // Unmap before releasing the lock that keeps locking writers out
+        let MmapFile { map, file, .. } = *self;
+        drop(map);
+        let result: Result<(), Error> = file.unlock();
+        if result.is_ok() {
+            return Ok(());
+        }
+
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
+
+    fn as_bytes(&self) -> Option<&[u8]> {
+        Some(&self.map)
+    }
+}
+
+/// Opens files as `MmapFile`. Mapped files are always readable, and any
+/// request for write access fails with `DBError::Unsupported`.
+#[derive(Clone)]
+pub struct OpenMmapFile {
+    write: bool,
+    append: bool,
+    truncate: bool,
+    create: bool,
+}
+
+impl OpenFileTrait for OpenMmapFile {
+    fn new() -> OpenFileBox
+    where
+        Self: Sized,
+    {
+        let write: bool = false;
+        let append: bool = false;
+        let truncate: bool = false;
+        let create: bool = false;
+        Box::new(Self {
+            write,
+            append,
+            truncate,
+            create,
+        })
+    }
+
+    fn boxed(&self) -> OpenFileBox {
+        Box::new(self.clone())
+    }
+
+    fn read(&mut self, _read: bool) {}
+
+    fn write(&mut self, write: bool) {
+        self.write = write;
+    }
+
+    fn append(&mut self, append: bool) {
+        self.append = append;
+    }
+
+    fn truncate(&mut self, truncate: bool) {
+        self.truncate = truncate;
+    }
+
+    fn create(&mut self, create: bool) {
+        self.create = create;
+    }
+
+    fn reset(&mut self) {
+        self.write = false;
+        self.append = false;
+        self.truncate = false;
+        self.create = false;
+    }
+
+    fn open(&self, path: &dyn CPathTrait) -> Result<FileBox, DBError> {
+        if self.write || self.append || self.truncate || self.create {
+            return Err(DBError::Unsupported);
+        }
+
+        let result: Result<File, Error> = File::open(path.as_str());
+        if let Ok(file) = result {
+            let mmap_file: MmapFile = MmapFile::new(file)?;
+            return Ok(Box::new(mmap_file));
+        }
+        let db_error: DBError = result.unwrap_err().into();
+        Err(db_error)
+    }
+}
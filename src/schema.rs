@@ -0,0 +1,367 @@
+extern crate alloc;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::db::Database;
+use crate::error::DBError;
+use crate::iterators::DBIterator;
+use crate::structures::DBEntry;
+use crate::traits::CPathTrait;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+use bincode::config::Config;
+use bincode::de::read::Reader;
+use bincode::de::Decoder;
+use bincode::de::DecoderImpl;
+use bincode::decode_from_slice;
+use bincode::enc::write::Writer;
+use bincode::enc::Encoder;
+use bincode::encode_to_vec;
+use bincode::error::DecodeError;
+use bincode::error::EncodeError;
+use bincode::Decode;
+use bincode::Encode;
+
+/// Bytes a versioned record starts with. Under the varint encoding bincode
+/// marks wider integers with 251 to 254 and reserves 0xFF, so no varint
+/// starts with it, but a raw byte such as a `u8` field may, as may any field
+/// under the fixint encoding. A plain entry starting with the tag is only
+/// read as versioned when the record holds a version of the item type, see
+/// `DBVersionedRecord`.
+pub const VERSION_TAG: [u8; 4] = [0xFF, b'm', b'd', b'v'];
+
+/// Item type with a schema version. Each version names the type of the
+/// version before it and how to upgrade from it, so entries written with any
+/// earlier version are read as the latest one. Versions start at 1 and the
+/// first one names `DBFirstVersion` as `Previous`.
+pub trait VersionedItem: Encode + Decode + Hash + Eq + Sized {
+    const VERSION: u32;
+    type Previous: VersionedItem;
+
+    fn upgrade(previous: Self::Previous) -> Self;
+}
+
+/// Stands in before the first version of an item type, no entry decodes
+/// as it.
+#[derive(Encode, Decode, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DBFirstVersion;
+
+impl VersionedItem for DBFirstVersion {
+    const VERSION: u32 = 0;
+    type Previous = DBFirstVersion;
+
+    fn upgrade(previous: Self::Previous) -> Self {
+        previous
+    }
+}
+
+/// Decodes `bytes` written with `version` of `V` or of a version before it.
+/// Version 0 stands for an entry written without a version, which holds the
+/// first version.
+pub fn decode_version<V: VersionedItem, C: DBConfig>(
+    version: u32,
+    bytes: &[u8],
+) -> Result<V, DecodeError> {
+    decode_version_with(version, bytes, C::config())
+}
+
+/// Decodes `bytes` like `decode_version`, with bincode `config`.
+fn decode_version_with<V: VersionedItem, K: Config>(
+    version: u32,
+    bytes: &[u8],
+    config: K,
+) -> Result<V, DecodeError> {
+    if V::VERSION == 0 || version > V::VERSION {
+        return Err(DecodeError::Other("unknown schema version"));
+    }
+    if version < V::VERSION && V::VERSION > 1 {
+        let previous: V::Previous = decode_version_with::<V::Previous, K>(version, bytes, config)?;
+        return Ok(V::upgrade(previous));
+    }
+
+    let item: (V, usize) = decode_from_slice(bytes, config)?;
+    match item {
+        (item, length) if length == bytes.len() => Ok(item),
+        _ => Err(DecodeError::Other("item left bytes unread")),
+    }
+}
+
+/// Decodes the first version of `V` from `decoder` and encodes it again on
+/// its own.
+fn decode_first<V: VersionedItem, D: Decoder>(decoder: &mut D) -> Result<Vec<u8>, DecodeError> {
+    if V::VERSION == 0 {
+        return Err(DecodeError::Other("unknown schema version"));
+    }
+    if V::VERSION > 1 {
+        return decode_first::<V::Previous, D>(decoder);
+    }
+
+    let item: V = V::decode(decoder)?;
+    let bytes: Result<Vec<u8>, EncodeError> = encode_to_vec(&item, *decoder.config());
+    bytes.map_err(|_| DecodeError::Other("item failed to encode again"))
+}
+
+/// Reads the bytes of `head` before those of `reader`.
+struct DBReplayReader<'r, R: Reader> {
+    head: &'r [u8],
+    reader: &'r mut R,
+}
+
+impl<'r, R: Reader> Reader for DBReplayReader<'r, R> {
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        let length: usize = self.head.len().min(bytes.len());
+        bytes[..length].copy_from_slice(&self.head[..length]);
+        self.head = &self.head[length..];
+        match length < bytes.len() {
+            true => self.reader.read(&mut bytes[length..]),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Reads from `reader`, keeping a copy of the bytes in `read`.
+struct DBRecordingReader<'r, R: Reader> {
+    reader: &'r mut R,
+    read: &'r mut Vec<u8>,
+}
+
+impl<'r, R: Reader> Reader for DBRecordingReader<'r, R> {
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        self.reader.read(bytes)?;
+        self.read.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Record of a versioned file, an item encoded on its own with the version
+/// of its type, after `VERSION_TAG`. Entries without the tag are read as
+/// the first version of `I`, with version 0. So are entries starting with
+/// the tag that do not hold a known version of `I`, when the first version
+/// decodes from them instead. Records of later versions are kept as read.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DBVersionedRecord<I> {
+    pub version: u32,
+    pub bytes: Vec<u8>,
+    marker: PhantomData<I>,
+}
+
+impl<I: VersionedItem> DBVersionedRecord<I> {
+    /// Decodes the first version of `I` from `head` followed by the bytes of
+    /// `decoder`, encoding it again on its own.
+    fn decode_plain<D: Decoder>(decoder: &mut D, head: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let config: D::C = *decoder.config();
+        let reader: DBReplayReader<'_, D::R> = DBReplayReader {
+            head,
+            reader: decoder.reader(),
+        };
+        let mut plain: DecoderImpl<DBReplayReader<'_, D::R>, D::C> =
+            DecoderImpl::new(reader, config);
+        decode_first::<I, _>(&mut plain)
+    }
+}
+
+impl<I> DBVersionedRecord<I> {
+    pub fn new(version: u32, bytes: Vec<u8>) -> Self {
+        let marker: PhantomData<I> = PhantomData;
+        Self {
+            version,
+            bytes,
+            marker,
+        }
+    }
+}
+
+impl<I> Encode for DBVersionedRecord<I> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        encoder.writer().write(&VERSION_TAG)?;
+        self.version.encode(encoder)?;
+        self.bytes.encode(encoder)
+    }
+}
+
+impl<I: VersionedItem> Decode for DBVersionedRecord<I> {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let config: D::C = *decoder.config();
+        let mut head: [u8; VERSION_TAG.len()] = [0; VERSION_TAG.len()];
+        for idx in 0..head.len() {
+            decoder.reader().read(&mut head[idx..idx + 1])?;
+            if head[idx] == VERSION_TAG[idx] {
+                continue;
+            }
+
+            let bytes: Vec<u8> = Self::decode_plain(decoder, &head[..=idx])?;
+            return Ok(Self::new(0, bytes));
+        }
+
+        let mut read: Vec<u8> = VERSION_TAG.to_vec();
+        let reader: DBRecordingReader<'_, D::R> = DBRecordingReader {
+            reader: decoder.reader(),
+            read: &mut read,
+        };
+        let mut tagged: DecoderImpl<DBRecordingReader<'_, D::R>, D::C> =
+            DecoderImpl::new(reader, config);
+        let record: Result<(u32, Vec<u8>), DecodeError> =
+            u32::decode(&mut tagged).and_then(|version| Ok((version, Vec::decode(&mut tagged)?)));
+        let error: DecodeError = match record {
+            // Versions after the latest one of `I` are left to fail on read
+            Ok((version, bytes)) if version > I::VERSION => return Ok(Self::new(version, bytes)),
+            Ok((version, bytes)) => match decode_version_with::<I, D::C>(version, &bytes, config) {
+                Ok(_) => return Ok(Self::new(version, bytes)),
+                Err(error) => error,
+            },
+            Err(error) => error,
+        };
+
+        // A plain entry may start with the tag, the error of the tagged
+        // record is kept when it is not one either
+        match Self::decode_plain(decoder, &read) {
+            Ok(bytes) => Ok(Self::new(0, bytes)),
+            Err(_) => Err(error),
+        }
+    }
+}
+
+/// Database of the records of a `DBVersioned`.
+pub type DBVersionedDatabase<'a, I, const CACHE: usize, const BLOCK: usize, C> =
+    Database<'a, BTreeSet<DBVersionedRecord<I>>, CACHE, BLOCK, C>;
+
+/// Entries of `I` stored as `DBVersionedRecord` entries of `database`.
+/// Entries written with an earlier version of `I` are upgraded on read, or
+/// rewritten at once by `migrate`. Plain entries of the first version of `I`,
+/// written before the file was versioned, are read as that version.
+pub struct DBVersioned<
+    'a,
+    I,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    I: VersionedItem,
+    C: DBConfig,
+{
+    pub database: DBVersionedDatabase<'a, I, CACHE, BLOCK, C>,
+    marker: PhantomData<I>,
+}
+
+impl<'a, I, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBVersioned<'a, I, CACHE, BLOCK, C>
+where
+    I: VersionedItem,
+{
+    fn encode(item: &I) -> Result<DBVersionedRecord<I>, DBError> {
+        let bytes: Result<Vec<u8>, EncodeError> = encode_to_vec(item, C::config());
+        let bytes: Vec<u8> = bytes.map_err(DBError::SerializeError)?;
+        let version: u32 = I::VERSION;
+        Ok(DBVersionedRecord::new(version, bytes))
+    }
+}
+
+impl<'a, I, const CACHE: usize, const BLOCK: usize, C: DBConfig> DBVersioned<'a, I, CACHE, BLOCK, C>
+where
+    I: VersionedItem,
+{
+    pub fn new(database: DBVersionedDatabase<'a, I, CACHE, BLOCK, C>) -> Self {
+        let marker: PhantomData<I> = PhantomData;
+        Self { database, marker }
+    }
+
+    pub fn add_entry(&mut self, item: &I) -> Result<(), DBError> {
+        let record: DBVersionedRecord<I> = Self::encode(item)?;
+        self.database.add_entry(&record)
+    }
+
+    pub fn get_iterator(&mut self) -> Result<DBVersionedIterator<'_, I, CACHE, BLOCK, C>, DBError> {
+        let iterator: DBIterator<'_, BTreeSet<DBVersionedRecord<I>>, CACHE, BLOCK, C> =
+            self.database.get_iterator()?;
+        Ok(DBVersionedIterator::new(iterator))
+    }
+
+    pub fn get_by_uid(&mut self, uid: C::UID) -> Result<DBEntry<I, C::UID>, DBError> {
+        let entry: DBEntry<DBVersionedRecord<I>, C::UID> = self.database.get_by_uid(uid)?;
+        let record: DBVersionedRecord<I> = entry.item;
+        match decode_version::<I, C>(record.version, &record.bytes) {
+            Ok(item) => Ok(DBEntry::new(uid, item)),
+            Err(error) => Err(DBError::DeserializeError {
+                uid: uid.into(),
+                error,
+            }),
+        }
+    }
+
+    /// Rewrites the entries of earlier versions with the latest one, keeping
+    /// their order and UIDs, in a single pass through
+    /// `Database::rewrite_entries`. The file is only replaced once `scratch`
+    /// holds every entry, and nothing changes if an entry fails to decode.
+    /// Returns the number of upgraded entries.
+    pub fn migrate(&mut self, scratch: &dyn CPathTrait) -> Result<usize, DBError> {
+        self.database.rewrite_entries(scratch, |entry| {
+            let record: DBVersionedRecord<I> = entry.item;
+            if record.version == I::VERSION {
+                return Ok(None);
+            }
+
+            let item: Result<I, DecodeError> =
+                decode_version::<I, C>(record.version, &record.bytes);
+            let item: I = item.map_err(|error| DBError::DeserializeError {
+                uid: entry.uid.into(),
+                error,
+            })?;
+            Ok(Some(Self::encode(&item)?))
+        })
+    }
+
+    /// Closes the underlying database, see `Database::close`.
+    pub fn close(self) -> Result<(), DBError> {
+        self.database.close()
+    }
+}
+
+/// Iterates over the entries of a `DBVersioned`, upgrading them to the
+/// latest version of `I`.
+pub struct DBVersionedIterator<'s, I, const CACHE: usize, const BLOCK: usize, C>
+where
+    I: VersionedItem,
+    C: DBConfig,
+{
+    iterator: DBIterator<'s, BTreeSet<DBVersionedRecord<I>>, CACHE, BLOCK, C>,
+    marker: PhantomData<I>,
+}
+
+impl<'s, I, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBVersionedIterator<'s, I, CACHE, BLOCK, C>
+where
+    I: VersionedItem,
+{
+    pub fn new(iterator: DBIterator<'s, BTreeSet<DBVersionedRecord<I>>, CACHE, BLOCK, C>) -> Self {
+        let marker: PhantomData<I> = PhantomData;
+        Self { iterator, marker }
+    }
+}
+
+impl<'s, I, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBVersionedIterator<'s, I, CACHE, BLOCK, C>
+where
+    I: VersionedItem,
+{
+    type Item = Result<DBEntry<I, C::UID>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry: DBEntry<DBVersionedRecord<I>, C::UID> = match self.iterator.next()? {
+            Ok(entry) => entry,
+            Err(error) => return Some(Err(error)),
+        };
+        let record: DBVersionedRecord<I> = entry.item;
+        match decode_version::<I, C>(record.version, &record.bytes) {
+            Ok(item) => Some(Ok(DBEntry::new(entry.uid, item))),
+            Err(error) => Some(Err(DBError::DeserializeError {
+                uid: entry.uid.into(),
+                error,
+            })),
+        }
+    }
+}
@@ -2,18 +2,33 @@ extern crate alloc;
 use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 
+use crate::cipher::DBCipher;
+use crate::compact::decode_varint;
+use crate::compact::DBCompactHeader;
+use crate::compact::VARINT_MAX;
+use crate::compress::compress;
+use crate::compress::DBDecompressor;
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::config::DBUid;
+use crate::eoe_block;
 use crate::error::DBError;
+use crate::stream::DBChunkReader;
+use crate::stream::DBFileStream;
 use crate::structures::DBEntry;
+use crate::structures::DBItemEntry;
 use crate::BLOCK_SIZE;
-use crate::EOE_BLOCK;
 
+use core::cell::Cell;
 use core::hash::Hash;
 use core::marker::PhantomData;
 
-use bincode::config::legacy;
-use bincode::config::standard;
-use bincode::config::*;
+use bincode::de::read::Reader;
+use bincode::de::read::SliceReader;
+use bincode::decode_from_reader;
 use bincode::decode_from_slice;
+use bincode::enc::write::SizeWriter;
+use bincode::encode_into_writer;
 use bincode::encode_to_vec;
 use bincode::error::DecodeError;
 use bincode::error::EncodeError;
@@ -22,153 +37,492 @@ use bincode::Encode;
 
 pub struct UIDSerializer;
 
+impl Default for UIDSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UIDSerializer {
     pub fn new() -> Self {
         Self {}
     }
 
-    pub fn serialize_uid(&self, uid: u32) -> [u8; BLOCK_SIZE] {
-        let mut block: [u8; BLOCK_SIZE] = [0u8; BLOCK_SIZE];
-        for i in 0..BLOCK_SIZE {
-            block[i] = ((uid >> (i * 8)) & 0xFF) as u8;
-        }
+    /// Encodes the UID into the first bytes of a block of `B` bytes. UIDs
+    /// are part of the block layout, they are always Little Endian
+    /// regardless of the entry encoding.
+    pub fn serialize_uid<const B: usize, U: DBUid>(&self, uid: U) -> [u8; B] {
+        let mut block: [u8; B] = [0u8; B];
+        uid.write_le(&mut block);
         block
     }
 
-    pub fn deserialize_uid(&self, buffer: &[u8]) -> Result<u32, DBError> {
-        let config: Configuration<LittleEndian, Fixint> = legacy();
-        let uid: Result<(u32, usize), DecodeError> = decode_from_slice(buffer, config);
-        if let Ok((uid, _)) = uid {
-            return Ok(uid);
+    pub fn deserialize_uid<U: DBUid>(&self, buffer: &[u8]) -> Result<U, DBError> {
+        if buffer.len() < U::SIZE {
+            let additional: usize = U::SIZE - buffer.len();
+            let error: DecodeError = DecodeError::UnexpectedEnd { additional };
+            return Err(DBError::UIDDeserializeError(error));
         }
-
-        Err(DBError::UIDDeserializeError)
+        Ok(U::read_le(buffer))
     }
 }
 
-pub struct DBSerializer<'a, T>
+/// Serializes entries encoded with `C` into chunks of `B`-byte blocks.
+pub struct DBSerializer<'a, T, const B: usize = BLOCK_SIZE, C = DBStandardConfig>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
     pub uid_serializer: UIDSerializer,
+    /// Cipher of an encrypted database, see `with_cipher`.
+    pub cipher: Option<DBCipher>,
+    /// Nonce counter the next sealed entry uses.
+    pub next_nonce: Cell<u64>,
     pub _marker: PhantomData<&'a T>,
+    pub _config: PhantomData<C>,
 }
 
-impl<'a, T> DBSerializer<'a, T>
+impl<'a, T, const B: usize, C: DBConfig> DBSerializer<'a, T, B, C>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
-    fn bincode_serialize(&self, item: &T::Item) -> Result<Vec<u8>, DBError> {
-        let config: Configuration = standard();
-        let bytes: Result<Vec<u8>, EncodeError> = encode_to_vec(item, config);
-        if let Ok(bytes) = bytes {
+    fn check_limit(&self, size: usize) -> Result<(), DBError> {
+        let capacity: usize = C::ENCODING.limit;
+        if size > capacity {
+            return Err(DBError::EntryTooLarge { size, capacity });
+        }
+        Ok(())
+    }
+
+    /// Encodes an entry and compresses it for compressed encodings, which
+    /// is the payload before it is sealed.
+    fn encode_payload(&self, item: &T::Item) -> Result<Vec<u8>, DBError> {
+        let bytes: Result<Vec<u8>, EncodeError> = encode_to_vec(item, C::config());
+        match bytes {
+            Ok(bytes) => {
+                // Readers would reject the entry, so it is never written
+                self.check_limit(bytes.len())?;
+                if C::ENCODING.compressed {
+                    return Ok(compress(&bytes));
+                }
+                Ok(bytes)
+            }
+            Err(error) => Err(DBError::SerializeError(error)),
+        }
+    }
+
+    fn bincode_serialize(&self, uid: C::UID, item: &T::Item) -> Result<Vec<u8>, DBError> {
+        let bytes: Vec<u8> = self.encode_payload(item)?;
+        if !C::ENCODING.encrypted {
             return Ok(bytes);
         }
-        Err(DBError::SerializeError)
+        self.seal_payload(uid, &bytes)
+    }
+
+    /// Seals `bytes` for the entry `uid` with the next reserved nonce.
+    fn seal_payload(&self, uid: C::UID, bytes: &[u8]) -> Result<Vec<u8>, DBError> {
+        let cipher: DBCipher = self.cipher.ok_or(DBError::MissingKey)?;
+        let nonce: u64 = self.next_nonce.get();
+        self.next_nonce.set(nonce + 1);
+
+        let mut buffer: Vec<u8> = Vec::with_capacity(DBCipher::sealed_size(bytes.len()));
+        cipher.seal(nonce, uid.into(), bytes, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Largest payload before it is sealed.
+    fn plaintext_limit() -> usize {
+        match C::ENCODING.compressed {
+            true => C::ENCODING.limit + 1,
+            false => C::ENCODING.limit,
+        }
+    }
+
+    fn open_payload(&self, uid: C::UID, bytes: &[u8]) -> Result<(Vec<u8>, usize), DBError> {
+        let cipher: DBCipher = self.cipher.ok_or(DBError::MissingKey)?;
+        let opened: Option<(Vec<u8>, usize)> =
+            cipher.open(bytes, uid.into(), Self::plaintext_limit());
+        opened.ok_or(DBError::AuthenticationFailed {
+            uid: Some(uid.into()),
+        })
+    }
+
+    /// Reads a sealed payload from `reader` into a buffer, it can only be
+    /// decrypted once the tag at its end has been checked.
+    fn read_sealed<R: Reader>(&self, uid: C::UID, mut reader: R) -> Result<Vec<u8>, DBError> {
+        let failed: DBError = DBError::AuthenticationFailed {
+            uid: Some(uid.into()),
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+        let length: Option<usize> = loop {
+            let mut byte: [u8; 1] = [0];
+            if buffer.len() == VARINT_MAX || reader.read(&mut byte).is_err() {
+                return Err(failed);
+            }
+            buffer.push(byte[0]);
+            if byte[0] & 0x80 == 0 {
+                let length: Option<(u64, usize)> = decode_varint(&buffer);
+                break length.and_then(|(length, _)| usize::try_from(length).ok());
+            }
+        };
+
+        let length: usize = match length {
+            Some(length) if length <= Self::plaintext_limit() => length,
+            _ => return Err(failed),
+        };
+        let start: usize = buffer.len();
+        buffer.resize(DBCipher::sealed_size(length).max(start), 0);
+        if reader.read(&mut buffer[start..]).is_err() {
+            return Err(failed);
+        }
+        Ok(buffer)
+    }
+
+    /// Decodes an entry from payload bytes that are not sealed.
+    fn decode_plaintext(&self, uid: C::UID, bytes: &[u8]) -> Result<(T::Item, usize), DBError> {
+        let config: C::Config = C::config();
+        let item: Result<(T::Item, usize), DecodeError> = match C::ENCODING.compressed {
+            true => {
+                let reader: SliceReader<'_> = SliceReader::new(bytes);
+                let mut reader: DBDecompressor<_> = DBDecompressor::new(reader, C::ENCODING.limit);
+                let item: Result<T::Item, DecodeError> = decode_from_reader(&mut reader, config);
+                item.map(|item| (item, reader.consumed()))
+            }
+            false => decode_from_slice(bytes, config),
+        };
+        match item {
+            Ok((item, length)) => Ok((item, length)),
+            Err(error) => Err(DBError::DeserializeError {
+                uid: uid.into(),
+                error,
+            }),
+        }
+    }
+
+    /// Decodes an entry from payload bytes, returning it with the number of
+    /// bytes it took. Sealed payloads are checked and decrypted first.
+    fn bincode_deserialize(&self, uid: C::UID, bytes: &[u8]) -> Result<(T::Item, usize), DBError> {
+        if !C::ENCODING.encrypted {
+            return self.decode_plaintext(uid, bytes);
+        }
+
+        let (plaintext, length) = self.open_payload(uid, bytes)?;
+        let (item, _) = self.decode_plaintext(uid, &plaintext)?;
+        Ok((item, length))
     }
 
-    fn bincode_deserialize(&self, bytes: &[u8]) -> Result<T::Item, DBError> {
-        let config: Configuration = standard();
-        let item: Result<(T::Item, usize), DecodeError> = decode_from_slice(bytes, config);
-        if let Ok((item, _)) = item {
+    /// Decodes an entry from the payload bytes of `reader`, decompressing
+    /// them first for compressed encodings. Sealed payloads are buffered so
+    /// they can be checked before anything is decoded.
+    fn decode_from_payload<R: Reader>(&self, uid: C::UID, reader: R) -> Result<T::Item, DBError> {
+        if C::ENCODING.encrypted {
+            let payload: Vec<u8> = self.read_sealed(uid, reader)?;
+            let (item, _) = self.bincode_deserialize(uid, &payload)?;
             return Ok(item);
         }
-        Err(DBError::DeserializeError)
+
+        let config: C::Config = C::config();
+        let item: Result<T::Item, DecodeError> = match C::ENCODING.compressed {
+            true => decode_from_reader(DBDecompressor::new(reader, C::ENCODING.limit), config),
+            false => decode_from_reader(reader, config),
+        };
+        item.map_err(|error| DBError::DeserializeError {
+            uid: uid.into(),
+            error,
+        })
     }
 
     fn pad_serialized_chunk(&self, chunk: &[u8], buffer: &mut Vec<u8>) {
         let mut padded_chunk: Vec<u8> = chunk.to_vec();
-        padded_chunk.resize(BLOCK_SIZE, 0);
+        padded_chunk.resize(B, 0);
         buffer.extend(padded_chunk);
     }
+
+    /// Size of the payload holding `length` bytes, once it is sealed.
+    fn sealed_size(length: usize) -> usize {
+        match C::ENCODING.encrypted {
+            true => DBCipher::sealed_size(length),
+            false => length,
+        }
+    }
+
+    /// Size of the chunk of an appended entry with `length` payload bytes.
+    fn chunk_size(&self, length: usize) -> usize {
+        if C::ENCODING.compact {
+            let header: DBCompactHeader = DBCompactHeader::new(1, length);
+            return header.chunk_size();
+        }
+        length.next_multiple_of(B) + 2 * B
+    }
+
+    /// Appends the chunk of an encoded entry. Compact chunks store `delta`,
+    /// the distance from the UID of the chunk before them, instead of `uid`.
+    fn push_chunk(&self, uid: C::UID, delta: u64, bytes: &[u8], buffer: &mut Vec<u8>) {
+        if C::ENCODING.compact {
+            DBCompactHeader::new(delta, bytes.len()).encode(buffer);
+            buffer.extend(bytes);
+            return;
+        }
+
+        let uid_block: [u8; B] = self.uid_serializer.serialize_uid(uid);
+        buffer.extend(uid_block);
+        for block in bytes.chunks(B) {
+            if block.len() == B {
+                buffer.extend(block);
+                continue;
+            }
+            self.pad_serialized_chunk(block, buffer);
+        }
+        buffer.extend(eoe_block::<B>());
+    }
+
+    /// Appended entries always follow the last UID in the file, or start the
+    /// file at the first UID, so their distance is known from the UID alone.
+    fn append_delta(uid: C::UID) -> u64 {
+        match uid == C::UID::default() {
+            true => 0,
+            false => 1,
+        }
+    }
+
+    fn compact_header(&self, buffer: &[u8]) -> Result<DBCompactHeader, DBError> {
+        let header: Option<DBCompactHeader> =
+            DBCompactHeader::decode(buffer, C::ENCODING.payload_limit());
+        match header {
+            Some(header) if header.chunk_size() <= buffer.len() => Ok(header),
+            _ => Err(DBError::InvalidData { offset: None }),
+        }
+    }
+
+    fn deserialize_compact_from_stream<const N: usize>(
+        &self,
+        stream: &mut DBFileStream<'_, N, B, C>,
+    ) -> Result<DBItemEntry<T, C>, DBError> {
+        stream.claim_chunk()?;
+        let (uid, length) = stream.next_compact_header()?;
+
+        let mut reader: DBChunkReader<'_, '_, N, B, C> = DBChunkReader::with_length(stream, length);
+        let item: Result<T::Item, DBError> = self.decode_from_payload(uid, &mut reader);
+        reader.finish()?;
+        Ok(DBEntry::new(uid, item?))
+    }
+}
+
+impl<'a, T, const B: usize, C: DBConfig> Default for DBSerializer<'a, T, B, C>
+where
+    T: IntoIterator + Eq,
+    T::Item: Encode + Decode + Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl<'a, T> DBSerializer<'a, T>
+impl<'a, T, const B: usize, C: DBConfig> DBSerializer<'a, T, B, C>
 where
     T: IntoIterator + Eq,
     T::Item: Encode + Decode + Hash + Eq,
 {
     pub fn new() -> Self {
         let uid_serializer: UIDSerializer = UIDSerializer::new();
+        let cipher: Option<DBCipher> = None;
+        let next_nonce: Cell<u64> = Cell::new(0);
         let _marker: PhantomData<&T> = PhantomData;
+        let _config: PhantomData<C> = PhantomData;
         Self {
             uid_serializer,
+            cipher,
+            next_nonce,
             _marker,
+            _config,
         }
     }
 
-    pub fn serialize(&self, uid: u32, item: &T::Item) -> Result<Vec<u8>, DBError> {
-        let mut buffer: Vec<u8> = Vec::new();
-        let uid_block: [u8; BLOCK_SIZE] = self.uid_serializer.serialize_uid(uid);
-        buffer.extend(uid_block);
+    /// Seals and opens entries with `cipher`, sealing the next entry with
+    /// nonce counter `next_nonce`. Every nonce handed to a serializer must be
+    /// reserved in the file first, see `DBCipherHeader::reserve`.
+    pub fn with_cipher(mut self, cipher: Option<DBCipher>, next_nonce: u64) -> Self {
+        self.cipher = cipher;
+        self.next_nonce = Cell::new(next_nonce);
+        self
+    }
 
-        let bytes: Vec<u8> = self.bincode_serialize(item)?;
-        for block in bytes.chunks(BLOCK_SIZE) {
-            if block.len() == BLOCK_SIZE {
-                buffer.extend(block);
-                continue;
-            }
-            self.pad_serialized_chunk(block, &mut buffer);
+    /// Size of the chunk `serialize` would produce, without allocating it.
+    pub fn serialized_size(&self, item: &T::Item) -> Result<usize, DBError> {
+        if C::ENCODING.compressed {
+            // Compressed sizes are only known once the entry is compressed
+            let bytes: Vec<u8> = self.encode_payload(item)?;
+            return Ok(self.chunk_size(Self::sealed_size(bytes.len())));
         }
 
-        buffer.extend(EOE_BLOCK);
+        let mut writer: SizeWriter = SizeWriter::default();
+        let result: Result<(), EncodeError> = encode_into_writer(item, &mut writer, C::config());
+        if let Err(error) = result {
+            return Err(DBError::SerializeError(error));
+        }
+        self.check_limit(writer.bytes_written)?;
+        Ok(self.chunk_size(Self::sealed_size(writer.bytes_written)))
+    }
+
+    /// Serializes the chunk of an entry appended with `uid`.
+    pub fn serialize(&self, uid: C::UID, item: &T::Item) -> Result<Vec<u8>, DBError> {
+        let mut buffer: Vec<u8> = Vec::new();
+        let bytes: Vec<u8> = self.bincode_serialize(uid, item)?;
+        self.push_chunk(uid, Self::append_delta(uid), &bytes, &mut buffer);
         Ok(buffer)
     }
 
+    /// Serializes `items` with consecutive UIDs starting at `uid`. Fails with
+    /// `DBError::UIDOverflow` when the UIDs would run past the UID range.
     pub fn serialize_items(
         &self,
-        mut uid: u32,
+        mut uid: C::UID,
         items: BTreeSet<T::Item>,
     ) -> Result<Vec<u8>, DBError> {
         let mut buffer: Vec<u8> = Vec::new();
 
-        for item in items.into_iter() {
-            let bytes: Vec<u8> = self.bincode_serialize(&item)?;
-            let uid_block: [u8; BLOCK_SIZE] = self.uid_serializer.serialize_uid(uid);
-            buffer.extend(uid_block);
-
-            for block in bytes.chunks(BLOCK_SIZE) {
-                if block.len() == BLOCK_SIZE {
-                    buffer.extend(block);
-                    continue;
-                }
-                self.pad_serialized_chunk(block, &mut buffer);
+        for (idx, item) in items.into_iter().enumerate() {
+            let mut delta: u64 = Self::append_delta(uid);
+            if idx > 0 {
+                uid = uid.checked_next().ok_or(DBError::UIDOverflow)?;
+                delta = 1;
             }
-
-            buffer.extend(EOE_BLOCK);
-            uid += 1;
+            let bytes: Vec<u8> = self.bincode_serialize(uid, &item)?;
+            self.push_chunk(uid, delta, &bytes, &mut buffer);
         }
         Ok(buffer)
     }
 
-    pub fn deserialize(&self, buffer: &[u8]) -> Result<DBEntry<T::Item>, DBError> {
-        let uid_block: &[u8] = &buffer[..BLOCK_SIZE];
-        let uid: u32 = self.uid_serializer.deserialize_uid(uid_block)?;
+    /// Copies the sealed `chunk` of the entry `from` with its payload sealed
+    /// again for the entry `to`, with the next reserved nonce. The UID the
+    /// chunk stores is left as is, see `Database::retain_by_uid`.
+    pub fn reseal(&self, chunk: &[u8], from: C::UID, to: C::UID) -> Result<Vec<u8>, DBError> {
+        let start: usize = match C::ENCODING.compact {
+            true => self.compact_header(chunk)?.size,
+            false => B,
+        };
+        let payload: &[u8] = chunk.get(start..).unwrap_or_default();
+        let (plaintext, length) = self.open_payload(from, payload)?;
+        let sealed: Vec<u8> = self.seal_payload(to, &plaintext)?;
 
-        let buffer: &[u8] = &buffer[BLOCK_SIZE..buffer.len() - BLOCK_SIZE];
-        let item: T::Item = self.bincode_deserialize(buffer)?;
+        // The same plaintext seals to the same size, so the chunk keeps it
+        let mut bytes: Vec<u8> = chunk.to_vec();
+        bytes[start..start + length].copy_from_slice(&sealed);
+        Ok(bytes)
+    }
 
-        let entry: DBEntry<T::Item> = DBEntry::new(uid, item);
-        return Ok(entry);
+    /// Deserializes a chunk. A compact chunk is read as the first chunk of a
+    /// file, see `deserialize_with_remainder` for the ones after it.
+    pub fn deserialize(&self, buffer: &[u8]) -> Result<DBEntry<T::Item, C::UID>, DBError> {
+        let (entry, _) = self.deserialize_with_remainder(buffer, 0)?;
+        Ok(entry)
     }
 
-    pub fn deserialize_items(&self, buffer: &[u8]) -> Result<Vec<DBEntry<T::Item>>, DBError> {
-        let mut items: Vec<DBEntry<T::Item>> = Vec::new();
+    /// Returns the UID of a chunk. `base` is the UID of the chunk before a
+    /// compact chunk, or 0 for the first one, and is ignored for block chunks.
+    pub fn chunk_uid(&self, buffer: &[u8], base: u64) -> Result<C::UID, DBError> {
+        if !C::ENCODING.compact {
+            return self.uid_serializer.deserialize_uid(buffer);
+        }
+
+        let header: DBCompactHeader = self.compact_header(buffer)?;
+        let uid: Option<C::UID> = base.checked_add(header.delta).and_then(C::UID::from_u64);
+        uid.ok_or(DBError::InvalidData { offset: None })
+    }
+
+    /// Deserializes a chunk like `deserialize`, also returning how many payload
+    /// bytes were left unread after the entry was decoded. `base` is used as
+    /// in `chunk_uid`.
+    pub fn deserialize_with_remainder(
+        &self,
+        buffer: &[u8],
+        base: u64,
+    ) -> Result<(DBItemEntry<T, C>, usize), DBError> {
+        if C::ENCODING.compact {
+            let uid: C::UID = self.chunk_uid(buffer, base)?;
+            let header: DBCompactHeader = self.compact_header(buffer)?;
+            let buffer: &[u8] = &buffer[header.size..header.chunk_size()];
+            let (item, length) = self.bincode_deserialize(uid, buffer)?;
+
+            let remainder: usize = buffer.len() - length;
+            return Ok((DBEntry::new(uid, item), remainder));
+        }
+
+        if buffer.len() < 2 * B {
+            return Err(DBError::InvalidData { offset: None });
+        }
+
+        let uid_block: &[u8] = &buffer[..B];
+        let uid: C::UID = self.uid_serializer.deserialize_uid(uid_block)?;
+
+        let buffer: &[u8] = &buffer[B..buffer.len() - B];
+        let (item, length) = self.bincode_deserialize(uid, buffer)?;
+
+        let remainder: usize = buffer.len() - length;
+        let entry: DBEntry<T::Item, C::UID> = DBEntry::new(uid, item);
+        Ok((entry, remainder))
+    }
+
+    /// Decodes the chunk at the current stream position straight from the
+    /// stream cache, leaving the stream after its EOE block. Fails with
+    /// `DBError::EndOfFileStream` when the stream ends before the chunk does.
+    pub fn deserialize_from_stream<const N: usize>(
+        &self,
+        stream: &mut DBFileStream<'_, N, B, C>,
+    ) -> Result<DBEntry<T::Item, C::UID>, DBError> {
+        if C::ENCODING.compact {
+            return self.deserialize_compact_from_stream(stream);
+        }
+
+        let offset: usize = stream.get_position();
+        stream.claim_chunk()?;
+        let uid_block: [u8; B] = match stream.next() {
+            Some(Ok(block)) => block,
+            _ => return Err(DBError::EndOfFileStream),
+        };
+        if uid_block == eoe_block::<B>() {
+            let offset: Option<usize> = Some(offset);
+            return Err(DBError::InvalidData { offset });
+        }
+
+        let uid: C::UID = self.uid_serializer.deserialize_uid(&uid_block)?;
+        let mut reader: DBChunkReader<'_, '_, N, B, C> = DBChunkReader::new(stream);
+        let item: Result<T::Item, DBError> = self.decode_from_payload(uid, &mut reader);
+        reader.finish()?;
+        Ok(DBEntry::new(uid, item?))
+    }
+
+    pub fn deserialize_items(&self, buffer: &[u8]) -> Result<Vec<DBItemEntry<T, C>>, DBError> {
+        let mut items: Vec<DBItemEntry<T, C>> = Vec::new();
+        if C::ENCODING.compact {
+            let mut buffer: &[u8] = buffer;
+            let mut base: u64 = 0;
+            while let Ok(header) = self.compact_header(buffer) {
+                if let Ok(uid) = self.chunk_uid(buffer, base) {
+                    if let Ok((entry, _)) = self.deserialize_with_remainder(buffer, base) {
+                        items.push(entry);
+                    }
+                    base = uid.into();
+                }
+                buffer = &buffer[header.chunk_size()..];
+            }
+            return Ok(items);
+        }
 
-        let mut uid: Option<u32> = None;
+        let mut uid: Option<C::UID> = None;
         let mut bytes: Vec<u8> = Vec::new();
-        for (idx, block) in buffer.chunks(BLOCK_SIZE).enumerate() {
+        for (idx, block) in buffer.chunks(B).enumerate() {
             if idx == 0 {
                 uid = Some(self.uid_serializer.deserialize_uid(block)?);
             }
 
-            if block == EOE_BLOCK {
-                let item: Result<T::Item, DBError> = self.bincode_deserialize(&bytes);
-                if let Ok(item) = item {
-                    if let Some(uid) = uid {
-                        let entry: DBEntry<T::Item> = DBEntry::new(uid, item);
+            if block == eoe_block::<B>() {
+                if let Some(uid) = uid {
+                    let item: Result<(T::Item, usize), DBError> =
+                        self.bincode_deserialize(uid, &bytes);
+                    if let Ok((item, _)) = item {
+                        let entry: DBEntry<T::Item, C::UID> = DBEntry::new(uid, item);
                         items.push(entry);
                     }
                 }
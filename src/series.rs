@@ -0,0 +1,452 @@
+extern crate alloc;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::db::DBClearPolicy;
+use crate::db::Database;
+use crate::error::DBError;
+use crate::error::DBErrorKind;
+use crate::iterators::DBIterator;
+use crate::structures::DBEntry;
+use crate::traits::ClockBox;
+use crate::BLOCK_SIZE;
+use crate::CACHE_SIZE;
+
+use core::hash::Hash;
+use core::ops::Range;
+
+use bincode::Decode;
+use bincode::Encode;
+
+/// Number of entries between two absolute timestamps.
+pub const KEYFRAME_INTERVAL: u64 = 64;
+
+/// Timestamp of a series record, stored ahead of its item.
+#[derive(Encode, Decode, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DBTime {
+    /// Starts a keyframe, later entries count from it.
+    Absolute(u64),
+    /// Time since the previous entry.
+    Delta(u64),
+}
+
+/// Record of a time series file.
+#[derive(Encode, Decode, Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DBTimedRecord<T> {
+    pub time: DBTime,
+    pub item: T,
+}
+
+/// Database of the records of a time series.
+pub type DBTimedDatabase<'a, T, const CACHE: usize, const BLOCK: usize, C> =
+    Database<'a, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C>;
+
+/// An entry of a time series with its resolved timestamp.
+#[derive(Debug)]
+pub struct DBTimedEntry<T, U = u32> {
+    pub uid: U,
+    pub time: u64,
+    pub item: T,
+}
+
+/// Chunk of a series file, `base` being the UID of the chunk before it.
+#[derive(Encode, Decode, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct DBTimePoint {
+    pub time: u64,
+    pub offset: usize,
+    pub base: u64,
+}
+
+/// Database the keyframes of a time series are kept in, see
+/// `DBTimeSeries::with_index`.
+pub type DBTimeIndexDatabase<'a, const CACHE: usize, const BLOCK: usize, C> =
+    Database<'a, BTreeSet<DBTimePoint>, CACHE, BLOCK, C>;
+
+/// How far the index has read into the file.
+#[derive(Debug, Clone, Copy)]
+struct DBTimeCursor {
+    /// Offset of the next chunk, `None` before the first one.
+    offset: Option<usize>,
+    base: u64,
+    time: u64,
+    entries: u64,
+    last: Option<DBTimePoint>,
+}
+
+/// Entries stamped by `clock`, stored as `DBTimedRecord` entries of
+/// `database`. Timestamps never go backwards and are stored as deltas, with
+/// an absolute keyframe every `interval` entries. `index` holds the keyframes
+/// so range queries binary search it and seek straight to the data.
+/// The index is built as the file is read, or loaded from `index_database`
+/// when one is given, and assumes entries are only added through the
+/// series, call `reload` after changing the file otherwise. A torn tail
+/// ends the scan. Capped databases return `DBError::Unsupported`.
+pub struct DBTimeSeries<
+    'a,
+    T,
+    const CACHE: usize = CACHE_SIZE,
+    const BLOCK: usize = BLOCK_SIZE,
+    C = DBStandardConfig,
+> where
+    T: Encode + Decode + Hash + Eq,
+    C: DBConfig,
+{
+    pub database: DBTimedDatabase<'a, T, CACHE, BLOCK, C>,
+    pub clock: ClockBox,
+    pub interval: u64,
+    pub index: Vec<DBTimePoint>,
+    /// Keeps `index` across opens, see `with_index`.
+    pub index_database: Option<DBTimeIndexDatabase<'a, CACHE, BLOCK, C>>,
+    cursor: DBTimeCursor,
+    /// Keyframes of `index` already in `index_database`.
+    persisted: usize,
+    /// Whether `index_database` is rebuilt instead of loaded.
+    stale: bool,
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBTimeSeries<'a, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq,
+{
+    fn start_cursor() -> DBTimeCursor {
+        DBTimeCursor {
+            offset: None,
+            base: 0,
+            time: 0,
+            entries: 0,
+            last: None,
+        }
+    }
+
+    /// Loads the keyframes kept in the index database. The last one must
+    /// still decode as the keyframe it names, otherwise the index database
+    /// is cleared and rebuilt as the file is read.
+    fn load_index(&mut self) -> Result<(), DBError> {
+        let mut points: Vec<DBTimePoint> = Vec::new();
+        match &mut self.index_database {
+            Some(database) if self.stale => {
+                if !database.read_only {
+                    database.clear(DBClearPolicy::ResetUids)?;
+                }
+                self.stale = false;
+                return Ok(());
+            }
+            Some(database) => {
+                // A torn tail of the index database ends it
+                for entry in database.get_iterator()? {
+                    match entry {
+                        Ok(entry) => points.push(entry.item),
+                        Err(_) => break,
+                    }
+                }
+            }
+            None => return Ok(()),
+        }
+
+        let last: DBTimePoint = match points.pop() {
+            Some(last) => last,
+            None => return Ok(()),
+        };
+        let found: Option<DBTime> = match self.records_from(last) {
+            Ok(mut iterator) => match iterator.next() {
+                Some(Ok(entry)) => Some(entry.item.time),
+                _ => None,
+            },
+            Err(_) => None,
+        };
+        if found != Some(DBTime::Absolute(last.time)) {
+            self.stale = true;
+            return self.load_index();
+        }
+
+        // The scan picks the last keyframe up again from the file, it is
+        // already persisted
+        self.cursor = DBTimeCursor {
+            offset: Some(last.offset),
+            base: last.base,
+            time: last.time,
+            entries: points.len() as u64 * self.interval.max(1),
+            last: None,
+        };
+        self.persisted = points.len() + 1;
+        self.index = points;
+        Ok(())
+    }
+
+    /// Appends the keyframes found since the last call to the index database.
+    fn persist_index(&mut self) -> Result<(), DBError> {
+        let database: &mut DBTimeIndexDatabase<'a, CACHE, BLOCK, C> = match &mut self.index_database
+        {
+            Some(database) if !database.read_only => database,
+            _ => return Ok(()),
+        };
+        let points: &[DBTimePoint] = self.index.get(self.persisted..).unwrap_or_default();
+        if !points.is_empty() {
+            database.add_entries_iter(points.iter().copied())?;
+            self.persisted = self.index.len();
+        }
+        Ok(())
+    }
+
+    /// Indexes the entries added since the last call.
+    fn catch_up(&mut self) -> Result<(), DBError> {
+        if self.database.capacity.is_some() {
+            return Err(DBError::Unsupported);
+        }
+        if self.cursor.offset.is_none() && self.index.is_empty() {
+            self.load_index()?;
+        }
+
+        let cursor: &mut DBTimeCursor = &mut self.cursor;
+        let mut iterator: DBIterator<'_, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C> =
+            self.database.get_iterator()?;
+        if let Some(offset) = cursor.offset {
+            match iterator.seek(offset, cursor.base) {
+                Err(DBError::EndOfFileStream) => return Ok(()),
+                result => result?,
+            };
+        }
+
+        loop {
+            let offset: usize = iterator.position();
+            // A torn tail or a last entry that does not read is left for the
+            // next call, it may still be in the middle of being written
+            let entry: DBEntry<DBTimedRecord<T>, C::UID> = match iterator.next() {
+                Some(Err(error)) if error.kind() == DBErrorKind::Corrupt => {
+                    match iterator.is_last(offset) {
+                        Ok(true) => break,
+                        _ => return Err(error),
+                    }
+                }
+                Some(entry) => entry?,
+                None => break,
+            };
+            let time: u64 = match entry.item.time {
+                DBTime::Absolute(time) => time,
+                DBTime::Delta(delta) => cursor.time.saturating_add(delta),
+            };
+            let base: u64 = cursor.base;
+            let point: DBTimePoint = DBTimePoint { time, offset, base };
+            if let DBTime::Absolute(_) = entry.item.time {
+                self.index.push(point);
+            }
+            cursor.offset = Some(iterator.position());
+            cursor.base = entry.uid.into();
+            cursor.time = time;
+            cursor.entries += 1;
+            cursor.last = Some(point);
+        }
+        drop(iterator);
+        self.persist_index()
+    }
+
+    /// Iterator over the records from `point` on.
+    fn records_from(
+        &mut self,
+        point: DBTimePoint,
+    ) -> Result<DBIterator<'_, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C>, DBError> {
+        let mut iterator: DBIterator<'_, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C> =
+            self.database.get_iterator()?;
+        iterator.seek(point.offset, point.base)?;
+        Ok(iterator)
+    }
+}
+
+impl<'a, T, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBTimeSeries<'a, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq,
+{
+    pub fn new(database: DBTimedDatabase<'a, T, CACHE, BLOCK, C>, clock: ClockBox) -> Self {
+        let interval: u64 = KEYFRAME_INTERVAL;
+        let index: Vec<DBTimePoint> = Vec::new();
+        let index_database: Option<DBTimeIndexDatabase<'a, CACHE, BLOCK, C>> = None;
+        let cursor: DBTimeCursor = Self::start_cursor();
+        let persisted: usize = 0;
+        let stale: bool = false;
+        Self {
+            database,
+            clock,
+            interval,
+            index,
+            index_database,
+            cursor,
+            persisted,
+            stale,
+        }
+    }
+
+    /// Keeps the keyframes in `index_database`, so the first query after
+    /// opening reads the index and the entries after its last keyframe
+    /// instead of the whole file. The index database is only written when
+    /// it is writable.
+    pub fn with_index(mut self, index_database: DBTimeIndexDatabase<'a, CACHE, BLOCK, C>) -> Self {
+        self.index_database = Some(index_database);
+        self
+    }
+
+    /// Adds `item` stamped with the clock, returning its timestamp. A clock
+    /// behind the last entry stamps it with the time of that entry.
+    pub fn add_entry(&mut self, item: &T) -> Result<u64, DBError>
+    where
+        T: Clone,
+    {
+        self.catch_up()?;
+        let last: u64 = self.cursor.time;
+        let now: u64 = self.clock.now().max(last);
+        let time: DBTime = match self.cursor.entries % self.interval.max(1) {
+            0 => DBTime::Absolute(now),
+            _ => DBTime::Delta(now - last),
+        };
+        let item: T = item.clone();
+        self.database.add_entry(&DBTimedRecord { time, item })?;
+        Ok(now)
+    }
+
+    /// Iterates over the entries stamped within `range`.
+    pub fn range_time(
+        &mut self,
+        range: Range<u64>,
+    ) -> Result<DBTimeIterator<'_, T, CACHE, BLOCK, C>, DBError> {
+        self.catch_up()?;
+        // Entries before the last keyframe ahead of the range are all earlier
+        let after: usize = self.index.partition_point(|point| point.time < range.start);
+        let point: Option<DBTimePoint> = self.index.get(after.saturating_sub(1)).copied();
+        let iterator: Option<DBIterator<'_, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C>> =
+            match point {
+                Some(point) => Some(self.records_from(point)?),
+                None => None,
+            };
+        Ok(DBTimeIterator::new(iterator, range))
+    }
+
+    /// Returns the most recent entry.
+    pub fn latest(&mut self) -> Result<Option<DBTimedEntry<T, C::UID>>, DBError> {
+        self.catch_up()?;
+        let point: DBTimePoint = match self.cursor.last {
+            Some(point) => point,
+            None => return Ok(None),
+        };
+        let entry: Result<DBEntry<DBTimedRecord<T>, C::UID>, DBError> =
+            match self.records_from(point)?.next() {
+                Some(entry) => entry,
+                None => Err(DBError::EntryNotFound {
+                    uid: Some(point.base),
+                }),
+            };
+        let entry: DBEntry<DBTimedRecord<T>, C::UID> = entry?;
+        Ok(Some(DBTimedEntry {
+            uid: entry.uid,
+            time: point.time,
+            item: entry.item.item,
+        }))
+    }
+
+    /// Forgets the index, for it to be rebuilt after the file was changed
+    /// other than through `add_entry`. The index database is cleared on the
+    /// next query.
+    pub fn reload(&mut self) {
+        self.index.clear();
+        self.cursor = Self::start_cursor();
+        self.persisted = 0;
+        self.stale = true;
+    }
+
+    /// Closes the underlying database and the index database, see
+    /// `Database::close`.
+    pub fn close(self) -> Result<(), DBError> {
+        if let Some(index_database) = self.index_database {
+            index_database.close()?;
+        }
+        self.database.close()
+    }
+}
+
+/// Iterates over the entries of a `DBTimeSeries` within a time range,
+/// stopping at the first entry past it.
+pub struct DBTimeIterator<'s, T, const CACHE: usize, const BLOCK: usize, C>
+where
+    T: Encode + Decode + Hash + Eq,
+    C: DBConfig,
+{
+    iterator: Option<DBIterator<'s, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C>>,
+    range: Range<u64>,
+    time: u64,
+    step: Option<u64>,
+    next_bucket: u64,
+}
+
+impl<'s, T, const CACHE: usize, const BLOCK: usize, C: DBConfig>
+    DBTimeIterator<'s, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq,
+{
+    pub fn new(
+        iterator: Option<DBIterator<'s, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C>>,
+        range: Range<u64>,
+    ) -> Self {
+        let time: u64 = 0;
+        let step: Option<u64> = None;
+        let next_bucket: u64 = range.start;
+        Self {
+            iterator,
+            range,
+            time,
+            step,
+            next_bucket,
+        }
+    }
+
+    /// Returns only the first entry of every `step` long bucket, counted
+    /// from the start of the range.
+    pub fn downsample(mut self, step: u64) -> Self {
+        self.step = Some(step.max(1));
+        self
+    }
+}
+
+impl<'s, T, const CACHE: usize, const BLOCK: usize, C: DBConfig> Iterator
+    for DBTimeIterator<'s, T, CACHE, BLOCK, C>
+where
+    T: Encode + Decode + Hash + Eq,
+{
+    type Item = Result<DBTimedEntry<T, C::UID>, DBError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let iterator: &mut DBIterator<'s, BTreeSet<DBTimedRecord<T>>, CACHE, BLOCK, C> =
+            self.iterator.as_mut()?;
+        for entry in iterator.by_ref() {
+            let entry: DBEntry<DBTimedRecord<T>, C::UID> = match entry {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+            self.time = match entry.item.time {
+                DBTime::Absolute(time) => time,
+                DBTime::Delta(delta) => self.time.saturating_add(delta),
+            };
+            if self.time >= self.range.end {
+                break;
+            }
+            if self.time < self.next_bucket {
+                continue;
+            }
+            if let Some(step) = self.step {
+                let buckets: u64 = (self.time - self.range.start) / step + 1;
+                self.next_bucket = self
+                    .range
+                    .start
+                    .saturating_add(buckets.saturating_mul(step));
+            }
+            return Some(Ok(DBTimedEntry {
+                uid: entry.uid,
+                time: self.time,
+                item: entry.item.item,
+            }));
+        }
+        self.iterator = None;
+        None
+    }
+}
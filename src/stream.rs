@@ -1,17 +1,35 @@
 extern crate alloc;
 use alloc::vec::Vec;
 
+use crate::compact::DBCompactHeader;
+use crate::compact::VARINT_MAX;
+use crate::config::DBConfig;
+use crate::config::DBStandardConfig;
+use crate::config::DBUid;
+use crate::eoe_block;
 use crate::error::DBError;
+use crate::format::DBFormatHeader;
+use crate::memory::DBMemory;
 use crate::serializer::UIDSerializer;
 use crate::traits::FileBox;
 use crate::BLOCK_SIZE;
-use crate::EOE_BLOCK;
+
+use core::marker::PhantomData;
+
+use bincode::de::read::Reader;
+use bincode::error::DecodeError;
 
 pub struct Range {
     start: usize,
     end: usize,
 }
 
+impl Default for Range {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Range {
     pub fn new() -> Self {
         let start: usize = 0;
@@ -46,9 +64,9 @@ impl<'a, const N: usize> DBStreamCache<'a, N> {
         self.cache_range.start + self.cache_offset
     }
 
-    fn increment_cache_offset(&mut self) {
+    fn increment_cache_offset(&mut self, length: usize) {
         if self.cache_offset < self.cache_range.end {
-            self.cache_offset += BLOCK_SIZE;
+            self.cache_offset += length;
         }
     }
 
@@ -90,7 +108,7 @@ impl<'a, const N: usize> DBStreamCache<'a, N> {
         if self.cache_written && cache_length > 0 {
             self.file.seek(self.cache_range.start)?;
             let buffer: &[u8] = &self.cache_buffer[..cache_length];
-            self.file.write(buffer)?;
+            self.file.write_all(buffer)?;
             self.file.seek(start)?;
             self.cache_written = false;
         }
@@ -122,22 +140,25 @@ impl<'a, const N: usize> DBStreamCache<'a, N> {
 
         self.copy_into_cache(buffer);
         self.cache_range.set_end(start + buffer.len());
-        return Ok(buffer.len());
+        Ok(buffer.len())
     }
 
     fn write_to_file(&mut self, buffer: &[u8]) -> Result<usize, DBError> {
-        let length: usize = self.file.write(buffer)?;
-        self.position += length;
+        let start: usize = self.get_cache_position();
+        self.flush_cache_buffer()?;
+
+        self.file.seek(start)?;
+        self.file.write_all(buffer)?;
+        self.position = start + buffer.len();
+
         self.cache_from_start(self.position - N)?;
         self.cache_offset = N;
-        Ok(length)
+        Ok(buffer.len())
     }
 }
 
 impl<'a, const N: usize> DBStreamCache<'a, N> {
     pub fn new(file: &'a mut FileBox) -> Self {
-        file.seek(0).unwrap();
-
         let position: usize = 0;
         let cache_offset: usize = 0;
         let cache_range: Range = Range::new();
@@ -154,23 +175,77 @@ impl<'a, const N: usize> DBStreamCache<'a, N> {
         }
     }
 
-    pub fn read(&mut self, buffer: &mut [u8; BLOCK_SIZE]) -> Result<(), DBError> {
+    pub fn read<const B: usize>(&mut self, buffer: &mut [u8; B]) -> Result<(), DBError> {
         let cache_length: usize = self.cache_range.length();
-        if self.cache_offset < cache_length && cache_length > 0 {
-            let start: usize = self.cache_offset;
-            let end: usize = start + BLOCK_SIZE;
+        let start: usize = self.cache_offset;
+        let end: usize = start + B;
 
+        if end <= cache_length {
             buffer.copy_from_slice(&self.cache_buffer[start..end]);
-            self.increment_cache_offset();
+            self.increment_cache_offset(B);
             return Ok(());
         }
 
         self.flush_cache_buffer()?;
-        self.cache_from_start(self.position)?;
+        self.cache_from_start(self.get_cache_position())?;
+
+        // A trailing partial block can never be completed by refilling
+        if self.cache_range.length() < B {
+            return Err(DBError::EndOfFileStream);
+        }
         self.read(buffer)?;
         Ok(())
     }
 
+    /// Reads exactly `buffer.len()` bytes, failing with
+    /// `DBError::EndOfFileStream` when the file ends first.
+    pub fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), DBError> {
+        let mut filled: usize = 0;
+        while filled < buffer.len() {
+            let available: usize = self.cache_range.length().saturating_sub(self.cache_offset);
+            if available == 0 {
+                self.flush_cache_buffer()?;
+                self.cache_from_start(self.get_cache_position())?;
+                continue;
+            }
+
+            let length: usize = available.min(buffer.len() - filled);
+            let start: usize = self.cache_offset;
+            buffer[filled..filled + length]
+                .copy_from_slice(&self.cache_buffer[start..start + length]);
+            self.cache_offset += length;
+            filled += length;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), DBError> {
+        self.flush_cache_buffer()
+    }
+
+    /// Moves everything from `from` to the end of the file down to `to`
+    /// through the cache buffer and truncates the file after it.
+    pub fn move_tail(&mut self, from: usize, to: usize) -> Result<usize, DBError> {
+        self.flush_cache_buffer()?;
+        let mut read_position: usize = from;
+        let mut write_position: usize = to;
+        loop {
+            self.file.seek(read_position)?;
+            let length: usize = self.file.read(&mut self.cache_buffer)?;
+            if length == 0 {
+                break;
+            }
+
+            self.file.seek(write_position)?;
+            self.file.write_all(&self.cache_buffer[..length])?;
+            read_position += length;
+            write_position += length;
+        }
+
+        self.set_len(write_position)?;
+        Ok(write_position)
+    }
+
     pub fn write(&mut self, buffer: &[u8]) -> Result<usize, DBError> {
         if buffer.len() > N {
             return self.write_to_file(buffer);
@@ -190,8 +265,14 @@ impl<'a, const N: usize> DBStreamCache<'a, N> {
                 error => return Err(error),
             }
         }
-        self.write(buffer)?;
-        Ok(buffer.len())
+
+        if self.buffer_fits_cache(buffer) {
+            self.copy_into_cache(buffer);
+            return Ok(buffer.len());
+        }
+
+        // The write runs past the end of the file
+        self.cache_write_end(buffer, start)
     }
 
     pub fn seek_from_start(&mut self, start: usize) -> Result<usize, DBError> {
@@ -219,27 +300,241 @@ impl<'a, const N: usize> DBStreamCache<'a, N> {
     }
 }
 
-pub struct DBFileStream<'a, const N: usize> {
+/// Block stream over a database file, reading through a cache of `N` bytes in
+/// blocks of `B` bytes. `C` is the encoding the file was written with, which
+/// decides the size of the format header and the chunk layout.
+///
+/// Compact chunks store their UID relative to the chunk before them, so the
+/// stream keeps the UID of the last chunk it moved past while walking the
+/// file from the first chunk.
+pub struct DBFileStream<'a, const N: usize, const B: usize = BLOCK_SIZE, C = DBStandardConfig> {
     stream: DBStreamCache<'a, N>,
     uid_serializer: UIDSerializer,
+    memory: Option<&'a DBMemory>,
+    base: u64,
+    marker: PhantomData<C>,
 }
 
-impl<'a, const N: usize> DBFileStream<'a, N> {
+impl<'a, const N: usize, const B: usize, C: DBConfig> DBFileStream<'a, N, B, C> {
+    /// Starts at byte offset `start`.
+    fn at(file: &'a mut FileBox, start: usize) -> Self {
+        const { assert!(N >= B, "cache must hold at least one block") };
+        let mut stream: DBStreamCache<'_, N> = DBStreamCache::new(file);
+        stream.set_cache([0; N], start, start);
+        let uid_serializer: UIDSerializer = UIDSerializer::new();
+        let memory: Option<&DBMemory> = None;
+        let base: u64 = 0;
+        let marker: PhantomData<C> = PhantomData;
+        DBFileStream {
+            stream,
+            uid_serializer,
+            memory,
+            base,
+            marker,
+        }
+    }
+
+    fn invalid_at(offset: usize) -> DBError {
+        DBError::InvalidData {
+            offset: Some(offset),
+        }
+    }
+
+    /// Error for the chunk at `start` the file ended inside of, which is
+    /// `DBError::EndOfFileStream` when no chunk starts there at all.
+    fn end_or_torn(&mut self, start: usize) -> DBError {
+        let result: Result<(), DBError> = self
+            .stream
+            .flush_cache_buffer()
+            .and_then(|_| self.stream.cache_from_start(start));
+        match result {
+            Ok(()) => Self::invalid_at(start),
+            Err(error) => error,
+        }
+    }
+
+    /// Reads the header of the compact chunk at the current position, failing
+    /// with `DBError::EndOfFileStream` when the file ends before it starts.
+    fn read_compact_header(&mut self) -> Result<DBCompactHeader, DBError> {
+        let start: usize = self.get_position();
+        let mut bytes: [u8; 2 * VARINT_MAX] = [0; 2 * VARINT_MAX];
+        let mut length: usize = 0;
+        let mut varints: usize = 0;
+
+        while varints < 2 {
+            if length == bytes.len() {
+                return Err(Self::invalid_at(start));
+            }
+
+            let mut byte: [u8; 1] = [0];
+            match self.stream.read_bytes(&mut byte) {
+                Ok(()) => {}
+                Err(DBError::EndOfFileStream) if length == 0 => {
+                    return Err(DBError::EndOfFileStream);
+                }
+                Err(DBError::EndOfFileStream) => return Err(Self::invalid_at(start)),
+                Err(error) => return Err(error),
+            }
+
+            bytes[length] = byte[0];
+            length += 1;
+            if byte[0] & 0x80 == 0 {
+                varints += 1;
+            }
+        }
+
+        let header: Option<DBCompactHeader> =
+            DBCompactHeader::decode(&bytes[..length], C::ENCODING.payload_limit());
+        header.ok_or(Self::invalid_at(start))
+    }
+
+    /// Moves past the compact chunk at the current position after making
+    /// sure its payload is in the file, failing with
+    /// `DBError::EndOfFileStream` when the file ends before the payload does.
+    /// On failure the stream stays at the start of the chunk.
+    fn pass_compact_chunk(&mut self) -> Result<(usize, DBCompactHeader), DBError> {
+        let start: usize = self.get_position();
+        let header: Result<DBCompactHeader, DBError> = self.read_compact_header();
+
+        let result: Result<DBCompactHeader, DBError> = header.and_then(|header| {
+            let mut byte: [u8; 1] = [0];
+            let end: usize = start + header.chunk_size();
+            self.stream.seek_from_start(end - 1)?;
+            self.stream.read_bytes(&mut byte)?;
+            Ok(header)
+        });
+
+        match result {
+            Ok(header) => Ok((start, header)),
+            Err(error) => {
+                let _ = self.stream.seek_from_start(start);
+                match error {
+                    DBError::InvalidData { .. } => Err(Self::invalid_at(start)),
+                    error => Err(error),
+                }
+            }
+        }
+    }
+
+    /// Like `pass_compact_chunk`, but only the end of the file fails with
+    /// `DBError::EndOfFileStream`, a chunk it ends inside with
+    /// `DBError::InvalidData`.
+    fn walk_compact_chunk(&mut self) -> Result<(usize, DBCompactHeader), DBError> {
+        let start: usize = self.get_position();
+        match self.pass_compact_chunk() {
+            Err(DBError::EndOfFileStream) => Err(self.end_or_torn(start)),
+            result => result,
+        }
+    }
+
+    fn advance_base(&mut self, delta: u64, start: usize) -> Result<C::UID, DBError> {
+        let uid: Option<u64> = self.base.checked_add(delta);
+        let uid: C::UID = uid
+            .and_then(C::UID::from_u64)
+            .ok_or(Self::invalid_at(start))?;
+        self.base = uid.into();
+        Ok(uid)
+    }
+
+    fn compact_chunk_bounds(&mut self) -> Result<(usize, usize), DBError> {
+        let (start, header) = self.walk_compact_chunk()?;
+        self.stream.seek_from_start(start)?;
+        Ok((start, start + header.chunk_size()))
+    }
+
+    fn skip_compact_chunk(&mut self) -> Result<[u8; B], DBError> {
+        let (start, header) = self.walk_compact_chunk()?;
+        let uid: C::UID = self.advance_base(header.delta, start)?;
+        Ok(self.uid_serializer.serialize_uid(uid))
+    }
+
+    fn iter_compact_chunk(&mut self) -> Result<Vec<u8>, DBError> {
+        let (start, header) = self.pass_compact_chunk()?;
+        self.advance_base(header.delta, start)?;
+
+        // The chunk is already skipped when it does not fit the budget
+        let size: usize = header.chunk_size();
+        self.claim(size)?;
+
+        let mut data: Vec<u8> = alloc::vec![0; size];
+        self.stream.seek_from_start(start)?;
+        self.stream.read_bytes(&mut data)?;
+        Ok(data)
+    }
+
+    /// Cuts the compact chunk at the current position out of the file. The
+    /// chunk after it takes over its UID distance, so the UIDs that follow
+    /// shift down the same way `rebuild_database` renumbers them.
+    fn remove_compact_chunk(&mut self) -> Result<(), DBError> {
+        let (start, header) = self.walk_compact_chunk()?;
+        let next: Result<(usize, DBCompactHeader), DBError> = self.pass_compact_chunk();
+        self.stream.flush()?;
+
+        let (next_start, next_header) = match next {
+            Ok(next) => next,
+            Err(_) => {
+                let end: usize = start + header.chunk_size();
+                self.stream.move_tail(end, start)?;
+                return Ok(());
+            }
+        };
+
+        let mut buffer: Vec<u8> = Vec::new();
+        DBCompactHeader::new(header.delta, next_header.length).encode(&mut buffer);
+        self.stream.file.seek(start)?;
+        self.stream.file.write_all(&buffer)?;
+
+        let payload: usize = next_start + next_header.size;
+        self.stream.move_tail(payload, start + buffer.len())?;
+        Ok(())
+    }
+
+    fn claim(&self, size: usize) -> Result<(), DBError> {
+        match self.memory {
+            Some(memory) => memory.claim(size),
+            None => Ok(()),
+        }
+    }
+
+    fn has_budget(&self) -> bool {
+        self.memory.and_then(|memory| memory.budget()).is_some()
+    }
+
+    /// Claims every chunk from the current position to the end of the file,
+    /// so that moving them cannot run out of budget halfway through.
+    fn claim_remaining(&mut self) -> Result<(), DBError> {
+        let position: usize = self.get_position();
+        let base: u64 = self.base;
+        let mut found: bool = false;
+        while let Ok((start, end)) = self.get_chunk_bounds() {
+            self.claim(end - start)?;
+            self.skip_chunk()?;
+            found = true;
+        }
+
+        // Without a chunk the position is the end of the file, no need to return
+        if found {
+            self.seek(position)?;
+        }
+        self.base = base;
+        Ok(())
+    }
+
     fn rebuild_database(
         &mut self,
         st_pos1: &mut usize,
         en_pos1: &mut usize,
-        mut uid: u32,
+        mut uid: C::UID,
     ) -> Result<(), DBError> {
         while let Ok((_, en_pos2)) = self.get_chunk_bounds() {
             // Create UID block
-            let uid_block: [u8; BLOCK_SIZE] = self.uid_serializer.serialize_uid(uid);
+            let uid_block: [u8; B] = self.uid_serializer.serialize_uid(uid);
 
             // Get next chunk
             let mut next_chunk: Vec<u8> = self.iter_chunk()?;
 
             // Overwrite next chunk UID
-            next_chunk[..BLOCK_SIZE].copy_from_slice(&uid_block);
+            next_chunk[..B].copy_from_slice(&uid_block);
 
             // Seek to current chunk start
             self.stream.seek_from_start(*st_pos1)?;
@@ -248,95 +543,481 @@ impl<'a, const N: usize> DBFileStream<'a, N> {
             self.stream.write(&next_chunk)?;
 
             // Seek to the end of next chunk
-            self.stream.seek_from_start(en_pos2)?;
+            let result: Result<usize, DBError> = self.stream.seek_from_start(en_pos2);
 
             // Set position of next chunk
-            *st_pos1 = *st_pos1 + next_chunk.len();
+            *st_pos1 += next_chunk.len();
             *en_pos1 = en_pos2;
 
-            uid += 1;
+            // The last chunk ends the file, there is nothing left to move
+            match result {
+                Err(DBError::EndOfFileStream) => break,
+                result => result?,
+            };
+
+            uid = uid.checked_next().ok_or(DBError::UIDOverflow)?;
         }
         Ok(())
     }
 }
 
-impl<'a, const N: usize> DBFileStream<'a, N> {
+impl<'a, const N: usize, const B: usize, C: DBConfig> DBFileStream<'a, N, B, C> {
+    /// Starts after the format header every file of `C` has, where the ring
+    /// header of a capped file follows. See `open` for the first chunk.
     pub fn new(file: &'a mut FileBox) -> Self {
-        let stream: DBStreamCache<'_, N> = DBStreamCache::new(file);
-        let uid_serializer: UIDSerializer = UIDSerializer::new();
-        DBFileStream {
-            stream,
-            uid_serializer,
-        }
+        let start: usize = DBFormatHeader::<B, C>::SIZE;
+        Self::at(file, start)
+    }
+
+    /// Starts at the first chunk, after the format header of the file.
+    pub fn open(file: &'a mut FileBox) -> Result<Self, DBError> {
+        let start: usize = DBFormatHeader::<B, C>::start(file)?;
+        Ok(Self::at(file, start))
+    }
+
+    /// Checks buffered and decoded chunks against `memory` and records them.
+    pub fn set_memory(&mut self, memory: &'a DBMemory) {
+        self.memory = Some(memory);
     }
 
+    /// Returns where the chunk at the current position starts and ends,
+    /// failing like `skip_chunk`.
     pub fn get_chunk_bounds(&mut self) -> Result<(usize, usize), DBError> {
+        if C::ENCODING.compact {
+            return self.compact_chunk_bounds();
+        }
+
         let cache_st: usize = self.stream.cache_range.start + self.stream.cache_offset;
+        loop {
+            let mut block: [u8; B] = [0; B];
+            match self.stream.read(&mut block) {
+                Ok(()) if block != eoe_block::<B>() => continue,
+                Ok(()) => {}
+                Err(DBError::EndOfFileStream) => return Err(self.end_or_torn(cache_st)),
+                Err(error) => return Err(error),
+            }
 
-        for block in self.into_iter() {
-            if let Ok(block) = block {
-                if block == EOE_BLOCK {
-                    break;
-                }
-                continue;
+            let cache_en: usize = self.stream.cache_range.start + self.stream.cache_offset;
+            self.stream.seek_from_start(cache_st)?;
+            return Ok((cache_st, cache_en));
+        }
+    }
+
+    pub fn seek(&mut self, position: usize) -> Result<usize, DBError> {
+        self.stream.seek_from_start(position)
+    }
+
+    /// Moves to the chunk at `position`, `base` being the UID of the chunk
+    /// before it, which compact chunks count from.
+    pub fn seek_chunk(&mut self, position: usize, base: u64) -> Result<usize, DBError> {
+        self.base = base;
+        self.seek(position)
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.stream.get_cache_position()
+    }
+
+    /// Writes `data` after the last chunk. A chunk the file ends inside is
+    /// not written over, it fails with `DBError::InvalidData`.
+    pub fn append_end(&mut self, data: &[u8]) -> Result<(), DBError> {
+        loop {
+            match self.skip_chunk() {
+                Ok(_) => {}
+                Err(DBError::EndOfFileStream) => break,
+                Err(error) => return Err(error),
             }
-            return Err(DBError::InvalidData);
+        }
+        self.stream.write(data)?;
+        self.stream.flush()
+    }
+
+    pub fn flush(&mut self) -> Result<(), DBError> {
+        self.stream.flush()
+    }
+
+    /// Returns the UID block of the last chunk, without buffering any chunk,
+    /// and `None` for a file without chunks. Fails like `skip_chunk`.
+    pub fn last_uid_block(&mut self) -> Result<Option<[u8; B]>, DBError> {
+        let mut last_block: Option<[u8; B]> = None;
+        loop {
+            match self.skip_chunk() {
+                Ok(block) => last_block = Some(block),
+                Err(DBError::EndOfFileStream) => return Ok(last_block),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Moves past the chunk at the current position without buffering it,
+    /// returning its UID block. Compact chunks return a UID block holding
+    /// their UID. Fails with `DBError::EndOfFileStream` at the end of the
+    /// file and with `DBError::InvalidData` for a chunk the file ends inside.
+    pub fn skip_chunk(&mut self) -> Result<[u8; B], DBError> {
+        if C::ENCODING.compact {
+            return self.skip_compact_chunk();
         }
 
-        let cache_en: usize = self.stream.cache_range.start + self.stream.cache_offset;
-        self.stream.seek_from_start(cache_st)?;
-        Ok((cache_st, cache_en))
+        let start: usize = self.stream.get_cache_position();
+        let mut uid_block: Option<[u8; B]> = None;
+        loop {
+            let mut block: [u8; B] = [0; B];
+            match self.stream.read(&mut block) {
+                Ok(()) => {}
+                Err(DBError::EndOfFileStream) => return Err(self.end_or_torn(start)),
+                Err(error) => return Err(error),
+            }
+
+            let first: [u8; B] = *uid_block.get_or_insert(block);
+            if block == eoe_block::<B>() {
+                return Ok(first);
+            }
+        }
     }
 
-    pub fn append_end(&mut self, data: &[u8]) {
-        while let Ok(_) = self.iter_chunk() {}
-        let _ = self.stream.write(data);
+    /// Moves past the chunk at the current position like `skip_chunk`,
+    /// returning `false` at the end of the file. Read errors are passed on,
+    /// and a chunk the file ends inside fails with `DBError::EndOfFileStream`.
+    pub fn pass_chunk(&mut self) -> Result<bool, DBError> {
+        let start: usize = self.get_position();
+        if C::ENCODING.compact {
+            let header: DBCompactHeader = match self.read_compact_header() {
+                Ok(header) => header,
+                Err(DBError::EndOfFileStream) => return Ok(false),
+                Err(error) => return Err(error),
+            };
+            let mut byte: [u8; 1] = [0];
+            self.stream
+                .seek_from_start(start + header.chunk_size() - 1)?;
+            self.stream.read_bytes(&mut byte)?;
+            self.advance_base(header.delta, start)?;
+            return Ok(true);
+        }
+
+        let mut first: bool = true;
+        loop {
+            let mut block: [u8; B] = [0; B];
+            match self.stream.read_bytes(&mut block) {
+                Err(DBError::EndOfFileStream) if self.get_position() == start => return Ok(false),
+                Err(error) => return Err(error),
+                Ok(()) if block != eoe_block::<B>() => first = false,
+                Ok(()) if first => return Err(Self::invalid_at(start)),
+                Ok(()) => return Ok(true),
+            }
+        }
     }
 
-    pub fn last_chunk(&mut self) -> Option<Vec<u8>> {
-        let mut last_chunk: Option<Vec<u8>> = None;
-        while let Ok(chunk) = self.iter_chunk() {
-            last_chunk = Some(chunk);
+    /// Checks the chunk at the current position against the memory budget
+    /// before it is decoded, skipping it when it does not fit. Without a
+    /// budget this does not read anything.
+    pub fn claim_chunk(&mut self) -> Result<(), DBError> {
+        if !self.has_budget() {
+            return Ok(());
+        }
+
+        // A chunk without an EOE block could never be decoded either
+        let (start, end) = match self.get_chunk_bounds() {
+            Ok(bounds) => bounds,
+            Err(_) => return Err(DBError::EndOfFileStream),
+        };
+        if let Err(error) = self.claim(end - start) {
+            self.skip_chunk()?;
+            return Err(error);
         }
-        last_chunk
+        Ok(())
     }
 
+    /// Buffers the chunk at the current position. A chunk that exceeds the
+    /// memory budget is skipped with `DBError::MemoryBudgetExceeded`, and the
+    /// end of the file, also inside a chunk, fails with
+    /// `DBError::EndOfFileStream`.
     pub fn iter_chunk(&mut self) -> Result<Vec<u8>, DBError> {
-        let mut data: Vec<u8> = Vec::new();
+        if C::ENCODING.compact {
+            return self.iter_compact_chunk();
+        }
 
-        for block in self.into_iter() {
-            if let Ok(block) = block {
-                data.extend(block);
-                if block == EOE_BLOCK {
-                    return Ok(data);
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            let mut block: [u8; B] = [0; B];
+            self.stream.read(&mut block)?;
+            if let Err(error) = self.claim(data.len() + B) {
+                if block != eoe_block::<B>() {
+                    self.skip_chunk()?;
                 }
-                continue;
+                return Err(error);
+            }
+
+            data.extend(block);
+            if block == eoe_block::<B>() {
+                return Ok(data);
             }
         }
-        Err(DBError::InvalidData)
     }
 
     pub fn remove_chunk(&mut self) -> Result<(), DBError> {
+        if C::ENCODING.compact {
+            return self.remove_compact_chunk();
+        }
+
         let (mut st_pos1, mut en_pos1) = self.get_chunk_bounds()?;
 
-        let current_chunk: Vec<u8> = self.iter_chunk()?;
-        let current_uid_block: &[u8] = &current_chunk[..BLOCK_SIZE];
-        let current_uid: u32 = self.uid_serializer.deserialize_uid(current_uid_block)?;
+        let current_uid_block: [u8; B] = self.skip_chunk()?;
+        let current_uid: C::UID = self.uid_serializer.deserialize_uid(&current_uid_block)?;
+
+        if self.has_budget() {
+            self.claim_remaining()?;
+        }
 
-        let _ = self.rebuild_database(&mut st_pos1, &mut en_pos1, current_uid);
+        self.rebuild_database(&mut st_pos1, &mut en_pos1, current_uid)?;
         self.stream.set_len(st_pos1)?;
         Ok(())
     }
+
+    /// Reads the header of the compact chunk at the current position,
+    /// returning its UID and payload length and leaving the stream at the
+    /// payload. Fails with `DBError::EndOfFileStream` at the end of the file.
+    pub fn next_compact_header(&mut self) -> Result<(C::UID, usize), DBError> {
+        let start: usize = self.get_position();
+        let header: DBCompactHeader = self.read_compact_header()?;
+        let uid: C::UID = self.advance_base(header.delta, start)?;
+        Ok((uid, header.length))
+    }
+}
+
+/// bincode `Reader` over the payload of the chunk at the current stream
+/// position. Bytes are copied straight out of the stream cache, so decoding
+/// never needs the whole encoded chunk in memory. Reads stop at the EOE block,
+/// or after the payload length of a compact chunk.
+pub struct DBChunkReader<'s, 'a, const N: usize, const B: usize, C> {
+    stream: &'s mut DBFileStream<'a, N, B, C>,
+    block: [u8; B],
+    offset: usize,
+    length: usize,
+    remaining: Option<usize>,
+    ended: bool,
+    truncated: bool,
+}
+
+impl<'s, 'a, const N: usize, const B: usize, C: DBConfig> DBChunkReader<'s, 'a, N, B, C> {
+    fn next_block(&mut self, additional: usize) -> Result<(), DecodeError> {
+        if !self.ended {
+            match self.stream.next() {
+                Some(Ok(block)) if block != eoe_block::<B>() => {
+                    self.block = block;
+                    self.offset = 0;
+                    return Ok(());
+                }
+                Some(Ok(_)) => self.ended = true,
+                _ => {
+                    self.ended = true;
+                    self.truncated = true;
+                }
+            }
+        }
+        Err(DecodeError::UnexpectedEnd { additional })
+    }
+
+    fn read_payload(&mut self, bytes: &mut [u8], remaining: usize) -> Result<(), DecodeError> {
+        if bytes.len() > remaining || self.truncated {
+            let additional: usize = bytes.len() - remaining.min(bytes.len());
+            return Err(DecodeError::UnexpectedEnd { additional });
+        }
+
+        if self.stream.stream.read_bytes(bytes).is_err() {
+            self.truncated = true;
+            let additional: usize = bytes.len();
+            return Err(DecodeError::UnexpectedEnd { additional });
+        }
+        self.remaining = Some(remaining - bytes.len());
+        self.length += bytes.len();
+        Ok(())
+    }
+
+    fn skip_payload(&mut self, mut remaining: usize) {
+        let mut buffer: [u8; B] = [0; B];
+        while remaining > 0 && !self.truncated {
+            let length: usize = remaining.min(B);
+            if self
+                .stream
+                .stream
+                .read_bytes(&mut buffer[..length])
+                .is_err()
+            {
+                self.truncated = true;
+            }
+            remaining -= length;
+        }
+        self.remaining = Some(0);
+    }
+}
+
+impl<'s, 'a, const N: usize, const B: usize, C: DBConfig> DBChunkReader<'s, 'a, N, B, C> {
+    pub fn new(stream: &'s mut DBFileStream<'a, N, B, C>) -> Self {
+        Self {
+            stream,
+            block: [0; B],
+            offset: B,
+            length: 0,
+            remaining: None,
+            ended: false,
+            truncated: false,
+        }
+    }
+
+    /// Reader over the `length` payload bytes of a compact chunk, with the
+    /// stream at the start of the payload.
+    pub fn with_length(stream: &'s mut DBFileStream<'a, N, B, C>, length: usize) -> Self {
+        let mut reader: DBChunkReader<'s, 'a, N, B, C> = Self::new(stream);
+        reader.remaining = Some(length);
+        reader
+    }
+
+    /// Skips the padding and anything else the decoder left unread, leaving
+    /// the stream after the EOE block. Fails with `DBError::EndOfFileStream`
+    /// when the stream ends before the chunk does. The bytes decoded are
+    /// recorded as a transient buffer.
+    pub fn finish(mut self) -> Result<(), DBError> {
+        match self.remaining {
+            Some(remaining) => self.skip_payload(remaining),
+            None => {
+                while !self.ended {
+                    let _ = self.next_block(0);
+                }
+            }
+        }
+
+        if let Some(memory) = self.stream.memory {
+            memory.record(self.length);
+        }
+
+        if self.truncated {
+            return Err(DBError::EndOfFileStream);
+        }
+        Ok(())
+    }
+}
+
+impl<'s, 'a, const N: usize, const B: usize, C: DBConfig> Reader
+    for DBChunkReader<'s, 'a, N, B, C>
+{
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        if let Some(remaining) = self.remaining {
+            return self.read_payload(bytes, remaining);
+        }
+
+        let mut filled: usize = 0;
+        while filled < bytes.len() {
+            if self.offset == B {
+                self.next_block(bytes.len() - filled)?;
+            }
+
+            let length: usize = (B - self.offset).min(bytes.len() - filled);
+            let block: &[u8] = &self.block[self.offset..self.offset + length];
+            bytes[filled..filled + length].copy_from_slice(block);
+            self.offset += length;
+            filled += length;
+        }
+        self.length += bytes.len();
+        Ok(())
+    }
+}
+
+/// Chunk reader over bytes that are already in memory, such as a memory-mapped
+/// file. Chunks are handed out as borrowed slices instead of copies.
+pub struct DBSliceStream<'a, const B: usize = BLOCK_SIZE, C = DBStandardConfig> {
+    buffer: &'a [u8],
+    position: usize,
+    marker: PhantomData<C>,
+}
+
+impl<'a, const B: usize, C: DBConfig> DBSliceStream<'a, B, C> {
+    /// Starts at the first chunk, after the format header.
+    pub fn new(buffer: &'a [u8]) -> Self {
+        let position: usize = DBFormatHeader::<B, C>::slice_start(buffer);
+        let marker: PhantomData<C> = PhantomData;
+        Self {
+            buffer,
+            position,
+            marker,
+        }
+    }
+
+    pub fn seek(&mut self, position: usize) -> Result<usize, DBError> {
+        if position > self.buffer.len() {
+            return Err(DBError::EndOfFileStream);
+        }
+        self.position = position;
+        Ok(position)
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    /// Whether every chunk of the buffer has been read.
+    pub fn at_end(&self) -> bool {
+        self.position >= self.buffer.len()
+    }
+
+    /// Returns the chunk at the current position like
+    /// `DBFileStream::iter_chunk`, failing with `DBError::EndOfFileStream`
+    /// at the end of the buffer, also inside a chunk.
+    pub fn iter_chunk(&mut self) -> Result<&'a [u8], DBError> {
+        if C::ENCODING.compact {
+            return self.iter_compact_chunk();
+        }
+
+        let start: usize = self.position;
+        let mut end: usize = start;
+
+        while end + B <= self.buffer.len() {
+            let block: &[u8] = &self.buffer[end..end + B];
+            end += B;
+            if block == eoe_block::<B>() {
+                self.position = end;
+                return Ok(&self.buffer[start..end]);
+            }
+        }
+
+        self.position = self.buffer.len();
+        Err(DBError::EndOfFileStream)
+    }
+
+    fn iter_compact_chunk(&mut self) -> Result<&'a [u8], DBError> {
+        let start: usize = self.position;
+        let bytes: &[u8] = self.buffer.get(start..).unwrap_or_default();
+        let header: Option<DBCompactHeader> =
+            DBCompactHeader::decode(bytes, C::ENCODING.payload_limit());
+
+        let end: Option<usize> = header.map(|header| start + header.chunk_size());
+        match end {
+            Some(end) if end <= self.buffer.len() => {
+                self.position = end;
+                Ok(&self.buffer[start..end])
+            }
+            _ if start >= self.buffer.len() => Err(DBError::EndOfFileStream),
+            Some(_) => {
+                self.position = self.buffer.len();
+                Err(DBError::EndOfFileStream)
+            }
+            None => {
+                self.position = self.buffer.len();
+                Err(DBError::InvalidData {
+                    offset: Some(start),
+                })
+            }
+        }
+    }
 }
 
-impl<'a, const N: usize> Iterator for DBFileStream<'a, N> {
-    type Item = Result<[u8; BLOCK_SIZE], DBError>;
+impl<'a, const N: usize, const B: usize, C: DBConfig> Iterator for DBFileStream<'a, N, B, C> {
+    type Item = Result<[u8; B], DBError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut buffer: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        let mut buffer: [u8; B] = [0; B];
         let result: Result<(), DBError> = self.stream.read(&mut buffer);
 
-        if let Ok(_) = result {
+        if result.is_ok() {
             return Some(Ok(buffer));
         }
 
@@ -344,8 +1025,9 @@ impl<'a, const N: usize> Iterator for DBFileStream<'a, N> {
     }
 }
 
-impl<'a, const N: usize> Drop for DBFileStream<'a, N> {
+impl<'a, const N: usize, const B: usize, C> Drop for DBFileStream<'a, N, B, C> {
     fn drop(&mut self) {
-        self.stream.flush_cache_buffer().unwrap();
+        // Writers flush explicitly to surface errors, this only catches leftovers
+        let _ = self.stream.flush_cache_buffer();
     }
 }
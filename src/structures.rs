@@ -1,16 +1,11 @@
-extern crate alloc;
-use alloc::vec::Vec;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use crate::iterators::DBChunkIterator;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use crate::iterators::DBIterator;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use crate::iterators::DBSliceIterator;
 
-use crate::error;
-use crate::serializer;
-use crate::stream;
-use crate::traits;
-use crate::CACHE_SIZE;
-
-use error::DBError;
-use serializer::DBSerializer;
-use stream::DBFileStream;
-use traits::FileBox;
+use crate::config::DBConfig;
 
 use core::fmt::Debug;
 use core::hash::Hash;
@@ -18,85 +13,24 @@ use core::hash::Hash;
 use bincode::Decode;
 use bincode::Encode;
 
+/// An entry and its UID, `U` is `u32` unless the database uses `DBWideUID`.
 #[derive(Debug)]
-pub struct DBEntry<T>
+pub struct DBEntry<T, U = u32>
 where
     T: Encode + Decode + Hash + Eq,
 {
-    pub uid: u32,
+    pub uid: U,
     pub item: T,
 }
 
-impl<T> DBEntry<T>
+impl<T, U> DBEntry<T, U>
 where
     T: Encode + Decode + Hash + Eq,
 {
-    pub fn new(uid: u32, item: T) -> Self {
+    pub fn new(uid: U, item: T) -> Self {
         Self { uid, item }
     }
 }
 
-pub struct DBIterator<'a, T>
-where
-    T: IntoIterator + Eq,
-    T::Item: Encode + Decode + Hash + Eq,
-{
-    stream: DBFileStream<'a, CACHE_SIZE>,
-    serializer: DBSerializer<'a, T>,
-}
-
-impl<'a, T> DBIterator<'a, T>
-where
-    T: IntoIterator + Eq,
-    T::Item: Encode + Decode + Hash + Eq,
-{
-    pub fn new(stream: DBFileStream<'a, CACHE_SIZE>, serializer: DBSerializer<'a, T>) -> Self {
-        Self { stream, serializer }
-    }
-
-    pub fn from_file(file: &'a mut FileBox) -> Self {
-        let stream: DBFileStream<CACHE_SIZE> = DBFileStream::new(file);
-        let serializer: DBSerializer<'_, T> = DBSerializer::new();
-        Self { stream, serializer }
-    }
-}
-
-impl<'a, T> Iterator for DBIterator<'a, T>
-where
-    T: IntoIterator + Eq,
-    T::Item: Encode + Decode + Hash + Eq,
-{
-    type Item = Result<DBEntry<T::Item>, DBError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let chunk = self.stream.iter_chunk();
-        if let Ok(chunk) = chunk {
-            let entry = self.serializer.deserialize(&chunk);
-            return Some(entry);
-        }
-        None
-    }
-}
-
-pub struct DBChunkIterator<'a> {
-    stream: DBFileStream<'a, CACHE_SIZE>,
-}
-
-impl<'a> DBChunkIterator<'a> {
-    pub fn from_file(file: &'a mut FileBox) -> Self {
-        let stream: DBFileStream<CACHE_SIZE> = DBFileStream::new(file);
-        Self { stream }
-    }
-}
-
-impl<'a> Iterator for DBChunkIterator<'a> {
-    type Item = Result<Vec<u8>, DBError>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let chunk = self.stream.iter_chunk();
-        if let Ok(chunk) = chunk {
-            return Some(Ok(chunk));
-        }
-        None
-    }
-}
+/// Entry of a database over `T` numbered with the UIDs of configuration `C`.
+pub type DBItemEntry<T, C> = DBEntry<<T as IntoIterator>::Item, <C as DBConfig>::UID>;
@@ -1,13 +1,22 @@
+#[cfg(any(feature = "std", feature = "alloc"))]
 extern crate alloc;
+#[cfg(any(feature = "std", feature = "alloc"))]
 use alloc::boxed::Box;
 
 use crate::error::DBError;
+#[cfg(any(feature = "std", feature = "alloc"))]
 use core::ops::Deref;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub type FileBox = Box<dyn FileTrait>;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub type CPathBox = Box<dyn CPathTrait>;
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub type OpenFileBox = Box<dyn OpenFileTrait>;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub type ClockBox = Box<dyn ClockTrait>;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub trait FileTrait {
     fn read(&mut self, buffer: &mut [u8]) -> Result<usize, DBError>;
     fn write(&mut self, buffer: &[u8]) -> Result<usize, DBError>;
@@ -15,9 +24,23 @@ pub trait FileTrait {
     fn seek(&mut self, position: usize) -> Result<usize, DBError>;
     fn set_len(&self, size: usize) -> Result<(), DBError>;
     fn close(self: Box<Self>) -> Result<(), DBError>;
+
+    /// Flushes written data to the storage, backends that write through
+    /// keep the default.
+    fn sync(&mut self) -> Result<(), DBError> {
+        Ok(())
+    }
+
+    /// Contents of the file when it is already mapped into memory,
+    /// which lets readers borrow chunks instead of copying them.
+    fn as_bytes(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub trait OpenFileTrait {
+    #[allow(clippy::new_ret_no_self)]
     fn new() -> OpenFileBox
     where
         Self: Sized;
@@ -30,17 +53,47 @@ pub trait OpenFileTrait {
     fn create(&mut self, create: bool);
     fn reset(&mut self);
     fn open(&self, path: &dyn CPathTrait) -> Result<FileBox, DBError>;
+
+    /// Deletes the file at `path`, backends that cannot return
+    /// `DBError::Unsupported`.
+    fn remove(&self, _path: &dyn CPathTrait) -> Result<(), DBError> {
+        Err(DBError::Unsupported)
+    }
+
+    /// Replaces the file at `to` with the file at `from` in a single step,
+    /// backends that cannot return `DBError::Unsupported`.
+    fn rename(&self, _from: &dyn CPathTrait, _to: &dyn CPathTrait) -> Result<(), DBError> {
+        Err(DBError::Unsupported)
+    }
+}
+
+/// Source of timestamps, in a unit of the implementation's choosing such as
+/// milliseconds since boot.
+pub trait ClockTrait {
+    fn now(&self) -> u64;
+}
+
+/// Storage owned directly by a `HeaplessDatabase`, so no trait objects or
+/// heap allocations are needed to reach it.
+pub trait StorageTrait {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, DBError>;
+    fn write_all(&mut self, buffer: &[u8]) -> Result<(), DBError>;
+    fn seek(&mut self, position: usize) -> Result<usize, DBError>;
+    fn set_len(&mut self, size: usize) -> Result<(), DBError>;
+    fn flush(&mut self) -> Result<(), DBError>;
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 pub trait CPathTrait {
     fn as_str(&self) -> &str;
     fn boxed(&self) -> CPathBox;
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 impl Deref for dyn CPathTrait {
     type Target = str;
 
     fn deref(&self) -> &Self::Target {
-        &self.as_str()
+        self.as_str()
     }
 }
@@ -2,10 +2,8 @@ use std::io::{self, Write};
 
 pub fn input(prompt: &str) {
     print!("{}", prompt);
-    io::stdout().flush().unwrap();
+    let _ = io::stdout().flush();
 
     let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
+    let _ = io::stdin().read_line(&mut input);
 }
@@ -0,0 +1,150 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 50;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord, Clone)]
+pub struct Metric {
+    online: bool,
+    host: u8,
+    load: u32,
+}
+
+pub fn create_metric(idx: u32) -> Metric {
+    let host: u8 = (idx % 4) as u8;
+    let online: bool = !idx.is_multiple_of(5);
+    let load: u32 = (idx * 37) % 101;
+    Metric { online, host, load }
+}
+
+fn aggregate_entries<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Metric>, 2048, 4, C> = Database::create(path, open).unwrap();
+    assert_eq!(db.count().unwrap(), 0);
+    assert!(db.min_by_key(|metric| metric.load).unwrap().is_none());
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_metric(idx)).unwrap();
+    }
+    let metrics: Vec<Metric> = (0..WRITE_ENTRIES).map(create_metric).collect();
+
+    assert_eq!(db.count().unwrap(), WRITE_ENTRIES as usize);
+    let count: usize = db.count_where(|metric| metric.host == 1).unwrap();
+    assert_eq!(
+        count,
+        metrics.iter().filter(|metric| metric.host == 1).count()
+    );
+
+    let min: DBEntry<Metric> = db.min_by_key(|metric| metric.load).unwrap().unwrap();
+    let expected: Option<&Metric> = metrics.iter().min_by_key(|metric| metric.load);
+    assert_eq!(Some(&min.item), expected);
+    assert_eq!(min.item, metrics[min.uid as usize]);
+
+    // Ties go to the first entry for the minimum and the last for the maximum
+    let first: DBEntry<Metric> = db.min_by_key(|metric| metric.host).unwrap().unwrap();
+    assert_eq!(first.uid, 0);
+    let last: DBEntry<Metric> = db.max_by_key(|metric| metric.host).unwrap().unwrap();
+    assert_eq!(last.uid, 47);
+
+    let total: u64 = db
+        .fold(0, |total, entry| total + entry.item.load as u64)
+        .unwrap();
+    assert_eq!(total, metrics.iter().map(|metric| metric.load as u64).sum());
+    remove_database(path);
+}
+
+pub fn aggregate_test(path: &dyn CPathTrait) {
+    println!("\n[AGGREGATE TEST]");
+    aggregate_entries::<DBStandardConfig>(path);
+    aggregate_entries::<DBCompact>(path);
+
+    let capacity: DBCapacity = DBCapacity::new(256);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Metric>> =
+        Database::create_capped(path, open, capacity).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_metric(idx)).unwrap();
+    }
+    let count: usize = db.get_iterator().unwrap().count();
+    assert!(count < WRITE_ENTRIES as usize);
+    assert_eq!(db.count().unwrap(), count);
+    let last: u32 = db.fold(0, |_, entry| entry.uid).unwrap();
+    assert_eq!(last, WRITE_ENTRIES - 1);
+    remove_database(path);
+}
+
+fn torn_entries<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Metric>, 2048, 4, C> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_metric(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    // Cut the last chunk short, as a torn write would
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    std::fs::write(path.as_str(), &bytes[..bytes.len() - 1]).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Metric>, 2048, 4, C> = Database::open(path, open).unwrap();
+    assert!(matches!(db.count(), Err(DBError::EndOfFileStream)));
+    let count: Result<usize, DBError> = db.count_where(|metric| metric.online);
+    assert!(matches!(count, Err(DBError::EndOfFileStream)));
+    let min: Result<Option<DBEntry<Metric>>, DBError> = db.min_by_key(|metric| metric.load);
+    assert!(matches!(min, Err(DBError::EndOfFileStream)));
+    let total: Result<u64, DBError> = db.fold(0, |total, entry| total + entry.item.load as u64);
+    assert!(matches!(total, Err(DBError::EndOfFileStream)));
+    remove_database(path);
+}
+
+pub fn aggregate_error_test(path: &dyn CPathTrait) {
+    println!("\n[AGGREGATE ERROR TEST]");
+    torn_entries::<DBStandardConfig>(path);
+    torn_entries::<DBCompact>(path);
+
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Metric>> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_metric(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    // Store an invalid bool in the first entry, past its UID block
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes[4] = 7;
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    // Counting reads the UID headers only, the rest report the entry
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Metric>> = Database::open(path, open).unwrap();
+    assert_eq!(db.count().unwrap(), WRITE_ENTRIES as usize);
+    let error: DBError = db.count_where(|metric| metric.online).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 0, .. }));
+    let error: DBError = db.max_by_key(|metric| metric.load).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 0, .. }));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
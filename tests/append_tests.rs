@@ -0,0 +1,116 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+use core::ops::Range;
+
+use micro_db::capped::DBCapacity;
+use micro_db::cipher::DBKey;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBEncrypted;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::Database;
+use micro_db::db::APPEND_BATCH;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: usize = APPEND_BATCH * 3 + 5;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Command {
+    id: u32,
+    payload: Vec<u8>,
+}
+
+/// Commands in descending order with repeats, some larger than the cache.
+pub fn create_command(idx: usize) -> Command {
+    let id: u32 = (WRITE_ENTRIES - idx) as u32 / 2;
+    let length: usize = if idx % 50 == 7 { 3000 } else { idx % 13 };
+    let payload: Vec<u8> = vec![id as u8; length];
+    Command { id, payload }
+}
+
+fn read_items<C: DBConfig>(db: &mut Database<'_, BTreeSet<Command>, 2048, 4, C>) -> Vec<Command> {
+    let items: Vec<Command> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    items
+}
+
+fn append_commands<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Command>, 2048, 4, C> = Database::create(path, open).unwrap();
+    if C::ENCODING.encrypted {
+        db.set_key(&DBKey::new([3; 32], 1)).unwrap();
+    }
+    db.add_entry(&create_command(0)).unwrap();
+
+    let uids: Range<u32> = db
+        .add_entries_iter((1..WRITE_ENTRIES).map(create_command))
+        .unwrap();
+    assert_eq!(uids, 1..WRITE_ENTRIES as u32);
+    let uids: Range<u32> = db.add_entries_iter(Vec::new()).unwrap();
+    assert_eq!(uids, WRITE_ENTRIES as u32..WRITE_ENTRIES as u32);
+    let uids: Range<u32> = db
+        .add_entries_iter(vec![create_command(0), create_command(0)])
+        .unwrap();
+    assert_eq!(uids, WRITE_ENTRIES as u32..WRITE_ENTRIES as u32 + 2);
+
+    // Caller order and repeats are kept
+    let mut expected: Vec<Command> = (0..WRITE_ENTRIES).map(create_command).collect();
+    expected.extend([create_command(0), create_command(0)]);
+    assert_eq!(read_items(&mut db), expected);
+    assert!(db.check().unwrap().is_clean());
+
+    db.add_entry(&create_command(1)).unwrap();
+    let entry: DBEntry<Command> = db.get_by_uid(WRITE_ENTRIES as u32 + 2).unwrap();
+    assert_eq!(entry.item, create_command(1));
+    remove_database(path);
+}
+
+pub fn append_iter_test(path: &dyn CPathTrait) {
+    println!("\n[APPEND ITER TEST]");
+    append_commands::<DBStandardConfig>(path);
+    append_commands::<DBCompact>(path);
+    append_commands::<DBEncrypted>(path);
+}
+
+pub fn capped_append_iter_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED APPEND ITER TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(8192);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Command>> =
+        Database::create_capped(path, open, capacity).unwrap();
+    let uids: Range<u32> = db
+        .add_entries_iter((0..WRITE_ENTRIES).map(create_command))
+        .unwrap();
+    assert_eq!(uids, 0..WRITE_ENTRIES as u32);
+
+    let entries: Vec<DBEntry<Command>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert!(!entries.is_empty() && entries.len() < WRITE_ENTRIES);
+    for entry in entries.iter() {
+        assert_eq!(entry.item, create_command(entry.uid as usize));
+    }
+    assert_eq!(entries.last().unwrap().uid, WRITE_ENTRIES as u32 - 1);
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
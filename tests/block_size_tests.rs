@@ -0,0 +1,163 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::format::FORMAT_MAGIC;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 40;
+const SMALL_CACHE: usize = 256;
+const FLASH_BLOCK: usize = 16;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SampleRecord {
+    sensor: u8,
+    values: Vec<u16>,
+}
+
+pub fn create_sample_record(idx: u32) -> SampleRecord {
+    let sensor: u8 = idx as u8;
+    let values: Vec<u16> = (0..idx * 7 % 200).map(|value| value as u16).collect();
+    SampleRecord { sensor, values }
+}
+
+type SmallCacheDatabase<'a> = Database<'a, BTreeSet<SampleRecord>, SMALL_CACHE>;
+type FlashDatabase<'a> = Database<'a, BTreeSet<SampleRecord>, SMALL_CACHE, FLASH_BLOCK>;
+
+pub fn small_cache_test(path: &dyn CPathTrait) {
+    println!("\n[SMALL CACHE TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: SmallCacheDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sample_record(idx)).unwrap();
+    }
+    let entry: DBEntry<SampleRecord> = db.get_by_uid(WRITE_ENTRIES - 1).unwrap();
+    assert_eq!(entry.item, create_sample_record(WRITE_ENTRIES - 1));
+    assert!(db.contains(&create_sample_record(33)).unwrap());
+    db.remove_by_uid(3).unwrap();
+    db.close().unwrap();
+
+    // The file layout does not depend on the cache size
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<SampleRecord>> = Database::open(path, open).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<SampleRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<SampleRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize - 1);
+    for (idx, entry) in entries.iter().enumerate() {
+        let original: u32 = if idx < 3 { idx as u32 } else { idx as u32 + 1 };
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_sample_record(original));
+    }
+    remove_database(path);
+}
+
+pub fn block_size_test(path: &dyn CPathTrait) {
+    println!("\n[BLOCK SIZE TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FlashDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sample_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    assert_eq!(bytes[..4], FORMAT_MAGIC);
+    assert_eq!(bytes[4..8], (FLASH_BLOCK as u32).to_le_bytes());
+    assert!(bytes.len().is_multiple_of(FLASH_BLOCK));
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FlashDatabase<'_> = Database::open(path, open).unwrap();
+    assert!(db.check().unwrap().is_clean());
+    db.remove_by_uid(0).unwrap();
+    let entry: DBEntry<SampleRecord> = db.get_by_uid(WRITE_ENTRIES - 2).unwrap();
+    assert_eq!(entry.item, create_sample_record(WRITE_ENTRIES - 1));
+
+    let db_iterator: DBIterator<'_, BTreeSet<SampleRecord>, SMALL_CACHE, FLASH_BLOCK> =
+        db.get_iterator().unwrap();
+    assert_eq!(db_iterator.count(), WRITE_ENTRIES as usize - 1);
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(2048);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FlashDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sample_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FlashDatabase<'_> = Database::open(path, open).unwrap();
+    assert_eq!(db.capacity, Some(capacity));
+    assert!(db.check().unwrap().is_clean());
+    let db_iterator: DBIterator<'_, BTreeSet<SampleRecord>, SMALL_CACHE, FLASH_BLOCK> =
+        db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<SampleRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert!(!entries.is_empty());
+    let first: u32 = WRITE_ENTRIES - entries.len() as u32;
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, first + idx as u32);
+        assert_eq!(entry.item, create_sample_record(first + idx as u32));
+    }
+    remove_database(path);
+}
+
+pub fn mismatch_test(path: &dyn CPathTrait) {
+    println!("\n[BLOCK SIZE MISMATCH TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FlashDatabase<'_> = Database::create(path, open).unwrap();
+    db.add_entry(&create_sample_record(1)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<SampleRecord>>::open(path, open)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        DBError::BlockSizeMismatch {
+            expected: 4,
+            found: FLASH_BLOCK
+        }
+    ));
+    assert_eq!(error.kind(), DBErrorKind::InvalidInput);
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: SmallCacheDatabase<'_> = Database::create(path, open).unwrap();
+    db.add_entry(&create_sample_record(1)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = FlashDatabase::open(path, open).err().unwrap();
+    assert!(matches!(
+        error,
+        DBError::BlockSizeMismatch {
+            expected: FLASH_BLOCK,
+            found: 4
+        }
+    ));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
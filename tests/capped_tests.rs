@@ -0,0 +1,135 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 1000;
+const MAX_ENTRIES: u32 = 16;
+const MAX_BYTES: usize = 1024;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SensorRecord {
+    sensor: u8,
+    value: i32,
+}
+
+pub fn create_sensor_record(idx: u32) -> SensorRecord {
+    let sensor: u8 = (idx % 4) as u8;
+    let value: i32 = idx as i32 * 3;
+    SensorRecord { sensor, value }
+}
+
+fn collect_entries(db: &mut Database<'_, BTreeSet<SensorRecord>>) -> Vec<DBEntry<SensorRecord>> {
+    let db_iterator: DBIterator<'_, BTreeSet<SensorRecord>> = db.get_iterator().unwrap();
+    db_iterator.map(|entry| entry.unwrap()).collect()
+}
+
+fn assert_sequential(entries: &[DBEntry<SensorRecord>], last_uid: u32) {
+    let first_uid: u32 = last_uid + 1 - entries.len() as u32;
+    for (idx, entry) in entries.iter().enumerate() {
+        let uid: u32 = first_uid + idx as u32;
+        assert_eq!(entry.uid, uid);
+        assert_eq!(entry.item, create_sensor_record(uid));
+    }
+}
+
+pub fn max_entries_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED MAX ENTRIES TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(MAX_BYTES * 64).with_max_entries(MAX_ENTRIES);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<SensorRecord>> = Database::new_capped(path, open, capacity);
+
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sensor_record(idx)).unwrap();
+    }
+
+    let entries: Vec<DBEntry<SensorRecord>> = collect_entries(&mut db);
+    assert_eq!(entries.len(), MAX_ENTRIES as usize);
+    assert_sequential(&entries, WRITE_ENTRIES - 1);
+    remove_database(path);
+}
+
+pub fn max_bytes_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED MAX BYTES TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(MAX_BYTES);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<SensorRecord>> = Database::new_capped(path, open, capacity);
+
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sensor_record(idx)).unwrap();
+
+        let entries: Vec<DBEntry<SensorRecord>> = collect_entries(&mut db);
+        assert!(!entries.is_empty());
+        assert_sequential(&entries, idx);
+    }
+
+    let file_size: u64 = std::fs::metadata(path.as_str()).unwrap().len();
+    assert!(file_size <= (MAX_BYTES + 64) as u64);
+    remove_database(path);
+}
+
+pub fn reopen_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED REOPEN TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(MAX_BYTES).with_max_entries(MAX_ENTRIES);
+    for idx in 0..WRITE_ENTRIES {
+        let open: OpenFileBox = OpenFile::new();
+        let mut db: Database<'_, BTreeSet<SensorRecord>> =
+            Database::new_capped(path, open, capacity);
+        db.add_entry(&create_sensor_record(idx)).unwrap();
+    }
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<SensorRecord>> = Database::new_capped(path, open, capacity);
+    let entries: Vec<DBEntry<SensorRecord>> = collect_entries(&mut db);
+    assert_eq!(entries.len(), MAX_ENTRIES as usize);
+    assert_sequential(&entries, WRITE_ENTRIES - 1);
+
+    let entry: DBEntry<SensorRecord> = db.get_by_uid(WRITE_ENTRIES - 2).unwrap();
+    assert_eq!(entry.item, create_sensor_record(WRITE_ENTRIES - 2));
+    assert!(db.get_by_uid(0).is_err());
+    assert!(db.remove_by_uid(WRITE_ENTRIES - 1).is_err());
+    remove_database(path);
+}
+
+pub fn capacity_limit_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED CAPACITY LIMIT TEST]");
+    remove_database(path);
+
+    // Offsets within the ring are 32-bit, larger capacities are refused
+    let open: OpenFileBox = OpenFile::new();
+    let capacity: DBCapacity = DBCapacity::new(u32::MAX as usize + 1);
+    let mut db: Database<'_, BTreeSet<SensorRecord>> = Database::new_capped(path, open, capacity);
+    let error: DBError = db.add_entry(&create_sensor_record(0)).unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::CapacityTooLarge {
+            capacity: 4_294_967_296,
+            ..
+        }
+    ));
+    assert!(db.get_iterator().is_err());
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
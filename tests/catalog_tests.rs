@@ -0,0 +1,204 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::catalog::CatalogItem;
+use micro_db::catalog::DBCatalog;
+use micro_db::catalog::DBCollection;
+use micro_db::catalog::DBRecord;
+use micro_db::config::DBCompressed;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Event {
+    code: u16,
+    timestamp: u64,
+}
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Setting {
+    name: String,
+    value: i32,
+}
+
+impl CatalogItem for Event {
+    const SCHEMA_ID: u64 = 1;
+}
+
+impl CatalogItem for Setting {
+    const SCHEMA_ID: u64 = 2;
+}
+
+/// Same encoding as `Event` under another name, as after renaming it
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct RenamedEvent {
+    code: u16,
+    timestamp: u64,
+}
+
+impl CatalogItem for RenamedEvent {
+    const SCHEMA_ID: u64 = 1;
+}
+
+pub fn create_event(idx: u16) -> Event {
+    let code: u16 = idx;
+    let timestamp: u64 = 1_700_000_000 + idx as u64;
+    Event { code, timestamp }
+}
+
+pub fn create_setting(idx: u16) -> Setting {
+    let name: String = format!("setting-{}", idx);
+    let value: i32 = -(idx as i32);
+    Setting { name, value }
+}
+
+fn open_catalog<'a>(path: &dyn CPathTrait) -> DBCatalog<'a> {
+    let open: OpenFileBox = OpenFile::new();
+    DBCatalog::new(Database::open_or_create(path, open).unwrap())
+}
+
+fn read_events(catalog: &mut DBCatalog<'_>) -> Vec<DBEntry<Event>> {
+    let mut events: DBCollection<'_, '_, Event, 2048, 4, DBStandardConfig> =
+        catalog.collection("events").unwrap();
+    let entries: Vec<DBEntry<Event>> = events
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    entries
+}
+
+pub fn collections_test(path: &dyn CPathTrait) {
+    println!("\n[CATALOG COLLECTIONS TEST]");
+    remove_database(path);
+
+    let mut catalog: DBCatalog<'_> = open_catalog(path);
+    for idx in 0..10 {
+        let mut events: DBCollection<'_, '_, Event, 2048, 4, DBStandardConfig> =
+            catalog.collection("events").unwrap();
+        events.add_entry(&create_event(idx)).unwrap();
+        if idx % 2 == 0 {
+            let mut settings: DBCollection<'_, '_, Setting, 2048, 4, DBStandardConfig> =
+                catalog.collection("settings").unwrap();
+            settings.add_entry(&create_setting(idx)).unwrap();
+        }
+    }
+    catalog.close().unwrap();
+
+    let mut catalog: DBCatalog<'_> = open_catalog(path);
+    assert_eq!(catalog.collections().unwrap(), vec!["events", "settings"]);
+
+    let events: Vec<DBEntry<Event>> = read_events(&mut catalog);
+    assert_eq!(events.len(), 10);
+    for (idx, entry) in events.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_event(idx as u16));
+    }
+
+    let mut settings: DBCollection<'_, '_, Setting, 2048, 4, DBStandardConfig> =
+        catalog.collection("settings").unwrap();
+    let entries: Vec<DBEntry<Setting>> = settings
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), 5);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_setting(idx as u16 * 2));
+    }
+
+    // Removing from one collection leaves the UIDs of the other alone
+    settings.remove_by_uid(1).unwrap();
+    assert_eq!(settings.get_by_uid(1).unwrap().item, create_setting(4));
+    let error: DBError = settings.get_by_uid(4).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { uid: Some(4) }));
+    assert_eq!(read_events(&mut catalog).len(), 10);
+
+    let mut empty: DBCollection<'_, '_, Event, 2048, 4, DBStandardConfig> =
+        catalog.collection("empty").unwrap();
+    assert_eq!(empty.get_iterator().unwrap().count(), 0);
+    let error: DBError = empty.remove_by_uid(0).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { uid: Some(0) }));
+    assert!(catalog.database.check().unwrap().is_clean());
+    catalog.close().unwrap();
+
+    // A read-only catalog cannot add names
+    let open: OpenFileBox = OpenFile::new();
+    let mut catalog: DBCatalog<'_> = DBCatalog::new(Database::open_read_only(path, open).unwrap());
+    assert_eq!(read_events(&mut catalog).len(), 10);
+    let result: Result<DBCollection<'_, '_, Event, 2048, 4, DBStandardConfig>, DBError> =
+        catalog.collection("missing");
+    assert!(matches!(result.err().unwrap(), DBError::ReadOnly));
+    remove_database(path);
+}
+
+pub fn catalog_layers_test(path: &dyn CPathTrait) {
+    println!("\n[CATALOG LAYERS TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let database: Database<'_, BTreeSet<DBRecord>, 2048, 4, DBCompressed> =
+        Database::create(path, open).unwrap();
+    let mut catalog: DBCatalog<'_, 2048, 4, DBCompressed> = DBCatalog::new(database);
+    let mut events: DBCollection<'_, '_, Event, 2048, 4, DBCompressed> =
+        catalog.collection("events").unwrap();
+    for idx in 0..5 {
+        events.add_entry(&create_event(idx)).unwrap();
+    }
+    let items: Vec<Event> = events
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    assert_eq!(items, (0..5).map(create_event).collect::<Vec<Event>>());
+
+    // A name stays bound to the item type it was added with
+    let result: Result<DBCollection<'_, '_, Setting, 2048, 4, DBCompressed>, DBError> =
+        catalog.collection("events");
+    let error: DBError = result.err().unwrap();
+    assert!(matches!(error, DBError::TypeMismatch { id: 0 }));
+    assert_eq!(catalog.collections().unwrap(), vec![String::from("events")]);
+
+    // The name is bound to the schema id, not to the name of the type
+    let mut renamed: DBCollection<'_, '_, RenamedEvent, 2048, 4, DBCompressed> =
+        catalog.collection("events").unwrap();
+    let entry: DBEntry<RenamedEvent> = renamed.get_by_uid(4).unwrap();
+    assert_eq!(entry.item.code, 4);
+    let record: DBRecord = catalog.database.get_by_uid(0).unwrap().item;
+    assert!(matches!(
+        record,
+        DBRecord::Collection {
+            id: 0,
+            schema_id: 1,
+            ..
+        }
+    ));
+    catalog.close().unwrap();
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(1024);
+    let open: OpenFileBox = OpenFile::new();
+    let mut catalog: DBCatalog<'_> =
+        DBCatalog::new(Database::create_capped(path, open, capacity).unwrap());
+    let result: Result<DBCollection<'_, '_, Event, 2048, 4, DBStandardConfig>, DBError> =
+        catalog.collection("events");
+    assert!(matches!(result.err().unwrap(), DBError::Unsupported));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,163 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::check::DBCheckReport;
+use micro_db::check::DBIssueKind;
+use micro_db::db::Database;
+use micro_db::impls::CPath;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBChunkIterator;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 30;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct SensorRecord {
+    active: bool,
+    reading: u32,
+}
+
+pub fn create_sensor_record(idx: u32) -> SensorRecord {
+    let active: bool = idx.is_multiple_of(2);
+    let reading: u32 = idx * 1000;
+    SensorRecord { active, reading }
+}
+
+type SensorDatabase<'a> = Database<'a, BTreeSet<SensorRecord>>;
+
+fn write_entries(path: &dyn CPathTrait) -> Vec<usize> {
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: SensorDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sensor_record(idx)).unwrap();
+    }
+
+    let chunk_iterator: DBChunkIterator<'_> = db.get_chunk_iterator().unwrap();
+    let mut offsets: Vec<usize> = vec![0];
+    for chunk in chunk_iterator {
+        let offset: usize = offsets.last().unwrap() + chunk.unwrap().len();
+        offsets.push(offset);
+    }
+    db.close().unwrap();
+    offsets
+}
+
+fn count_entries(db: &mut SensorDatabase<'_>) -> usize {
+    let db_iterator: DBIterator<'_, BTreeSet<SensorRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<SensorRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    entries.len()
+}
+
+pub fn clean_check_test(path: &dyn CPathTrait) {
+    println!("\n[CLEAN CHECK TEST]");
+    remove_database(path);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: SensorDatabase<'_> = Database::open(path, open).unwrap();
+    let report: DBCheckReport = db.check().unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.entries, WRITE_ENTRIES);
+    remove_database(path);
+}
+
+pub fn torn_tail_test(path: &dyn CPathTrait, quarantine: &CPath) {
+    println!("\n[TORN TAIL TEST]");
+    remove_database(path);
+    remove_database(quarantine);
+    write_entries(path);
+
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    let length: usize = bytes.len();
+    bytes.extend([30, 0, 0, 0, 1, 0xFB, 0x10]);
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: SensorDatabase<'_> = Database::open(path, open).unwrap();
+    let report: DBCheckReport = db.check().unwrap();
+    assert_eq!(report.entries, WRITE_ENTRIES);
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].kind, DBIssueKind::TruncatedTail);
+    assert_eq!(report.issues[0].offset, length);
+    assert_eq!(report.issues[0].length, 7);
+
+    let report: DBCheckReport = db.repair(quarantine).unwrap();
+    assert_eq!(report.quarantined, 7);
+    assert!(db.check().unwrap().is_clean());
+    assert_eq!(
+        std::fs::metadata(path.as_str()).unwrap().len(),
+        length as u64
+    );
+    assert_eq!(std::fs::read(quarantine.as_str()).unwrap(), bytes[length..]);
+
+    db.add_entry(&create_sensor_record(WRITE_ENTRIES)).unwrap();
+    let entry: DBEntry<SensorRecord> = db.get_by_uid(WRITE_ENTRIES).unwrap();
+    assert_eq!(entry.item, create_sensor_record(WRITE_ENTRIES));
+    assert_eq!(count_entries(&mut db), WRITE_ENTRIES as usize + 1);
+    remove_database(path);
+    remove_database(quarantine);
+}
+
+pub fn quarantine_test(path: &dyn CPathTrait, quarantine: &CPath) {
+    println!("\n[QUARANTINE TEST]");
+    remove_database(path);
+    remove_database(quarantine);
+    let offsets: Vec<usize> = write_entries(path);
+
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    // Lose the EOE block of entry 5, so it runs into entry 6
+    bytes[offsets[6] - 4..offsets[6]].copy_from_slice(&[0; 4]);
+    // Store an invalid bool in entry 10
+    bytes[offsets[10] + 4] = 7;
+    // Rewind the UID of entry 15
+    bytes[offsets[15]..offsets[15] + 4].copy_from_slice(&2u32.to_le_bytes());
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: SensorDatabase<'_> = Database::open(path, open).unwrap();
+    let report: DBCheckReport = db.check().unwrap();
+    let kinds: Vec<DBIssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            DBIssueKind::MissingEOE,
+            DBIssueKind::UndecodablePayload,
+            DBIssueKind::NonMonotonicUID { previous: 14 },
+        ]
+    );
+    assert_eq!(report.issues[0].offset, offsets[5]);
+    assert_eq!(report.issues[0].length, offsets[7] - offsets[5]);
+    assert_eq!(report.issues[1].uid, Some(10));
+    assert_eq!(report.issues[2].uid, Some(2));
+    assert_eq!(report.entries, WRITE_ENTRIES - 4);
+
+    let report: DBCheckReport = db.repair(quarantine).unwrap();
+    let quarantined: usize = report.issues.iter().map(|issue| issue.length).sum();
+    assert_eq!(report.quarantined, quarantined);
+    assert_eq!(
+        std::fs::metadata(quarantine.as_str()).unwrap().len(),
+        quarantined as u64
+    );
+
+    let report: DBCheckReport = db.check().unwrap();
+    assert!(report.is_clean());
+    assert_eq!(report.entries, WRITE_ENTRIES - 4);
+    assert_eq!(count_entries(&mut db), WRITE_ENTRIES as usize - 4);
+    assert!(db.get_by_uid(10).is_err());
+    assert_eq!(db.get_by_uid(16).unwrap().item, create_sensor_record(16));
+    remove_database(path);
+    remove_database(quarantine);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,232 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::cipher::DBKey;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBEncrypted;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::DBClearPolicy;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::format::FORMAT_MAGIC;
+use micro_db::impls::OpenFile;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 30;
+// Format and cipher fields written ahead of the chunks of an encrypted file
+const ENCRYPTED_HEADER_SIZE: usize = 84;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Alarm {
+    zone: u16,
+    armed: bool,
+}
+
+pub fn create_alarm(idx: u32) -> Alarm {
+    let zone: u16 = idx as u16;
+    let armed: bool = idx.is_multiple_of(2);
+    Alarm { zone, armed }
+}
+
+type AlarmDatabase<'a, C> = Database<'a, BTreeSet<Alarm>, 2048, 4, C>;
+
+fn write_alarms<C: DBConfig>(db: &mut AlarmDatabase<'_, C>) {
+    db.add_entries_iter((0..WRITE_ENTRIES).map(create_alarm))
+        .unwrap();
+}
+
+fn file_length(path: &dyn CPathTrait) -> usize {
+    std::fs::metadata(path.as_str()).unwrap().len() as usize
+}
+
+fn clear_entries<C: DBConfig<UID = u32>>(path: &dyn CPathTrait, header: usize) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: AlarmDatabase<'_, C> = Database::create(path, open).unwrap();
+    if C::ENCODING.encrypted {
+        db.set_key(&DBKey::new([9; 32], 1)).unwrap();
+    }
+    db.clear(DBClearPolicy::ResetUids).unwrap();
+    write_alarms(&mut db);
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+
+    db.clear(DBClearPolicy::ResetUids).unwrap();
+    assert_eq!(file_length(path), header);
+    assert_eq!(db.count().unwrap(), 0);
+    // The header, and with it the nonce counter, is kept
+    assert_eq!(std::fs::read(path.as_str()).unwrap(), bytes[..header]);
+
+    // Keeping UID 0 adds no header
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    assert_eq!(file_length(path), header);
+    db.add_entry(&create_alarm(7)).unwrap();
+    assert_eq!(db.get_by_uid(0).unwrap().item, create_alarm(7));
+    assert!(db.check().unwrap().is_clean());
+    remove_database(path);
+}
+
+fn keep_uids<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: AlarmDatabase<'_, C> = Database::create(path, open).unwrap();
+    if C::ENCODING.encrypted {
+        db.set_key(&DBKey::new([9; 32], 1)).unwrap();
+    }
+    write_alarms(&mut db);
+
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    assert_eq!(db.count().unwrap(), 0);
+    // An empty file keeps the UID through another clear and a reopen
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: AlarmDatabase<'_, C> = Database::open(path, open).unwrap();
+    if C::ENCODING.encrypted {
+        db.set_key(&DBKey::new([9; 32], 1)).unwrap();
+    }
+    db.add_entries_iter((0..4).map(create_alarm)).unwrap();
+    db.add_entry(&create_alarm(4)).unwrap();
+    let uids: Vec<u32> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().uid)
+        .collect();
+    let expected: Vec<u32> = (WRITE_ENTRIES..WRITE_ENTRIES + 5).collect();
+    assert_eq!(uids, expected);
+    assert!(db.check().unwrap().is_clean());
+
+    // Removals number the kept entries from the first UID
+    db.remove_by_uid(WRITE_ENTRIES + 1).unwrap();
+    assert_eq!(
+        db.get_by_uid(WRITE_ENTRIES + 1).unwrap().item,
+        create_alarm(2)
+    );
+    assert!(db.remove_by_uid(0).is_err());
+    db.retain_by_uid(|uid| uid != WRITE_ENTRIES).unwrap();
+    assert_eq!(db.get_by_uid(WRITE_ENTRIES).unwrap().item, create_alarm(2));
+    assert_eq!(db.count().unwrap(), 3);
+    assert!(db.check().unwrap().is_clean());
+
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    db.add_entry(&create_alarm(9)).unwrap();
+    assert_eq!(
+        db.get_by_uid(WRITE_ENTRIES + 3).unwrap().item,
+        create_alarm(9)
+    );
+
+    db.clear(DBClearPolicy::ResetUids).unwrap();
+    db.add_entry(&create_alarm(9)).unwrap();
+    assert_eq!(db.get_by_uid(0).unwrap().item, create_alarm(9));
+    remove_database(path);
+}
+
+pub fn clear_test(path: &dyn CPathTrait) {
+    println!("\n[CLEAR TEST]");
+    clear_entries::<DBStandardConfig>(path, 0);
+    clear_entries::<DBCompact>(path, 24);
+    clear_entries::<DBEncrypted>(path, ENCRYPTED_HEADER_SIZE);
+    keep_uids::<DBStandardConfig>(path);
+    keep_uids::<DBCompact>(path);
+    keep_uids::<DBEncrypted>(path);
+
+    // Files with the default format get a header to keep the next UID in,
+    // and lose it again once the UIDs are reset
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Alarm>> = Database::new(path, open);
+    write_alarms(&mut db);
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    assert_eq!(bytes.len(), 24);
+    assert_eq!(bytes[..4], FORMAT_MAGIC);
+    assert_eq!(bytes[16..], (WRITE_ENTRIES as u64).to_le_bytes());
+    db.add_entry(&create_alarm(1)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Alarm>> = Database::open(path, open).unwrap();
+    assert_eq!(db.get_by_uid(WRITE_ENTRIES).unwrap().item, create_alarm(1));
+    assert_eq!(db.get_chunk_iterator().unwrap().count(), 1);
+    db.clear(DBClearPolicy::ResetUids).unwrap();
+    assert_eq!(file_length(path), 0);
+    write_alarms(&mut db);
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Alarm>> = Database::open_read_only(path, open).unwrap();
+    let error: DBError = db.clear(DBClearPolicy::ResetUids).unwrap_err();
+    assert!(matches!(error, DBError::ReadOnly));
+    assert_eq!(db.count().unwrap(), WRITE_ENTRIES as usize);
+    remove_database(path);
+}
+
+pub fn clear_capped_test(path: &dyn CPathTrait) {
+    println!("\n[CLEAR CAPPED TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(4096).with_max_entries(20);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Alarm>> =
+        Database::create_capped(path, open, capacity).unwrap();
+    write_alarms(&mut db);
+    assert_eq!(db.count().unwrap(), 20);
+
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    assert_eq!(db.count().unwrap(), 0);
+    db.add_entry(&create_alarm(1)).unwrap();
+    assert_eq!(db.get_by_uid(WRITE_ENTRIES).unwrap().item, create_alarm(1));
+    db.close().unwrap();
+
+    // The limits of the file are kept
+    let open: OpenFileBox = OpenFile::new();
+    let capacity: DBCapacity = DBCapacity::new(4096);
+    let mut db: Database<'_, BTreeSet<Alarm>> =
+        Database::open_or_create_capped(path, open, capacity).unwrap();
+    db.clear(DBClearPolicy::ResetUids).unwrap();
+    write_alarms(&mut db);
+    assert_eq!(db.count().unwrap(), 20);
+    let uids: Vec<u32> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().uid)
+        .collect();
+    assert_eq!(uids, (10..WRITE_ENTRIES).collect::<Vec<u32>>());
+    remove_database(path);
+}
+
+pub fn destroy_test(path: &dyn CPathTrait) {
+    println!("\n[DESTROY TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Alarm>> = Database::create(path, open).unwrap();
+    write_alarms(&mut db);
+    db.destroy().unwrap();
+    assert!(!std::path::Path::new(path.as_str()).exists());
+
+    let open: OpenFileBox = OpenFile::new();
+    let db: Database<'_, BTreeSet<Alarm>> = Database::create(path, open).unwrap();
+    db.close().unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let db: Database<'_, BTreeSet<Alarm>> = Database::open_read_only(path, open).unwrap();
+    assert!(matches!(db.destroy(), Err(DBError::ReadOnly)));
+    assert!(std::path::Path::new(path.as_str()).exists());
+
+    let open: OpenFileBox = OpenFile::new();
+    let db: Database<'_, BTreeSet<Alarm>> = Database::open(path, open).unwrap();
+    db.destroy().unwrap();
+    assert!(!std::path::Path::new(path.as_str()).exists());
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
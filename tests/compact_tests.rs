@@ -0,0 +1,241 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::check::DBCheckReport;
+use micro_db::check::DBIssueKind;
+use micro_db::config::DBCompact;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::CPath;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBChunkIterator;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::config::standard;
+use bincode::encode_to_vec;
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 100;
+// Format header written ahead of compact chunks
+const FORMAT_HEADER_SIZE: usize = 24;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord, Clone)]
+pub struct SensorRecord {
+    sensor: u8,
+    value: i32,
+}
+
+pub fn create_sensor_record(idx: u32) -> SensorRecord {
+    let sensor: u8 = (idx % 4) as u8;
+    let value: i32 = idx as i32 - 50;
+    SensorRecord { sensor, value }
+}
+
+type CompactDatabase<'a> = Database<'a, BTreeSet<SensorRecord>, 2048, 4, DBCompact>;
+type BlockDatabase<'a> = Database<'a, BTreeSet<SensorRecord>>;
+
+fn write_entries(path: &dyn CPathTrait) {
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompactDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sensor_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+}
+
+fn chunk_offsets(db: &mut CompactDatabase<'_>) -> Vec<usize> {
+    let chunk_iterator: DBChunkIterator<'_, 2048, 4, DBCompact> = db.get_chunk_iterator().unwrap();
+    let mut offsets: Vec<usize> = vec![FORMAT_HEADER_SIZE];
+    for chunk in chunk_iterator {
+        let offset: usize = offsets.last().unwrap() + chunk.unwrap().len();
+        offsets.push(offset);
+    }
+    offsets
+}
+
+fn collect_compact(db: &mut CompactDatabase<'_>) -> Vec<(u32, SensorRecord)> {
+    let db_iterator: DBIterator<'_, BTreeSet<SensorRecord>, 2048, 4, DBCompact> =
+        db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<SensorRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    entries
+        .into_iter()
+        .map(|entry| (entry.uid, entry.item))
+        .collect()
+}
+
+fn collect_block(db: &mut BlockDatabase<'_>) -> Vec<(u32, SensorRecord)> {
+    let db_iterator: DBIterator<'_, BTreeSet<SensorRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<SensorRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    entries
+        .into_iter()
+        .map(|entry| (entry.uid, entry.item))
+        .collect()
+}
+
+pub fn compact_layout_test(path: &dyn CPathTrait, block_path: &dyn CPathTrait) {
+    println!("\n[COMPACT LAYOUT TEST]");
+    remove_database(path);
+    remove_database(block_path);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: BlockDatabase<'_> = Database::create(block_path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sensor_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    // Each chunk is a 1-byte UID distance, a 1-byte length and the payload
+    let mut expected: usize = FORMAT_HEADER_SIZE;
+    for idx in 0..WRITE_ENTRIES {
+        let payload: Vec<u8> = encode_to_vec(create_sensor_record(idx), standard()).unwrap();
+        expected += 2 + payload.len();
+    }
+    let length: usize = std::fs::metadata(path.as_str()).unwrap().len() as usize;
+    let block_length: usize = std::fs::metadata(block_path.as_str()).unwrap().len() as usize;
+    assert_eq!(length, expected);
+    assert!(length * 2 < block_length);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompactDatabase<'_> = Database::open(path, open).unwrap();
+    let entries: Vec<(u32, SensorRecord)> = collect_compact(&mut db);
+    let expected: Vec<(u32, SensorRecord)> = (0..WRITE_ENTRIES)
+        .map(|idx| (idx, create_sensor_record(idx)))
+        .collect();
+    assert_eq!(entries, expected);
+
+    let items: BTreeSet<SensorRecord> = (WRITE_ENTRIES..WRITE_ENTRIES + 10)
+        .map(create_sensor_record)
+        .collect();
+    db.add_entries(items.clone()).unwrap();
+    let entry: DBEntry<SensorRecord> = db.get_by_uid(WRITE_ENTRIES + 9).unwrap();
+    assert_eq!(&entry.item, items.iter().last().unwrap());
+    db.close().unwrap();
+
+    // Chunks read from memory count their UIDs the same way
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<SensorRecord>, 2048, 4, DBCompact> =
+        DBIterator::from_slice(&bytes);
+    let uids: Vec<u32> = db_iterator.map(|entry| entry.unwrap().uid).collect();
+    assert_eq!(uids, (0..WRITE_ENTRIES + 10).collect::<Vec<u32>>());
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = BlockDatabase::open(path, open).err().unwrap();
+    assert!(matches!(
+        error,
+        DBError::EncodingMismatch { expected, found } if !expected.compact && found.compact
+    ));
+    remove_database(path);
+    remove_database(block_path);
+}
+
+pub fn compact_remove_test(path: &dyn CPathTrait, block_path: &dyn CPathTrait) {
+    println!("\n[COMPACT REMOVE TEST]");
+    remove_database(path);
+    remove_database(block_path);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut block_db: BlockDatabase<'_> = Database::create(block_path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        block_db.add_entry(&create_sensor_record(idx)).unwrap();
+    }
+
+    // Removal renumbers the entries after it like the block layout does
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompactDatabase<'_> = Database::open(path, open).unwrap();
+    for uid in [0, 10, WRITE_ENTRIES - 3] {
+        db.remove_by_uid(uid).unwrap();
+        block_db.remove_by_uid(uid).unwrap();
+        assert_eq!(collect_compact(&mut db), collect_block(&mut block_db));
+    }
+
+    db.add_entry(&create_sensor_record(WRITE_ENTRIES)).unwrap();
+    block_db
+        .add_entry(&create_sensor_record(WRITE_ENTRIES))
+        .unwrap();
+    let entries: Vec<(u32, SensorRecord)> = collect_compact(&mut db);
+    assert_eq!(entries, collect_block(&mut block_db));
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize - 2);
+    remove_database(path);
+    remove_database(block_path);
+}
+
+pub fn compact_repair_test(path: &dyn CPathTrait, quarantine: &CPath) {
+    println!("\n[COMPACT REPAIR TEST]");
+    remove_database(path);
+    remove_database(quarantine);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompactDatabase<'_> = Database::open(path, open).unwrap();
+    let offsets: Vec<usize> = chunk_offsets(&mut db);
+    db.close().unwrap();
+
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    // Store a 64-bit marker in the `i32` of entry 5
+    bytes[offsets[5] + 3] = 253;
+    // Tear the last chunk
+    bytes.pop();
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompactDatabase<'_> = Database::open(path, open).unwrap();
+    let report: DBCheckReport = db.check().unwrap();
+    let kinds: Vec<DBIssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![DBIssueKind::UndecodablePayload, DBIssueKind::TruncatedTail]
+    );
+    assert_eq!(report.issues[0].uid, Some(5));
+    assert_eq!(report.issues[1].offset, offsets[WRITE_ENTRIES as usize - 1]);
+    assert_eq!(report.entries, WRITE_ENTRIES - 2);
+
+    db.repair(quarantine).unwrap();
+    assert!(db.check().unwrap().is_clean());
+
+    // Entries after the quarantined chunk keep their UIDs
+    let uids: Vec<u32> = collect_compact(&mut db)
+        .into_iter()
+        .map(|(uid, _)| uid)
+        .collect();
+    let expected: Vec<u32> = (0..WRITE_ENTRIES - 1).filter(|uid| *uid != 5).collect();
+    assert_eq!(uids, expected);
+    assert_eq!(db.get_by_uid(6).unwrap().item, create_sensor_record(6));
+
+    db.add_entry(&create_sensor_record(WRITE_ENTRIES)).unwrap();
+    let entry: DBEntry<SensorRecord> = db.get_by_uid(WRITE_ENTRIES - 1).unwrap();
+    assert_eq!(entry.item, create_sensor_record(WRITE_ENTRIES));
+    remove_database(path);
+    remove_database(quarantine);
+}
+
+pub fn compact_capped_test(path: &dyn CPathTrait) {
+    println!("\n[COMPACT CAPPED TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(1024);
+    let open: OpenFileBox = OpenFile::new();
+    let result: Result<CompactDatabase<'_>, DBError> =
+        Database::create_capped(path, open, capacity);
+    assert!(matches!(result, Err(DBError::Unsupported)));
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompactDatabase<'_> = Database::new_capped(path, open, capacity);
+    let error: DBError = db.add_entry(&create_sensor_record(0)).unwrap_err();
+    assert!(matches!(error, DBError::Unsupported));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
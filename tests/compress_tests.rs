@@ -0,0 +1,239 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::check::DBCheckReport;
+use micro_db::check::DBIssueKind;
+use micro_db::compress::compress;
+use micro_db::compress::DBDecompressor;
+use micro_db::compress::METHOD_LZSS;
+use micro_db::compress::METHOD_STORED;
+use micro_db::config::DBCompact;
+use micro_db::config::DBCompressed;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::de::read::Reader;
+use bincode::de::read::SliceReader;
+use bincode::error::DecodeError;
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u16 = 50;
+// Format header written ahead of the chunks of a compressed file
+const FORMAT_HEADER_SIZE: usize = 24;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord, Clone)]
+pub struct SampleRecord {
+    sequence: u16,
+    samples: Vec<u16>,
+}
+
+pub fn create_sample_record(idx: u16) -> SampleRecord {
+    let sequence: u16 = idx;
+    // A sensor settling on a value repeats it for most of the window
+    let samples: Vec<u16> = (0..64).map(|sample| 1000 + idx + sample / 16).collect();
+    SampleRecord { sequence, samples }
+}
+
+type CompressedDatabase<'a> = Database<'a, BTreeSet<SampleRecord>, 2048, 4, DBCompressed>;
+
+fn write_entries<C: DBConfig>(path: &dyn CPathTrait) {
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<SampleRecord>, 2048, 4, C> =
+        Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sample_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+}
+
+fn file_length(path: &dyn CPathTrait) -> usize {
+    std::fs::metadata(path.as_str()).unwrap().len() as usize
+}
+
+fn decompress(payload: &[u8], limit: usize) -> Result<Vec<u8>, DecodeError> {
+    let reader: SliceReader<'_> = SliceReader::new(payload);
+    let mut reader: DBDecompressor<SliceReader<'_>> = DBDecompressor::new(reader, limit);
+    let mut byte: [u8; 1] = [0];
+    let mut bytes: Vec<u8> = Vec::new();
+    loop {
+        match reader.read(&mut byte) {
+            Ok(()) => bytes.push(byte[0]),
+            Err(DecodeError::UnexpectedEnd { .. }) => break,
+            Err(error) => return Err(error),
+        }
+    }
+    if reader.consumed() != payload.len() {
+        return Err(DecodeError::Other("payload left unread"));
+    }
+    Ok(bytes)
+}
+
+pub fn round_trip_test() {
+    println!("\n[COMPRESSION ROUND TRIP TEST]");
+    let mut runs: Vec<u8> = Vec::new();
+    for idx in 0..40u8 {
+        // Runs longer than a single match and repeats further than the window
+        runs.extend(std::iter::repeat_n(idx % 7, 300 + idx as usize));
+    }
+    let noise: Vec<u8> = (0..500u32).map(|idx| (idx * 7919 % 251) as u8).collect();
+
+    for bytes in [runs, noise, vec![], vec![42]] {
+        let payload: Vec<u8> = compress(&bytes);
+        assert!(payload.len() <= bytes.len() + 1);
+        assert_eq!(decompress(&payload, bytes.len()).unwrap(), bytes);
+    }
+
+    let bytes: Vec<u8> = vec![7; 1000];
+    let payload: Vec<u8> = compress(&bytes);
+    assert_eq!(payload[0], METHOD_LZSS);
+    assert!(payload.len() < 20);
+    let error: DecodeError = decompress(&payload, 999).unwrap_err();
+    assert!(matches!(error, DecodeError::LimitExceeded));
+
+    let payload: Vec<u8> = compress(&[1, 2, 3]);
+    assert_eq!(payload, vec![METHOD_STORED, 1, 2, 3]);
+}
+
+pub fn compressed_database_test(path: &dyn CPathTrait, plain_path: &dyn CPathTrait) {
+    println!("\n[COMPRESSED DATABASE TEST]");
+    remove_database(path);
+    remove_database(plain_path);
+    write_entries::<DBCompressed>(path);
+    write_entries::<DBStandardConfig>(plain_path);
+    assert!(file_length(path) * 3 < file_length(plain_path));
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompressedDatabase<'_> = Database::open(path, open).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<SampleRecord>, 2048, 4, DBCompressed> =
+        db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<SampleRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_sample_record(idx as u16));
+    }
+
+    let items: BTreeSet<SampleRecord> = (WRITE_ENTRIES..WRITE_ENTRIES + 5)
+        .map(create_sample_record)
+        .collect();
+    db.add_entries(items).unwrap();
+    db.remove_by_uid(3).unwrap();
+    let entry: DBEntry<SampleRecord> = db.get_by_uid(3).unwrap();
+    assert_eq!(entry.item, create_sample_record(4));
+    assert!(db.check().unwrap().is_clean());
+    db.close().unwrap();
+
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<SampleRecord>, 2048, 4, DBCompressed> =
+        DBIterator::from_slice(&bytes);
+    let items: Vec<SampleRecord> = db_iterator.map(|entry| entry.unwrap().item).collect();
+    let expected: Vec<SampleRecord> = (0..WRITE_ENTRIES + 5)
+        .filter(|idx| *idx != 3)
+        .map(create_sample_record)
+        .collect();
+    assert_eq!(items, expected);
+
+    // Readers without compression refuse the file instead of misreading it
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<SampleRecord>>::open(path, open)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        DBError::EncodingMismatch { expected, found } if !expected.compressed && found.compressed
+    ));
+    remove_database(path);
+    remove_database(plain_path);
+}
+
+pub fn compact_compressed_test(path: &dyn CPathTrait) {
+    println!("\n[COMPACT COMPRESSED TEST]");
+    remove_database(path);
+    write_entries::<DBCompact<DBCompressed>>(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<SampleRecord>, 2048, 4, DBCompact<DBCompressed>> =
+        Database::open(path, open).unwrap();
+    let items: Vec<SampleRecord> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    let expected: Vec<SampleRecord> = (0..WRITE_ENTRIES).map(create_sample_record).collect();
+    assert_eq!(items, expected);
+    remove_database(path);
+}
+
+pub fn corrupt_compressed_test(path: &dyn CPathTrait) {
+    println!("\n[CORRUPT COMPRESSED TEST]");
+    remove_database(path);
+    write_entries::<DBCompressed>(path);
+
+    // Mark the first entry with a compression method no reader knows
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes[FORMAT_HEADER_SIZE + 4] = 0x7F;
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompressedDatabase<'_> = Database::open(path, open).unwrap();
+    let mut db_iterator: DBIterator<'_, BTreeSet<SampleRecord>, 2048, 4, DBCompressed> =
+        db.get_iterator().unwrap();
+    let error: DBError = db_iterator.next().unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::DeserializeError {
+            uid: 0,
+            error: DecodeError::Other("unknown compression method")
+        }
+    ));
+    assert_eq!(db_iterator.next().unwrap().unwrap().uid, 1);
+    drop(db_iterator);
+
+    let report: DBCheckReport = db.check().unwrap();
+    let kinds: Vec<DBIssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+    assert_eq!(kinds, vec![DBIssueKind::UndecodablePayload]);
+    remove_database(path);
+}
+
+pub fn capped_compressed_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED COMPRESSED TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(1024);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompressedDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_sample_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: CompressedDatabase<'_> = Database::open(path, open).unwrap();
+    let entries: Vec<DBEntry<SampleRecord>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert!(!entries.is_empty());
+    assert_eq!(entries.last().unwrap().uid, WRITE_ENTRIES as u32 - 1);
+    for entry in entries {
+        assert_eq!(entry.item, create_sample_record(entry.uid as u16));
+    }
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,185 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use micro_db::config::DBBincodeConfig;
+use micro_db::config::DBEncoding;
+use micro_db::config::DBEndian;
+use micro_db::config::DBIntEncoding;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::format::FORMAT_MAGIC;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::config::BigEndian;
+use bincode::config::Fixint;
+use bincode::error::DecodeError;
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 20;
+const ENTRY_LIMIT: usize = 256;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct MeterRecord {
+    meter: u8,
+    readings: Vec<u16>,
+}
+
+pub fn create_meter_record(idx: u32, length: u16) -> MeterRecord {
+    let meter: u8 = idx as u8;
+    let readings: Vec<u16> = (0..length).map(|value| value + idx as u16).collect();
+    MeterRecord { meter, readings }
+}
+
+type FixintConfig = DBBincodeConfig<BigEndian, Fixint, ENTRY_LIMIT>;
+type FixintDatabase<'a> = Database<'a, BTreeSet<MeterRecord>, 2048, 4, FixintConfig>;
+
+pub fn encoding_test(path: &dyn CPathTrait) {
+    println!("\n[ENCODING TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FixintDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_meter_record(idx, 3)).unwrap();
+    }
+    db.close().unwrap();
+
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    assert_eq!(bytes[..4], FORMAT_MAGIC);
+    assert_eq!(bytes[4..8], 4u32.to_le_bytes());
+    assert_eq!(bytes[8..12], 0x43u32.to_le_bytes());
+    assert_eq!(bytes[12..16], (ENTRY_LIMIT as u32).to_le_bytes());
+    assert_eq!(bytes[16..24], 0u64.to_le_bytes());
+    // UID stays Little Endian, the length of the readings is a Big Endian u64
+    assert_eq!(bytes[24..28], [0, 0, 0, 0]);
+    assert_eq!(bytes[28..37], [0, 0, 0, 0, 0, 0, 0, 0, 3]);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FixintDatabase<'_> = Database::open(path, open).unwrap();
+    assert!(db.check().unwrap().is_clean());
+    let db_iterator: DBIterator<'_, BTreeSet<MeterRecord>, 2048, 4, FixintConfig> =
+        db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<MeterRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_meter_record(idx as u32, 3));
+    }
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<MeterRecord>>::open(path, open)
+        .err()
+        .unwrap();
+    let found: DBEncoding = DBEncoding {
+        endian: DBEndian::Big,
+        int_encoding: DBIntEncoding::Fixint,
+        limit: ENTRY_LIMIT,
+        uid_size: 4,
+        compact: false,
+        compressed: false,
+        encrypted: false,
+    };
+    assert!(matches!(
+        error,
+        DBError::EncodingMismatch { expected, found: actual }
+            if expected == DBEncoding::STANDARD && actual == found
+    ));
+    assert_eq!(error.kind(), DBErrorKind::InvalidInput);
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<MeterRecord>> = Database::create(path, open).unwrap();
+    db.add_entry(&create_meter_record(1, 3)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = FixintDatabase::open(path, open).err().unwrap();
+    assert!(matches!(error, DBError::EncodingMismatch { .. }));
+    remove_database(path);
+}
+
+pub fn limit_test(path: &dyn CPathTrait) {
+    println!("\n[ENCODING LIMIT TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FixintDatabase<'_> = Database::create(path, open).unwrap();
+    db.add_entry(&create_meter_record(0, 3)).unwrap();
+
+    let length: u64 = std::fs::metadata(path.as_str()).unwrap().len();
+    let error: DBError = db.add_entry(&create_meter_record(1, 200)).unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::EntryTooLarge {
+            capacity: ENTRY_LIMIT,
+            ..
+        }
+    ));
+    assert_eq!(std::fs::metadata(path.as_str()).unwrap().len(), length);
+    db.close().unwrap();
+
+    // A chunk claiming billions of readings must fail before allocating them
+    let mut file: File = OpenOptions::new().append(true).open(path.as_str()).unwrap();
+    let mut chunk: Vec<u8> = 1u32.to_le_bytes().to_vec();
+    chunk.push(7);
+    chunk.extend(0x4000_0000u64.to_be_bytes());
+    chunk.resize(16, 0);
+    chunk.extend(micro_db::EOE_BLOCK);
+    file.write_all(&chunk).unwrap();
+    drop(file);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FixintDatabase<'_> = Database::open(path, open).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<MeterRecord>, 2048, 4, FixintConfig> =
+        db.get_iterator().unwrap();
+    let entries: Vec<Result<DBEntry<MeterRecord>, DBError>> = db_iterator.collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].is_ok());
+    assert!(matches!(
+        entries[1],
+        Err(DBError::DeserializeError {
+            uid: 1,
+            error: DecodeError::LimitExceeded
+        })
+    ));
+    remove_database(path);
+}
+
+pub fn unknown_flags_test(path: &dyn CPathTrait) {
+    println!("\n[UNKNOWN FLAGS TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: FixintDatabase<'_> = Database::create(path, open).unwrap();
+    db.add_entry(&create_meter_record(0, 3)).unwrap();
+    db.close().unwrap();
+
+    // A flag from a newer format is refused rather than ignored
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes[11] |= 0x80;
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = FixintDatabase::open(path, open).err().unwrap();
+    assert!(matches!(error, DBError::UnsupportedFormat { flags } if flags == 0x8000_0043));
+    assert_eq!(error.kind(), DBErrorKind::Unsupported);
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
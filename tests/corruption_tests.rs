@@ -0,0 +1,258 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+
+use micro_db::capped::DBCapacity;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::DBClearPolicy;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBChunkIterator;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+use micro_db::EOE_BLOCK;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: usize = 40;
+const MUTATIONS: usize = 150;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct MixedRecord {
+    id: u32,
+    name: String,
+    samples: Vec<u16>,
+}
+
+pub fn create_mixed_record(idx: usize) -> MixedRecord {
+    let id: u32 = idx as u32;
+    let name: String = format!("record-{}", idx);
+    let samples: Vec<u16> = (0..idx % 9).map(|sample| sample as u16 * 300).collect();
+    MixedRecord { id, name, samples }
+}
+
+type MixedDatabase<'a> = Database<'a, BTreeSet<MixedRecord>>;
+type ConfigDatabase<'a, C> = Database<'a, BTreeSet<MixedRecord>, 2048, 4, C>;
+
+/// Deterministic xorshift generator so failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        self.next() % bound.max(1)
+    }
+}
+
+fn mutate(rng: &mut Rng, original: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = original.to_vec();
+    match rng.below(5) {
+        0 => bytes.truncate(rng.below(bytes.len())),
+        1 => {
+            for _ in 0..1 + rng.below(8) {
+                let idx: usize = rng.below(bytes.len());
+                bytes[idx] ^= 1 << rng.below(8);
+            }
+        }
+        2 => {
+            let idx: usize = rng.below(bytes.len()) & !3;
+            let end: usize = (idx + 4).min(bytes.len());
+            bytes[idx..end].copy_from_slice(&[0xC2, 0xB5, 0x64, 0x62][..end - idx]);
+        }
+        3 => {
+            let idx: usize = rng.below(bytes.len());
+            for byte in bytes[idx..].iter_mut().take(16) {
+                *byte = 0xFF;
+            }
+        }
+        _ => {
+            let length: usize = rng.below(256);
+            bytes = (0..length).map(|_| rng.next() as u8).collect();
+        }
+    }
+    bytes
+}
+
+fn exercise_database(path: &dyn CPathTrait) {
+    let open: OpenFileBox = OpenFile::new();
+    let _ = Database::<'_, BTreeSet<MixedRecord>>::open(path, open);
+
+    let open: OpenFileBox = OpenFile::new();
+    let _ = Database::<'_, BTreeSet<MixedRecord>>::open_read_only(path, open);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: MixedDatabase<'_> = Database::new(path, open);
+
+    if let Ok(db_iterator) = db.get_iterator() {
+        let db_iterator: DBIterator<'_, BTreeSet<MixedRecord>> = db_iterator;
+        for entry in db_iterator {
+            let _ = entry;
+        }
+    }
+    if let Ok(chunk_iterator) = db.get_chunk_iterator() {
+        let chunk_iterator: DBChunkIterator<'_> = chunk_iterator;
+        for chunk in chunk_iterator {
+            let _ = chunk;
+        }
+    }
+
+    let _ = db.check();
+    let _ = db.contains(&create_mixed_record(3));
+    let _ = db.get_by_uid(5);
+    let _ = db.query(|record: &MixedRecord| &record.id, 7);
+    let _ = db.remove_by_uid(2);
+    let _ = db.add_entry(&create_mixed_record(WRITE_ENTRIES));
+
+    let items: BTreeSet<MixedRecord> = BTreeSet::from([create_mixed_record(WRITE_ENTRIES + 1)]);
+    let _ = db.add_entries(items);
+    let _ = db.close();
+
+    let open: OpenFileBox = OpenFile::new();
+    let capacity: DBCapacity = DBCapacity::new(512).with_max_entries(12);
+    let mut db: MixedDatabase<'_> = Database::new_capped(path, open, capacity);
+    let _ = db.add_entry(&create_mixed_record(0));
+    if let Ok(db_iterator) = db.get_iterator() {
+        for entry in db_iterator {
+            let _ = entry;
+        }
+    }
+    let _ = db.close();
+}
+
+fn corruption_test(path: &dyn CPathTrait, original: &[u8], seed: u64) {
+    let mut rng: Rng = Rng(seed);
+    for mutation in 0..MUTATIONS {
+        let bytes: Vec<u8> = mutate(&mut rng, original);
+        std::fs::write(path.as_str(), &bytes).unwrap();
+
+        let result = catch_unwind(AssertUnwindSafe(|| exercise_database(path)));
+        assert!(
+            result.is_ok(),
+            "panicked on mutation {} of seed {}",
+            mutation,
+            seed
+        );
+    }
+    remove_database(path);
+}
+
+pub fn plain_corruption_test(path: &dyn CPathTrait) {
+    println!("\n[PLAIN CORRUPTION TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: MixedDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_mixed_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    let original: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    corruption_test(path, &original, 0x9E37_79B9_7F4A_7C15);
+}
+
+pub fn capped_corruption_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED CORRUPTION TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let capacity: DBCapacity = DBCapacity::new(512).with_max_entries(12);
+    let mut db: MixedDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_mixed_record(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    let original: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    corruption_test(path, &original, 0xD1B5_4A32_D192_ED03);
+}
+
+fn torn_append<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: ConfigDatabase<'_, C> = Database::create(path, open).unwrap();
+    db.add_entries_iter((0..4).map(create_mixed_record))
+        .unwrap();
+    db.close().unwrap();
+
+    // The last chunk loses its end, as after a torn write
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes.pop();
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    // Nothing is written after it, its UID is not continued either
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: ConfigDatabase<'_, C> = Database::new(path, open);
+    let error: DBError = db.add_entry(&create_mixed_record(4)).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(_) }));
+    assert_eq!(error.kind(), DBErrorKind::Corrupt);
+    let items: BTreeSet<MixedRecord> = BTreeSet::from([create_mixed_record(4)]);
+    let error: DBError = db.add_entries(items).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(_) }));
+    let items: [MixedRecord; 1] = [create_mixed_record(4)];
+    let error: DBError = db.add_entries_iter(items).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(_) }));
+    if C::ENCODING.compact {
+        let error: DBError = db.clear(DBClearPolicy::KeepUids).unwrap_err();
+        assert!(matches!(error, DBError::InvalidData { offset: Some(_) }));
+    }
+    db.close().unwrap();
+    assert_eq!(std::fs::read(path.as_str()).unwrap(), bytes);
+    remove_database(path);
+}
+
+pub fn torn_append_test(path: &dyn CPathTrait) {
+    println!("\n[TORN APPEND TEST]");
+    torn_append::<DBStandardConfig>(path);
+    torn_append::<DBCompact>(path);
+}
+
+pub fn corrupt_lookup_test(path: &dyn CPathTrait) {
+    println!("\n[CORRUPT LOOKUP TEST]");
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: MixedDatabase<'_> = Database::create(path, open).unwrap();
+    db.add_entries_iter((0..4).map(create_mixed_record))
+        .unwrap();
+    db.close().unwrap();
+
+    // The id of entry 1 starts with a byte no varint takes
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    let eoe: Option<usize> = bytes.chunks(4).position(|block| block == EOE_BLOCK);
+    let start: usize = (eoe.unwrap() + 2) * 4;
+    bytes[start] = 0xFF;
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    // Lookups that pass it fail instead of reporting the entry as missing
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: MixedDatabase<'_> = Database::new(path, open);
+    assert_eq!(db.get_by_uid(0).unwrap().item, create_mixed_record(0));
+    let error: DBError = db.get_by_uid(3).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 1, .. }));
+    let error: DBError = db.contains(&create_mixed_record(3)).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 1, .. }));
+    let error: DBError = db.query(|record: &MixedRecord| &record.id, 3).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 1, .. }));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
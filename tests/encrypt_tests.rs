@@ -0,0 +1,303 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::check::DBCheckReport;
+use micro_db::check::DBIssueKind;
+use micro_db::cipher::DBKey;
+use micro_db::config::DBCompact;
+use micro_db::config::DBCompressed;
+use micro_db::config::DBConfig;
+use micro_db::config::DBEncrypted;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::CPath;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u16 = 20;
+// Format and cipher fields written ahead of the chunks of an encrypted file
+const HEADER_SIZE: usize = 84;
+// Offset of the next nonce counter in the cipher fields
+const NEXT_NONCE_OFFSET: usize = 44;
+const MARKER: &[u8] = b"calibration-secret";
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord, Clone)]
+pub struct Reading {
+    sequence: u16,
+    label: String,
+}
+
+pub fn create_reading(idx: u16) -> Reading {
+    let sequence: u16 = idx;
+    let label: String = format!("{}-{}", String::from_utf8_lossy(MARKER), idx);
+    Reading { sequence, label }
+}
+
+type EncryptedDatabase<'a> = Database<'a, BTreeSet<Reading>, 2048, 4, DBEncrypted>;
+
+fn key() -> DBKey {
+    DBKey::new([7; 32], 1)
+}
+
+fn write_entries<C: DBConfig>(path: &dyn CPathTrait) {
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Reading>, 2048, 4, C> = Database::create(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_reading(idx)).unwrap();
+    }
+    db.close().unwrap();
+}
+
+fn read_items<C: DBConfig>(path: &dyn CPathTrait) -> Vec<Reading> {
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Reading>, 2048, 4, C> = Database::open(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    let items: Vec<Reading> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    items
+}
+
+fn contains(bytes: &[u8], needle: &[u8]) -> bool {
+    bytes.windows(needle.len()).any(|window| window == needle)
+}
+
+pub fn encrypted_database_test(path: &dyn CPathTrait) {
+    println!("\n[ENCRYPTED DATABASE TEST]");
+    remove_database(path);
+    write_entries::<DBEncrypted>(path);
+
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    assert!(!contains(&bytes, MARKER));
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::open(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    let entries: Vec<DBEntry<Reading>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_reading(idx as u16));
+    }
+
+    let items: BTreeSet<Reading> = (WRITE_ENTRIES..WRITE_ENTRIES + 3)
+        .map(create_reading)
+        .collect();
+    db.add_entries(items).unwrap();
+    db.remove_by_uid(2).unwrap();
+    let entry: DBEntry<Reading> = db.get_by_uid(2).unwrap();
+    assert_eq!(entry.item, create_reading(3));
+    assert!(db.check().unwrap().is_clean());
+    db.close().unwrap();
+
+    let expected: Vec<Reading> = (0..WRITE_ENTRIES + 3)
+        .filter(|idx| *idx != 2)
+        .map(create_reading)
+        .collect();
+    assert_eq!(read_items::<DBEncrypted>(path), expected);
+
+    // Readers without encryption refuse the file instead of misreading it
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<Reading>>::open(path, open)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        DBError::EncodingMismatch { expected, found } if !expected.encrypted && found.encrypted
+    ));
+    remove_database(path);
+}
+
+pub fn key_test(path: &dyn CPathTrait) {
+    println!("\n[ENCRYPTION KEY TEST]");
+    remove_database(path);
+    write_entries::<DBEncrypted>(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::open(path, open).unwrap();
+    let error: DBError = db.get_iterator().err().unwrap();
+    assert!(matches!(error, DBError::MissingKey));
+    let error: DBError = db.add_entry(&create_reading(0)).unwrap_err();
+    assert!(matches!(error, DBError::MissingKey));
+
+    let wrong: DBKey = DBKey::new([8; 32], 1);
+    let error: DBError = db.set_key(&wrong).unwrap_err();
+    assert!(matches!(error, DBError::AuthenticationFailed { uid: None }));
+    // The file keeps its salt, so a key with another salt still opens it
+    db.set_key(&DBKey::new([7; 32], 2)).unwrap();
+    assert_eq!(db.get_by_uid(4).unwrap().item, create_reading(4));
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::open_read_only(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    assert!(db.contains(&create_reading(5)).unwrap());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Reading>> = Database::new(path, open);
+    assert!(matches!(db.set_key(&key()), Err(DBError::Unsupported)));
+    remove_database(path);
+}
+
+pub fn tampered_test(path: &dyn CPathTrait, quarantine: &CPath) {
+    println!("\n[ENCRYPTION TAMPERED TEST]");
+    remove_database(path);
+    remove_database(quarantine);
+    write_entries::<DBEncrypted>(path);
+
+    // Flip a ciphertext byte of the first entry, past its UID, length and nonce
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes[HEADER_SIZE + 4 + 1 + 8] ^= 1;
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::open(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    let mut db_iterator: DBIterator<'_, BTreeSet<Reading>, 2048, 4, DBEncrypted> =
+        db.get_iterator().unwrap();
+    let error: DBError = db_iterator.next().unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::AuthenticationFailed { uid: Some(0) }
+    ));
+    assert_eq!(db_iterator.next().unwrap().unwrap().uid, 1);
+    drop(db_iterator);
+
+    let report: DBCheckReport = db.check().unwrap();
+    let kinds: Vec<DBIssueKind> = report.issues.iter().map(|issue| issue.kind).collect();
+    assert_eq!(kinds, vec![DBIssueKind::AuthenticationFailed]);
+
+    let report: DBCheckReport = db.repair(quarantine).unwrap();
+    assert_eq!(report.issues.len(), 1);
+    assert!(db.check().unwrap().is_clean());
+    let items: Vec<Reading> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    let expected: Vec<Reading> = (1..WRITE_ENTRIES).map(create_reading).collect();
+    assert_eq!(items, expected);
+    remove_database(path);
+    remove_database(quarantine);
+}
+
+pub fn binding_test(path: &dyn CPathTrait) {
+    println!("\n[ENCRYPTION BINDING TEST]");
+    remove_database(path);
+    write_entries::<DBEncrypted>(path);
+
+    // Every entry takes a chunk of the same size
+    let original: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    assert_eq!((original.len() - HEADER_SIZE) % WRITE_ENTRIES as usize, 0);
+    let chunk: usize = (original.len() - HEADER_SIZE) / WRITE_ENTRIES as usize;
+
+    // A payload copied behind another UID does not open
+    let mut bytes: Vec<u8> = original.clone();
+    let first: usize = HEADER_SIZE + chunk + 4;
+    let second: usize = HEADER_SIZE + 2 * chunk + 4;
+    bytes.copy_within(second..second + chunk - 4, first);
+    std::fs::write(path.as_str(), &bytes).unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::open(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    let mut db_iterator: DBIterator<'_, BTreeSet<Reading>, 2048, 4, DBEncrypted> =
+        db.get_iterator().unwrap();
+    assert_eq!(db_iterator.next().unwrap().unwrap().uid, 0);
+    let error: DBError = db_iterator.next().unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::AuthenticationFailed { uid: Some(1) }
+    ));
+    assert_eq!(db_iterator.next().unwrap().unwrap().item, create_reading(2));
+    drop(db_iterator);
+    db.close().unwrap();
+
+    // A counter moved back fails instead of handing out used nonces
+    let mut bytes: Vec<u8> = original.clone();
+    bytes[NEXT_NONCE_OFFSET..NEXT_NONCE_OFFSET + 8].copy_from_slice(&0u64.to_le_bytes());
+    std::fs::write(path.as_str(), &bytes).unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::open(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    let error: DBError = db.add_entry(&create_reading(0)).unwrap_err();
+    assert!(matches!(error, DBError::AuthenticationFailed { uid: None }));
+    assert_eq!(db.get_by_uid(3).unwrap().item, create_reading(3));
+    db.close().unwrap();
+
+    // A file created again with the same salt gets another subkey
+    remove_database(path);
+    write_entries::<DBEncrypted>(path);
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    assert_eq!(bytes.len(), original.len());
+    assert_ne!(bytes[HEADER_SIZE..], original[HEADER_SIZE..]);
+
+    // Removing renumbers the entries after it, which still open
+    remove_database(path);
+    write_entries::<DBCompact<DBEncrypted>>(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<Reading>, 2048, 4, DBCompact<DBEncrypted>> =
+        Database::open(path, open).unwrap();
+    db.set_key(&key()).unwrap();
+    db.remove_by_uid(0).unwrap();
+    db.remove_by_uid(5).unwrap();
+    let error: DBError = db.remove_by_uid(WRITE_ENTRIES as u32).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { .. }));
+    assert!(db.check().unwrap().is_clean());
+    db.close().unwrap();
+    let expected: Vec<Reading> = (1..WRITE_ENTRIES)
+        .filter(|idx| *idx != 6)
+        .map(create_reading)
+        .collect();
+    assert_eq!(read_items::<DBCompact<DBEncrypted>>(path), expected);
+    remove_database(path);
+}
+
+pub fn encrypted_layouts_test(path: &dyn CPathTrait) {
+    println!("\n[ENCRYPTED LAYOUTS TEST]");
+    let expected: Vec<Reading> = (0..WRITE_ENTRIES).map(create_reading).collect();
+
+    remove_database(path);
+    write_entries::<DBCompressed<DBEncrypted>>(path);
+    assert!(!contains(&std::fs::read(path.as_str()).unwrap(), MARKER));
+    assert_eq!(read_items::<DBCompressed<DBEncrypted>>(path), expected);
+
+    remove_database(path);
+    write_entries::<DBCompact<DBEncrypted>>(path);
+    assert_eq!(read_items::<DBCompact<DBEncrypted>>(path), expected);
+
+    remove_database(path);
+    let capacity: DBCapacity = DBCapacity::new(512);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EncryptedDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    db.set_key(&key()).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_reading(idx)).unwrap();
+    }
+    db.close().unwrap();
+
+    let items: Vec<Reading> = read_items::<DBEncrypted>(path);
+    assert!(!items.is_empty() && items.len() < WRITE_ENTRIES as usize);
+    assert_eq!(items, expected[expected.len() - items.len()..]);
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
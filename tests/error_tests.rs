@@ -0,0 +1,104 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use std::error::Error;
+
+use micro_db::capped::DBCapacity;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct ByteRecord {
+    value: u8,
+}
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct FlagRecord {
+    flag: bool,
+}
+
+pub fn context_test(path: &dyn CPathTrait) {
+    println!("\n[ERROR CONTEXT TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<ByteRecord>> = Database::create(path, open).unwrap();
+    db.add_entry(&ByteRecord { value: 1 }).unwrap();
+    db.add_entry(&ByteRecord { value: 200 }).unwrap();
+
+    let error: DBError = db.get_by_uid(7).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { uid: Some(7) }));
+    assert_eq!(error.kind(), DBErrorKind::NotFound);
+    assert_eq!(error.to_string(), "entry 7 not found");
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<FlagRecord>> = Database::open(path, open).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<FlagRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<Result<DBEntry<FlagRecord>, DBError>> = db_iterator.collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].is_ok());
+
+    let error: &DBError = entries[1].as_ref().unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 1, .. }));
+    assert_eq!(error.kind(), DBErrorKind::Corrupt);
+    assert!(error.source().is_some());
+    assert!(error
+        .to_string()
+        .starts_with("failed to deserialize entry 1"));
+    remove_database(path);
+}
+
+pub fn kind_test(path: &dyn CPathTrait) {
+    println!("\n[ERROR KIND TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<ByteRecord>>::open(path, open)
+        .err()
+        .unwrap();
+    assert_eq!(error.kind(), DBErrorKind::NotFound);
+    assert!(error.source().is_none());
+
+    std::fs::write(path.as_str(), b"not a database").unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<ByteRecord>>::open(path, open)
+        .err()
+        .unwrap();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(0) }));
+    assert_eq!(error.kind(), DBErrorKind::Corrupt);
+    assert_eq!(error.to_string(), "invalid data at byte 0");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let capacity: DBCapacity = DBCapacity::new(8);
+    let mut db: Database<'_, BTreeSet<ByteRecord>> =
+        Database::create_capped(path, open, capacity).unwrap();
+    // The chunk takes 12 bytes and its length block 4 more
+    let error: DBError = db.add_entry(&ByteRecord { value: 1 }).unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::EntryTooLarge {
+            size: 16,
+            capacity: 8
+        }
+    ));
+    assert_eq!(error.kind(), DBErrorKind::InvalidInput);
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
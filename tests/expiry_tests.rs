@@ -0,0 +1,141 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::expiry::DBExpiring;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::ClockBox;
+use micro_db::traits::ClockTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 30;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Alert {
+    code: u32,
+    message: String,
+}
+
+pub fn create_alert(idx: u32) -> Alert {
+    let code: u32 = idx;
+    let message: String = format!("alert-{}", idx);
+    Alert { code, message }
+}
+
+/// Clock the test moves by hand.
+#[derive(Clone)]
+pub struct ManualClock {
+    time: Rc<Cell<u64>>,
+}
+
+impl ClockTrait for ManualClock {
+    fn now(&self) -> u64 {
+        self.time.get()
+    }
+}
+
+type Alerts<'a, C> = DBExpiring<'a, Alert, 2048, 4, C>;
+
+fn open_alerts<'a, C: DBConfig>(path: &dyn CPathTrait, clock: &ManualClock) -> Alerts<'a, C> {
+    let open: OpenFileBox = OpenFile::new();
+    let clock: ClockBox = Box::new(clock.clone());
+    DBExpiring::new(Database::open_or_create(path, open).unwrap(), clock)
+}
+
+fn codes<C: DBConfig>(alerts: &mut Alerts<'_, C>) -> Vec<u32> {
+    let entries: Vec<DBEntry<Alert, C::UID>> = alerts
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    entries.iter().map(|entry| entry.item.code).collect()
+}
+
+pub fn expiry_test(path: &dyn CPathTrait) {
+    println!("\n[EXPIRY TEST]");
+    remove_database(path);
+
+    let time: Rc<Cell<u64>> = Rc::new(Cell::new(100));
+    let clock: ManualClock = ManualClock { time: time.clone() };
+    let mut alerts: Alerts<'_, DBStandardConfig> = open_alerts(path, &clock);
+    alerts.add_entry(&create_alert(0)).unwrap();
+    alerts.add_entry_with_ttl(&create_alert(1), 10).unwrap();
+    alerts.add_entry_with_ttl(&create_alert(2), 50).unwrap();
+    alerts
+        .add_entry_with_ttl(&create_alert(3), u64::MAX)
+        .unwrap();
+    alerts.close().unwrap();
+
+    let mut alerts: Alerts<'_, DBStandardConfig> = open_alerts(path, &clock);
+    assert_eq!(codes(&mut alerts), vec![0, 1, 2, 3]);
+    time.set(110);
+    assert_eq!(codes(&mut alerts), vec![0, 2, 3]);
+    let error: DBError = alerts.get_by_uid(1).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { uid: Some(1) }));
+    assert_eq!(alerts.get_by_uid(2).unwrap().item, create_alert(2));
+
+    time.set(1_000);
+    assert_eq!(codes(&mut alerts), vec![0, 3]);
+    let entry: DBEntry<Alert> = alerts.get_by_uid(3).unwrap();
+    assert_eq!(entry.uid, 3);
+
+    // Expired entries stay in the file until purged
+    assert_eq!(
+        alerts.database.get_by_uid(1).unwrap().item.item,
+        create_alert(1)
+    );
+    remove_database(path);
+}
+
+fn purge_alerts<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let time: Rc<Cell<u64>> = Rc::new(Cell::new(0));
+    let clock: ManualClock = ManualClock { time: time.clone() };
+    let mut alerts: Alerts<'_, C> = open_alerts(path, &clock);
+    for idx in 0..WRITE_ENTRIES {
+        match idx % 3 {
+            0 => alerts.add_entry(&create_alert(idx)).unwrap(),
+            1 => alerts.add_entry_with_ttl(&create_alert(idx), 5).unwrap(),
+            _ => alerts.add_entry_with_ttl(&create_alert(idx), 500).unwrap(),
+        }
+    }
+    assert_eq!(alerts.purge_expired().unwrap(), 0);
+
+    time.set(5);
+    assert_eq!(alerts.purge_expired().unwrap(), WRITE_ENTRIES as usize / 3);
+    assert!(alerts.database.check().unwrap().is_clean());
+    let expected: Vec<u32> = (0..WRITE_ENTRIES).filter(|idx| idx % 3 != 1).collect();
+    assert_eq!(codes(&mut alerts), expected);
+    assert_eq!(alerts.get_by_uid(1).unwrap().item, create_alert(2));
+    alerts.close().unwrap();
+
+    let mut alerts: Alerts<'_, C> = open_alerts(path, &clock);
+    time.set(500);
+    assert_eq!(alerts.purge_expired().unwrap(), WRITE_ENTRIES as usize / 3);
+    let expected: Vec<u32> = (0..WRITE_ENTRIES).filter(|idx| idx % 3 == 0).collect();
+    assert_eq!(codes(&mut alerts), expected);
+    remove_database(path);
+}
+
+pub fn purge_expired_test(path: &dyn CPathTrait) {
+    println!("\n[PURGE EXPIRED TEST]");
+    purge_alerts::<DBStandardConfig>(path);
+    purge_alerts::<DBCompact>(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,175 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use std::fs::File;
+use std::fs::OpenOptions;
+
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::heapless::DBSliceStorage;
+use micro_db::heapless::HeaplessDatabase;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u16 = 20;
+const STORAGE_SIZE: usize = 520;
+const ENTRY_BUFFER_SIZE: usize = 16;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct ReadingRecord {
+    channel: u16,
+    samples: [u8; 5],
+}
+
+pub fn create_reading_record(idx: u16) -> ReadingRecord {
+    let channel: u16 = idx * 300;
+    let samples: [u8; 5] = [idx as u8; 5];
+    ReadingRecord { channel, samples }
+}
+
+type SliceDatabase<'a> = HeaplessDatabase<DBSliceStorage<'a>, ReadingRecord>;
+
+pub fn slice_storage_test() {
+    println!("\n[HEAPLESS SLICE STORAGE TEST]");
+    let mut storage_buffer: [u8; STORAGE_SIZE] = [0; STORAGE_SIZE];
+    let mut entry_buffer: [u8; ENTRY_BUFFER_SIZE] = [0; ENTRY_BUFFER_SIZE];
+
+    let storage: DBSliceStorage<'_> = DBSliceStorage::new(&mut storage_buffer, 0);
+    let mut db: SliceDatabase<'_> = HeaplessDatabase::open(storage).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        let uid: u32 = db
+            .add_entry(&create_reading_record(idx), &mut entry_buffer)
+            .unwrap();
+        assert_eq!(uid, idx as u32);
+    }
+
+    let entry: DBEntry<ReadingRecord> = db.get_by_uid(7, &mut entry_buffer).unwrap();
+    assert_eq!(entry.item, create_reading_record(7));
+    assert!(db
+        .contains(&create_reading_record(19), &mut entry_buffer)
+        .unwrap());
+
+    let mut count: u16 = 0;
+    for (idx, entry) in db.get_iterator(&mut entry_buffer).enumerate() {
+        assert_eq!(entry.unwrap().item, create_reading_record(idx as u16));
+        count += 1;
+    }
+    assert_eq!(count, WRITE_ENTRIES);
+
+    let mut result: Result<u32, DBError> = Ok(0);
+    while result.is_ok() {
+        result = db.add_entry(&create_reading_record(0), &mut entry_buffer);
+    }
+    assert_eq!(result.unwrap_err().kind(), DBErrorKind::StorageFull);
+
+    let length: usize = db.into_storage().unwrap().len();
+
+    // The failed write left a torn chunk behind for reopening to detect
+    let storage: DBSliceStorage<'_> = DBSliceStorage::new(&mut storage_buffer, length);
+    let mut db: SliceDatabase<'_> = HeaplessDatabase::open(storage).unwrap();
+    let next_uid: u32 = db.next_uid.unwrap();
+    let total: usize = db.get_iterator(&mut entry_buffer).count();
+    assert_eq!(total, next_uid as usize);
+
+    assert!(db.torn);
+    let error: DBError = db
+        .add_entry(&create_reading_record(1), &mut [0; 2])
+        .unwrap_err();
+    assert!(matches!(error, DBError::SerializeError(_)));
+    let error: DBError = db.get_by_uid(0, &mut [0; 4]).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { uid: Some(0) }));
+}
+
+pub fn torn_tail_test() {
+    println!("\n[HEAPLESS TORN TAIL TEST]");
+    let mut storage_buffer: [u8; STORAGE_SIZE] = [0xAB; STORAGE_SIZE];
+    let mut entry_buffer: [u8; ENTRY_BUFFER_SIZE] = [0; ENTRY_BUFFER_SIZE];
+
+    let storage: DBSliceStorage<'_> = DBSliceStorage::new(&mut storage_buffer, 0);
+    let mut db: SliceDatabase<'_> = HeaplessDatabase::open(storage).unwrap();
+    for idx in 0..3 {
+        db.add_entry(&create_reading_record(idx), &mut entry_buffer)
+            .unwrap();
+    }
+    let length: usize = db.into_storage().unwrap().len();
+
+    // Leave part of a chunk behind, as an interrupted write would
+    let storage: DBSliceStorage<'_> = DBSliceStorage::new(&mut storage_buffer, length + 22);
+    let mut db: SliceDatabase<'_> = HeaplessDatabase::open(storage).unwrap();
+    assert!(db.torn);
+    assert_eq!(db.end, length);
+
+    db.add_entry(&create_reading_record(3), &mut entry_buffer)
+        .unwrap();
+    assert!(!db.torn);
+    let storage: DBSliceStorage<'_> = db.into_storage().unwrap();
+    assert_eq!(storage.len(), length + 16);
+
+    let mut db: SliceDatabase<'_> = HeaplessDatabase::open(storage).unwrap();
+    assert!(!db.torn);
+    let entries: Vec<DBEntry<ReadingRecord>> = db
+        .get_iterator(&mut entry_buffer)
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[3].item, create_reading_record(3));
+}
+
+pub fn file_compat_test(path: &dyn CPathTrait) {
+    println!("\n[HEAPLESS FILE COMPAT TEST]");
+    remove_database(path);
+    let mut entry_buffer: [u8; ENTRY_BUFFER_SIZE] = [0; ENTRY_BUFFER_SIZE];
+
+    let file: File = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.as_str())
+        .unwrap();
+    let mut db: HeaplessDatabase<File, ReadingRecord> = HeaplessDatabase::open(file).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_reading_record(idx), &mut entry_buffer)
+            .unwrap();
+    }
+    drop(db.into_storage().unwrap());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<ReadingRecord>> = Database::open(path, open).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<ReadingRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<ReadingRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_reading_record(idx as u16));
+    }
+    db.add_entry(&create_reading_record(WRITE_ENTRIES)).unwrap();
+    db.close().unwrap();
+
+    let file: File = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path.as_str())
+        .unwrap();
+    let mut db: HeaplessDatabase<File, ReadingRecord> = HeaplessDatabase::open(file).unwrap();
+    assert_eq!(db.next_uid, Some(WRITE_ENTRIES as u32 + 1));
+    let entry: DBEntry<ReadingRecord> = db
+        .get_by_uid(WRITE_ENTRIES as u32, &mut entry_buffer)
+        .unwrap();
+    assert_eq!(entry.item, create_reading_record(WRITE_ENTRIES));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
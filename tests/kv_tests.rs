@@ -0,0 +1,164 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::kv::DBKvRecord;
+use micro_db::kv::KvStore;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+const WRITE_ENTRIES: u32 = 40;
+
+type Store<'a, C> = KvStore<'a, String, u32, 2048, 4, C>;
+
+fn open_store<'a, C: DBConfig>(path: &dyn CPathTrait) -> Store<'a, C> {
+    let open: OpenFileBox = OpenFile::new();
+    KvStore::new(Database::open_or_create(path, open).unwrap())
+}
+
+fn file_length(path: &dyn CPathTrait) -> usize {
+    std::fs::metadata(path.as_str()).unwrap().len() as usize
+}
+
+pub fn kv_store_test(path: &dyn CPathTrait) {
+    println!("\n[KV STORE TEST]");
+    remove_database(path);
+
+    let mut store: Store<'_, DBStandardConfig> = open_store(path);
+    assert_eq!(store.get(&String::from("volume")).unwrap(), None);
+    store.set(String::from("volume"), 3).unwrap();
+    store.set(String::from("brightness"), 80).unwrap();
+    store.set(String::from("volume"), 7).unwrap();
+    assert_eq!(store.get(&String::from("volume")).unwrap(), Some(7));
+
+    assert_eq!(store.remove(&String::from("brightness")).unwrap(), Some(80));
+    assert_eq!(store.remove(&String::from("brightness")).unwrap(), None);
+    assert_eq!(store.get(&String::from("brightness")).unwrap(), None);
+    store.set(String::from("contrast"), 50).unwrap();
+
+    // Records written around the store are seen once it reloads its index
+    let record: DBKvRecord<String, u32> = DBKvRecord::Set {
+        key: String::from("contrast"),
+        value: 60,
+    };
+    store.database.add_entry(&record).unwrap();
+    assert_eq!(store.get(&String::from("contrast")).unwrap(), Some(50));
+    store.reload();
+    assert_eq!(store.get(&String::from("contrast")).unwrap(), Some(60));
+    let record: DBKvRecord<String, u32> = DBKvRecord::Set {
+        key: String::from("contrast"),
+        value: 50,
+    };
+    store.database.add_entry(&record).unwrap();
+    store.close().unwrap();
+
+    let mut store: Store<'_, DBStandardConfig> = open_store(path);
+    let pairs: Vec<(String, u32)> = store.iter().unwrap().map(|pair| pair.unwrap()).collect();
+    let expected: Vec<(String, u32)> =
+        vec![(String::from("volume"), 7), (String::from("contrast"), 50)];
+    assert_eq!(pairs, expected);
+    remove_database(path);
+}
+
+fn compact_store<C: DBConfig>(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    remove_database(path);
+    // A scratch file left by an interrupted compaction is overwritten
+    std::fs::write(scratch.as_str(), [0xFF; 64]).unwrap();
+    let mut store: Store<'_, C> = open_store(path);
+    for idx in 0..WRITE_ENTRIES {
+        store.set(format!("key-{}", idx % 4), idx).unwrap();
+    }
+    store.remove(&String::from("key-1")).unwrap();
+    let length: usize = file_length(path);
+
+    // 4 keys were set 10 times each and one of them was removed
+    assert_eq!(
+        store.compact(scratch).unwrap(),
+        WRITE_ENTRIES as usize + 1 - 3
+    );
+    assert!(file_length(path) * 5 < length);
+    assert!(std::fs::metadata(scratch.as_str()).is_err());
+    assert_eq!(store.get(&String::from("key-2")).unwrap(), Some(38));
+    assert_eq!(store.compact(scratch).unwrap(), 0);
+    assert!(store.database.check().unwrap().is_clean());
+    store.close().unwrap();
+
+    let mut store: Store<'_, C> = open_store(path);
+    assert_eq!(store.get(&String::from("key-0")).unwrap(), Some(36));
+    assert_eq!(store.get(&String::from("key-1")).unwrap(), None);
+    store.set(String::from("key-0"), 100).unwrap();
+    let pairs: Vec<(String, u32)> = store.iter().unwrap().map(|pair| pair.unwrap()).collect();
+    let expected: Vec<(String, u32)> = vec![
+        (String::from("key-2"), 38),
+        (String::from("key-3"), 39),
+        (String::from("key-0"), 100),
+    ];
+    assert_eq!(pairs, expected);
+
+    let uids: Vec<u64> = store
+        .database
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().uid.into())
+        .collect();
+    assert_eq!(uids, vec![0, 1, 2, 3]);
+    remove_database(path);
+}
+
+pub fn kv_compact_test(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    println!("\n[KV COMPACT TEST]");
+    compact_store::<DBStandardConfig>(path, scratch);
+    compact_store::<DBCompact>(path, scratch);
+}
+
+pub fn retain_test(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    println!("\n[RETAIN TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<u32>> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&(idx * 10)).unwrap();
+    }
+    assert_eq!(db.retain_by_uid(|uid| uid % 3 == 0).unwrap(), 26);
+    let entries: Vec<DBEntry<u32>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), 14);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, idx as u32 * 30);
+    }
+    db.add_entry(&7).unwrap();
+    assert_eq!(db.get_by_uid(14).unwrap().item, 7);
+    db.close().unwrap();
+
+    // A torn tail is refused before anything moves
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes.extend([1, 2, 3]);
+    std::fs::write(path.as_str(), &bytes).unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<u32>> = Database::open(path, open).unwrap();
+    let error: DBError = db.retain_by_uid(|_| false).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(_) }));
+    let error: DBError = db.rewrite_by_uid(scratch, |_| false).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(_) }));
+    assert_eq!(std::fs::read(path.as_str()).unwrap(), bytes);
+    assert!(std::fs::metadata(scratch.as_str()).is_err());
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
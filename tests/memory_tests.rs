@@ -0,0 +1,163 @@
+// Installs emballoc as the global allocator to measure real heap usage, so it
+// runs as its own test binary instead of a module of run_tests.
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::impls::CPath;
+use micro_db::impls::OpenFile;
+use micro_db::memory::DBMemoryStats;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+use micro_db::CACHE_SIZE;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const HEAP_SIZE: usize = 1024 * 1024;
+const WRITE_ENTRIES: u32 = 2000;
+const LARGE_VALUES: u16 = 1000;
+const BUDGET: usize = 512;
+
+#[global_allocator]
+static ALLOCATOR: emballoc::Allocator<HEAP_SIZE> = emballoc::Allocator::new();
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct LogRecord {
+    level: u8,
+    values: Vec<u16>,
+}
+
+pub fn create_log_record(idx: u32, length: u16) -> LogRecord {
+    let level: u8 = idx as u8;
+    let values: Vec<u16> = (0..length).map(|value| value.wrapping_mul(31)).collect();
+    LogRecord { level, values }
+}
+
+type LogDatabase<'a> = Database<'a, BTreeSet<LogRecord>>;
+
+#[test]
+fn memory_test() {
+    let path: CPath = CPath::new("./memory_database.mdb");
+    scan_usage_test(&path);
+    budget_test(&path);
+    remove_database(&path);
+}
+
+fn scan_usage_test(path: &dyn CPathTrait) {
+    println!("\n[SCAN USAGE TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: LogDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_log_record(idx, 40)).unwrap();
+    }
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: LogDatabase<'_> = Database::open(path, open).unwrap();
+    let file_size: usize = std::fs::metadata(path.as_str()).unwrap().len() as usize;
+
+    // The stream cache lives inside the iterator, which is kept on the stack
+    let baseline: usize = ALLOCATOR.get_used_memory();
+    let mut peak: usize = baseline;
+    let mut count: u32 = 0;
+    let db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    for entry in db_iterator {
+        let entry: DBEntry<LogRecord> = entry.unwrap();
+        peak = peak.max(ALLOCATOR.get_used_memory());
+        assert_eq!(entry.uid, count);
+        count += 1;
+    }
+    assert_eq!(count, WRITE_ENTRIES);
+
+    let stats: DBMemoryStats = db.memory_stats();
+    println!(
+        "File: {file_size}B, heap growth: {}B, {stats:?}",
+        peak - baseline
+    );
+    assert_eq!(stats.cache_size, CACHE_SIZE);
+    assert_eq!(stats.budget, None);
+    assert!(stats.peak_transient > 0);
+    assert!(peak - baseline <= 4 * stats.peak_transient);
+    assert!(peak - baseline < file_size / 100);
+
+    // Holding on to the entries does show up in the measurement
+    let db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<LogRecord>> = db_iterator.flatten().collect();
+    assert!(ALLOCATOR.get_used_memory() - baseline > file_size / 4);
+    drop(entries);
+    db.close().unwrap();
+}
+
+fn budget_test(path: &dyn CPathTrait) {
+    println!("\n[MEMORY BUDGET TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: LogDatabase<'_> = Database::create(path, open).unwrap();
+    db.set_memory_budget(Some(BUDGET));
+    for idx in 0..10 {
+        db.add_entry(&create_log_record(idx, 8)).unwrap();
+    }
+
+    let length: u64 = std::fs::metadata(path.as_str()).unwrap().len();
+    let error: DBError = db
+        .add_entry(&create_log_record(10, LARGE_VALUES))
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        DBError::MemoryBudgetExceeded { budget: BUDGET, .. }
+    ));
+    assert_eq!(error.kind(), DBErrorKind::OutOfMemory);
+    assert_eq!(std::fs::metadata(path.as_str()).unwrap().len(), length);
+
+    db.set_memory_budget(None);
+    db.add_entry(&create_log_record(10, LARGE_VALUES)).unwrap();
+    db.add_entry(&create_log_record(11, 8)).unwrap();
+    db.set_memory_budget(Some(BUDGET));
+
+    // The oversized entry fails on its own, the scan goes on after it
+    let db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<Result<DBEntry<LogRecord>, DBError>> = db_iterator.collect();
+    assert_eq!(entries.len(), 12);
+    for (idx, entry) in entries.iter().enumerate() {
+        match entry {
+            Err(DBError::MemoryBudgetExceeded { .. }) => assert_eq!(idx, 10),
+            Ok(entry) => assert_eq!(entry.uid, idx as u32),
+            Err(error) => panic!("unexpected error: {error}"),
+        }
+    }
+    let chunks: Vec<Result<Vec<u8>, DBError>> = db.get_chunk_iterator().unwrap().collect();
+    assert_eq!(chunks.len(), 12);
+    assert!(chunks[10].is_err() && chunks[11].is_ok());
+
+    let length: u64 = std::fs::metadata(path.as_str()).unwrap().len();
+    let error: DBError = db.remove_by_uid(3).unwrap_err();
+    assert!(matches!(error, DBError::MemoryBudgetExceeded { .. }));
+    assert_eq!(std::fs::metadata(path.as_str()).unwrap().len(), length);
+    assert!(matches!(
+        db.check(),
+        Err(DBError::MemoryBudgetExceeded { .. })
+    ));
+    assert!(db.memory_stats().peak_transient <= length as usize);
+
+    db.set_memory_budget(None);
+    db.remove_by_uid(3).unwrap();
+    assert!(db.check().unwrap().is_clean());
+    let entry: DBEntry<LogRecord> = db.get_by_uid(9).unwrap();
+    assert_eq!(entry.item, create_log_record(10, LARGE_VALUES));
+    db.close().unwrap();
+}
+
+fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,288 @@
+#![cfg(feature = "mmap")]
+
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::DBClearPolicy;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::error::DBErrorKind;
+use micro_db::impls::OpenFile;
+use micro_db::mmap::OpenMmapFile;
+use micro_db::structures::DBChunkIterator;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::structures::DBSliceIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: usize = 500;
+
+type LogDatabase<'a, C> = Database<'a, BTreeSet<LogRecord>, 2048, 4, C>;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct LogRecord {
+    idx: usize,
+    message: Vec<u8>,
+}
+
+pub fn create_log_record(idx: usize) -> LogRecord {
+    let message: Vec<u8> = vec![idx as u8; idx % 17];
+    LogRecord { idx, message }
+}
+
+pub fn write_entries(path: &dyn CPathTrait) {
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+
+    let items: BTreeSet<LogRecord> = (0..WRITE_ENTRIES).map(create_log_record).collect();
+    db.add_entries(items).unwrap();
+}
+
+pub fn mapped_iterator_test(path: &dyn CPathTrait) {
+    println!("\n[MAPPED ITERATOR TEST]");
+    remove_database(path);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    let db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+
+    let entries: Vec<DBEntry<LogRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_log_record(idx));
+    }
+
+    let entry: DBEntry<LogRecord> = db.get_by_uid(321).unwrap();
+    assert_eq!(entry.item, create_log_record(321));
+    remove_database(path);
+}
+
+pub fn slice_iterator_test(path: &dyn CPathTrait) {
+    println!("\n[SLICE ITERATOR TEST]");
+    remove_database(path);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    let chunk_iterator: DBChunkIterator<'_> = db.get_chunk_iterator().unwrap();
+    let chunks: Vec<Vec<u8>> = chunk_iterator.map(|chunk| chunk.unwrap()).collect();
+    assert!(matches!(db.get_slice_iterator(), Err(DBError::Unsupported)));
+
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    let slice_iterator: DBSliceIterator<'_> = db.get_slice_iterator().unwrap();
+    let slices: Vec<&[u8]> = slice_iterator.map(|chunk| chunk.unwrap()).collect();
+
+    assert_eq!(slices.len(), chunks.len());
+    for (slice, chunk) in slices.iter().zip(chunks.iter()) {
+        assert_eq!(*slice, chunk.as_slice());
+    }
+    drop(db);
+
+    // The header that keeps the next UID is skipped too
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    db.clear(DBClearPolicy::KeepUids).unwrap();
+    db.add_entry(&create_log_record(1)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    let entries: Vec<DBEntry<LogRecord>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].uid, WRITE_ENTRIES as u32);
+    assert_eq!(db.get_slice_iterator().unwrap().count(), 1);
+    remove_database(path);
+}
+
+pub fn mapped_capped_test(path: &dyn CPathTrait) {
+    println!("\n[MAPPED CAPPED TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(512);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new_capped(path, open, capacity);
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_log_record(idx)).unwrap();
+    }
+    let db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    let expected: Vec<DBEntry<LogRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    drop(db);
+
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new_capped(path, open, capacity);
+    let db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<LogRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+
+    assert!(!entries.is_empty());
+    assert_eq!(entries.len(), expected.len());
+    for (entry, expected) in entries.iter().zip(expected.iter()) {
+        assert_eq!(entry.uid, expected.uid);
+        assert_eq!(entry.item, expected.item);
+    }
+    assert_eq!(entries.last().unwrap().uid, WRITE_ENTRIES as u32 - 1);
+    remove_database(path);
+}
+
+pub fn mapped_seek_test(path: &dyn CPathTrait) {
+    println!("\n[MAPPED SEEK TEST]");
+    remove_database(path);
+    write_entries(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    let mut db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    db_iterator.nth(99).unwrap().unwrap();
+    let position: usize = db_iterator.position();
+    drop(db_iterator);
+    drop(db);
+
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    let mut db_iterator: DBIterator<'_, BTreeSet<LogRecord>> = db.get_iterator().unwrap();
+    db_iterator.seek(position, 99).unwrap();
+    let entry: DBEntry<LogRecord> = db_iterator.next().unwrap().unwrap();
+    assert_eq!(entry.uid, 100);
+    assert_eq!(entry.item, create_log_record(100));
+    assert_eq!(db_iterator.count(), WRITE_ENTRIES - 101);
+    remove_database(path);
+}
+
+pub fn mapped_destroy_test(path: &dyn CPathTrait) {
+    println!("\n[MAPPED DESTROY TEST]");
+    remove_database(path);
+    write_entries(path);
+
+    // Mapped files cannot be deleted through the backend
+    let open: OpenFileBox = OpenMmapFile::new();
+    let db: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    assert!(matches!(db.destroy(), Err(DBError::Unsupported)));
+    assert!(std::path::Path::new(path.as_str()).exists());
+    remove_database(path);
+}
+
+pub fn mapped_lock_test(path: &dyn CPathTrait) {
+    println!("\n[MAPPED LOCK TEST]");
+    remove_database(path);
+    write_entries(path);
+
+    // The mapping holds a shared lock until it is closed
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut mapped: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    assert_eq!(mapped.count().unwrap(), WRITE_ENTRIES);
+
+    let file: std::fs::File = std::fs::File::open(path.as_str()).unwrap();
+    assert!(file.try_lock().is_err());
+    file.try_lock_shared().unwrap();
+    file.unlock().unwrap();
+    mapped.close().unwrap();
+    file.try_lock().unwrap();
+
+    // And the file cannot be mapped while another handle locks it
+    let open: OpenFileBox = OpenMmapFile::new();
+    let mut mapped: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    assert!(matches!(mapped.count(), Err(DBError::Locked)));
+    file.unlock().unwrap();
+    assert_eq!(mapped.count().unwrap(), WRITE_ENTRIES);
+    mapped.close().unwrap();
+
+    // Plain handles do not lock, a reader and a writer share the file
+    let open: OpenFileBox = OpenFile::new();
+    let mut reader: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    assert_eq!(reader.count().unwrap(), WRITE_ENTRIES);
+    let open: OpenFileBox = OpenFile::new();
+    let mut writer: Database<'_, BTreeSet<LogRecord>> = Database::new(path, open);
+    writer.add_entry(&create_log_record(0)).unwrap();
+    writer.close().unwrap();
+    assert_eq!(reader.count().unwrap(), WRITE_ENTRIES + 1);
+    remove_database(path);
+}
+
+// Entries and chunk lengths read back through `open`, errors by their kind
+type ReadBack = (
+    Vec<Result<u32, DBErrorKind>>,
+    Vec<Result<usize, DBErrorKind>>,
+);
+
+fn read_back<C: DBConfig<UID = u32>>(path: &dyn CPathTrait, mapped: bool) -> ReadBack {
+    let open: OpenFileBox = match mapped {
+        true => OpenMmapFile::new(),
+        false => OpenFile::new(),
+    };
+    let mut db: LogDatabase<'_, C> = Database::new(path, open);
+    let entries: Vec<Result<u32, DBErrorKind>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.map(|entry| entry.uid).map_err(|error| error.kind()))
+        .collect();
+    let chunks: Vec<Result<usize, DBErrorKind>> = match mapped {
+        true => db
+            .get_slice_iterator()
+            .unwrap()
+            .map(|chunk| chunk.map(|chunk| chunk.len()).map_err(|error| error.kind()))
+            .collect(),
+        false => db
+            .get_chunk_iterator()
+            .unwrap()
+            .map(|chunk| chunk.map(|chunk| chunk.len()).map_err(|error| error.kind()))
+            .collect(),
+    };
+    (entries, chunks)
+}
+
+fn corrupt_entries<C: DBConfig<UID = u32>>(path: &dyn CPathTrait, tail: &[u8], ends: bool) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: LogDatabase<'_, C> = Database::new(path, open);
+    db.add_entries_iter((0..10).map(create_log_record)).unwrap();
+    db.close().unwrap();
+
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes.extend_from_slice(tail);
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    // Both backends read the same, and stop at the first chunk that does not
+    let (entries, chunks) = read_back::<C>(path, false);
+    let (mapped_entries, mapped_chunks) = read_back::<C>(path, true);
+    assert_eq!(mapped_entries, entries);
+    assert_eq!(mapped_chunks, chunks);
+
+    let expected: Vec<Result<u32, DBErrorKind>> = (0..10).map(Ok).collect();
+    assert_eq!(entries[..10], expected);
+    assert_eq!(chunks.len(), entries.len());
+    match ends {
+        true => assert_eq!(entries.len(), 10),
+        false => assert_eq!(entries[10..], [Err(DBErrorKind::Corrupt)]),
+    }
+    remove_database(path);
+}
+
+pub fn mapped_corrupt_test(path: &dyn CPathTrait) {
+    println!("\n[MAPPED CORRUPT TEST]");
+    // A torn tail ends the entries
+    corrupt_entries::<DBStandardConfig>(path, &[7, 0, 0, 0, 1, 2], true);
+    corrupt_entries::<DBCompact>(path, &[0x01, 0x20, 1, 2], true);
+    // A chunk header that never ends is an error, and nothing follows it
+    corrupt_entries::<DBCompact>(path, &[0xFF; 24], false);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,129 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 50;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct EventRecord {
+    code: u16,
+    payload: [u8; 3],
+}
+
+pub fn create_event_record(idx: u32) -> EventRecord {
+    let code: u16 = idx as u16;
+    let payload: [u8; 3] = [idx as u8; 3];
+    EventRecord { code, payload }
+}
+
+type EventDatabase<'a> = Database<'a, BTreeSet<EventRecord>>;
+
+fn count_entries(db: &mut EventDatabase<'_>) -> usize {
+    let db_iterator: DBIterator<'_, BTreeSet<EventRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<EventRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    entries.len()
+}
+
+pub fn create_test(path: &dyn CPathTrait) {
+    println!("\n[CREATE TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EventDatabase<'_> = Database::create(path, open).unwrap();
+    assert!(std::fs::metadata(path.as_str()).is_ok());
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_event_record(idx)).unwrap();
+    }
+    drop(db);
+
+    let open: OpenFileBox = OpenFile::new();
+    let db: Result<EventDatabase<'_>, DBError> = Database::create(path, open);
+    assert!(matches!(db, Err(DBError::AlreadyExists)));
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EventDatabase<'_> = Database::open(path, open).unwrap();
+    assert_eq!(count_entries(&mut db), WRITE_ENTRIES as usize);
+    remove_database(path);
+}
+
+pub fn open_test(path: &dyn CPathTrait) {
+    println!("\n[OPEN TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let db: Result<EventDatabase<'_>, DBError> = Database::open(path, open);
+    assert!(matches!(db, Err(DBError::NotFound)));
+    assert!(std::fs::metadata(path.as_str()).is_err());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EventDatabase<'_> = Database::open_or_create(path, open).unwrap();
+    assert_eq!(count_entries(&mut db), 0);
+    db.add_entry(&create_event_record(0)).unwrap();
+    drop(db);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EventDatabase<'_> = Database::open_or_create(path, open).unwrap();
+    let entry: DBEntry<EventRecord> = db.get_by_uid(0).unwrap();
+    assert_eq!(entry.item, create_event_record(0));
+    remove_database(path);
+}
+
+pub fn open_capped_test(path: &dyn CPathTrait) {
+    println!("\n[OPEN CAPPED TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(256).with_max_entries(8);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EventDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_event_record(idx)).unwrap();
+    }
+    drop(db);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: EventDatabase<'_> = Database::open(path, open).unwrap();
+    assert_eq!(db.capacity, Some(capacity));
+    assert_eq!(count_entries(&mut db), 8);
+    drop(db);
+
+    let open: OpenFileBox = OpenFile::new();
+    let other: DBCapacity = DBCapacity::new(1024);
+    let db: EventDatabase<'_> = Database::open_or_create_capped(path, open, other).unwrap();
+    assert_eq!(db.capacity, Some(capacity));
+    remove_database(path);
+}
+
+pub fn open_invalid_test(path: &dyn CPathTrait) {
+    println!("\n[OPEN INVALID TEST]");
+    remove_database(path);
+
+    std::fs::write(path.as_str(), b"not a database").unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let db: Result<EventDatabase<'_>, DBError> = Database::open(path, open);
+    assert!(matches!(db, Err(DBError::InvalidData { .. })));
+
+    let open: OpenFileBox = OpenFile::new();
+    let capacity: DBCapacity = DBCapacity::new(256);
+    let db: Result<EventDatabase<'_>, DBError> =
+        Database::open_or_create_capped(path, open, capacity);
+    assert!(matches!(db, Err(DBError::InvalidData { .. })));
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
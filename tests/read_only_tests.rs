@@ -0,0 +1,130 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::FileBox;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 100;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct ConfigRecord {
+    key: u32,
+    value: u64,
+}
+
+pub fn create_config_record(idx: u32) -> ConfigRecord {
+    let key: u32 = idx;
+    let value: u64 = idx as u64 * 7;
+    ConfigRecord { key, value }
+}
+
+/// Opener that fails the test as soon as write access is requested.
+pub struct NoWriteOpenFile {
+    inner: OpenFileBox,
+}
+
+impl OpenFileTrait for NoWriteOpenFile {
+    fn new() -> OpenFileBox
+    where
+        Self: Sized,
+    {
+        let inner: OpenFileBox = OpenFile::new();
+        Box::new(Self { inner })
+    }
+
+    fn boxed(&self) -> OpenFileBox {
+        let inner: OpenFileBox = self.inner.boxed();
+        Box::new(Self { inner })
+    }
+
+    fn read(&mut self, read: bool) {
+        self.inner.read(read);
+    }
+
+    fn write(&mut self, write: bool) {
+        assert!(!write, "write access requested");
+        self.inner.write(write);
+    }
+
+    fn append(&mut self, append: bool) {
+        assert!(!append, "append access requested");
+        self.inner.append(append);
+    }
+
+    fn truncate(&mut self, truncate: bool) {
+        assert!(!truncate, "truncate access requested");
+        self.inner.truncate(truncate);
+    }
+
+    fn create(&mut self, create: bool) {
+        assert!(!create, "create access requested");
+        self.inner.create(create);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn open(&self, path: &dyn CPathTrait) -> Result<FileBox, DBError> {
+        self.inner.open(path)
+    }
+}
+
+pub fn missing_file_test(path: &dyn CPathTrait) {
+    println!("\n[READ ONLY MISSING FILE TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = NoWriteOpenFile::new();
+    let db: Result<Database<'_, BTreeSet<ConfigRecord>>, DBError> =
+        Database::open_read_only(path, open);
+    assert!(matches!(db, Err(DBError::NotFound)));
+    assert!(std::fs::metadata(path.as_str()).is_err());
+}
+
+pub fn read_only_test(path: &dyn CPathTrait) {
+    println!("\n[READ ONLY TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<ConfigRecord>> = Database::new(path, open);
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_config_record(idx)).unwrap();
+    }
+    drop(db);
+    let file_size: u64 = std::fs::metadata(path.as_str()).unwrap().len();
+
+    let open: OpenFileBox = NoWriteOpenFile::new();
+    let mut db: Database<'_, BTreeSet<ConfigRecord>> =
+        Database::open_read_only(path, open).unwrap();
+
+    let db_iterator: DBIterator<'_, BTreeSet<ConfigRecord>> = db.get_iterator().unwrap();
+    let entries: Vec<DBEntry<ConfigRecord>> = db_iterator.map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize);
+
+    let item: ConfigRecord = create_config_record(WRITE_ENTRIES);
+    assert!(matches!(db.add_entry(&item), Err(DBError::ReadOnly)));
+    assert!(matches!(db.remove_by_uid(0), Err(DBError::ReadOnly)));
+
+    let items: BTreeSet<ConfigRecord> = BTreeSet::from([item]);
+    assert!(matches!(db.add_entries(items), Err(DBError::ReadOnly)));
+
+    assert!(db.contains(&create_config_record(42)).unwrap());
+    assert_eq!(std::fs::metadata(path.as_str()).unwrap().len(), file_size);
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,155 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::DBRemoved;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 1000;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Job {
+    id: u32,
+    done: bool,
+}
+
+pub fn create_job(idx: u32) -> Job {
+    let id: u32 = idx;
+    let done: bool = idx % 3 != 1;
+    Job { id, done }
+}
+
+type JobDatabase<'a, C> = Database<'a, BTreeSet<Job>, 2048, 4, C>;
+
+fn write_jobs<'a, C: DBConfig>(path: &dyn CPathTrait) -> JobDatabase<'a, C> {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: JobDatabase<'_, C> = Database::create(path, open).unwrap();
+    db.add_entries_iter((0..WRITE_ENTRIES).map(create_job))
+        .unwrap();
+    db
+}
+
+fn read_ids<C: DBConfig<UID = u32>>(db: &mut JobDatabase<'_, C>) -> Vec<u32> {
+    let entries: Vec<DBEntry<Job>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+    }
+    entries.iter().map(|entry| entry.item.id).collect()
+}
+
+fn remove_jobs<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    let mut db: JobDatabase<'_, C> = write_jobs(path);
+    let removed: DBRemoved<u32> = db.remove_where(|job| job.done).unwrap();
+    let expected: Vec<u32> = (0..WRITE_ENTRIES)
+        .filter(|idx| create_job(*idx).done)
+        .collect();
+    assert_eq!(removed.count, expected.len());
+    assert_eq!(removed.uids, expected);
+    let expected: Vec<u32> = (0..WRITE_ENTRIES)
+        .filter(|idx| !create_job(*idx).done)
+        .collect();
+    assert_eq!(read_ids(&mut db), expected);
+    let removed: DBRemoved<u32> = db.remove_where(|job| job.done).unwrap();
+    assert_eq!(removed.count, 0);
+    assert!(removed.uids.is_empty());
+
+    // UIDs are those of the entries before the removal
+    let removed: DBRemoved<u32> = db.remove_range(10..20).unwrap();
+    assert_eq!(removed.count, 10);
+    assert_eq!(removed.uids, (10..20).collect::<Vec<u32>>());
+    let removed: DBRemoved<u32> = db.remove_range(320..400).unwrap();
+    assert_eq!(
+        removed.uids,
+        (320..expected.len() as u32 - 10).collect::<Vec<u32>>()
+    );
+    assert_eq!(removed.count, removed.uids.len());
+    let mut remaining: Vec<u32> = expected[..10].to_vec();
+    remaining.extend(&expected[20..330]);
+    assert_eq!(read_ids(&mut db), remaining);
+    assert_eq!(db.remove_range(500..600).unwrap().count, 0);
+    assert!(db.check().unwrap().is_clean());
+    remove_database(path);
+}
+
+pub fn remove_where_test(path: &dyn CPathTrait) {
+    println!("\n[REMOVE WHERE TEST]");
+    remove_jobs::<DBStandardConfig>(path);
+    remove_jobs::<DBCompact>(path);
+}
+
+pub fn remove_refused_test(path: &dyn CPathTrait) {
+    println!("\n[REMOVE REFUSED TEST]");
+    let db: JobDatabase<'_, DBStandardConfig> = write_jobs(path);
+    db.close().unwrap();
+
+    // Store an invalid bool in the first entry, past its UID block and id
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes[5] = 7;
+    std::fs::write(path.as_str(), &bytes).unwrap();
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: JobDatabase<'_, DBStandardConfig> = Database::open(path, open).unwrap();
+
+    // An entry that fails to read stops the removal before anything moves
+    let error: DBError = db.remove_where(|job| job.done).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(0) }));
+    let error: DBError = db.remove_range(0..5).unwrap_err();
+    assert!(matches!(error, DBError::InvalidData { offset: Some(0) }));
+    assert_eq!(std::fs::read(path.as_str()).unwrap(), bytes);
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(1024);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: JobDatabase<'_, DBStandardConfig> =
+        Database::create_capped(path, open, capacity).unwrap();
+    db.add_entry(&create_job(0)).unwrap();
+    let error: DBError = db.remove_range(0..1).unwrap_err();
+    assert!(matches!(error, DBError::Unsupported));
+    remove_database(path);
+}
+
+fn remove_missing<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    let mut db: JobDatabase<'_, C> = write_jobs(path);
+    let bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+
+    // UIDs at and past the end of the file are not stored
+    for uid in [WRITE_ENTRIES, WRITE_ENTRIES + 10] {
+        let error: DBError = db.remove_by_uid(uid).unwrap_err();
+        assert!(
+            matches!(error, DBError::EntryNotFound { uid: Some(found) } if found == uid as u64)
+        );
+    }
+    assert_eq!(std::fs::read(path.as_str()).unwrap(), bytes);
+    db.remove_by_uid(WRITE_ENTRIES - 1).unwrap();
+    let error: DBError = db.remove_by_uid(WRITE_ENTRIES - 1).unwrap_err();
+    assert!(matches!(error, DBError::EntryNotFound { .. }));
+    remove_database(path);
+}
+
+pub fn remove_missing_test(path: &dyn CPathTrait) {
+    println!("\n[REMOVE MISSING TEST]");
+    remove_missing::<DBStandardConfig>(path);
+    remove_missing::<DBCompact>(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
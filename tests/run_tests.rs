@@ -1,4 +1,31 @@
+mod aggregate_tests;
+mod append_tests;
+mod block_size_tests;
+mod capped_tests;
+mod catalog_tests;
+mod check_tests;
+mod clear_tests;
+mod compact_tests;
+mod compress_tests;
+mod config_tests;
+mod corruption_tests;
+mod encrypt_tests;
+mod error_tests;
+mod expiry_tests;
+#[allow(dead_code, clippy::let_and_return, clippy::manual_flatten)]
 mod fixed_size_tests;
+mod heapless_tests;
+mod kv_tests;
+#[cfg(feature = "mmap")]
+mod mmap_tests;
+mod open_tests;
+mod read_only_tests;
+mod remove_tests;
+mod schema_tests;
+mod series_tests;
+mod stream_tests;
+mod uid_tests;
+#[allow(dead_code, clippy::let_and_return, clippy::manual_flatten)]
 mod variable_size_tests;
 
 extern crate micro_db;
@@ -60,3 +87,239 @@ fn variable_size_test() {
     // variable_size_tests::print_database(&path);
     variable_size_tests::database_integrity_test(&path);
 }
+
+#[test]
+fn capped_test() {
+    println!("[CAPPED TEST]");
+    let path: CPath = CPath::new("./capped_database.mdb");
+
+    capped_tests::max_entries_test(&path);
+    capped_tests::max_bytes_test(&path);
+    capped_tests::reopen_test(&path);
+    capped_tests::capacity_limit_test(&path);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mmap_test() {
+    println!("[MMAP TEST]");
+    let path: CPath = CPath::new("./mmap_database.mdb");
+
+    mmap_tests::mapped_iterator_test(&path);
+    mmap_tests::slice_iterator_test(&path);
+    mmap_tests::mapped_capped_test(&path);
+    mmap_tests::mapped_seek_test(&path);
+    mmap_tests::mapped_destroy_test(&path);
+    mmap_tests::mapped_lock_test(&path);
+    mmap_tests::mapped_corrupt_test(&path);
+}
+
+#[test]
+fn read_only_test() {
+    println!("[READ ONLY TEST]");
+    let path: CPath = CPath::new("./read_only_database.mdb");
+
+    read_only_tests::missing_file_test(&path);
+    read_only_tests::read_only_test(&path);
+}
+
+#[test]
+fn open_test() {
+    println!("[OPEN TEST]");
+    let path: CPath = CPath::new("./open_database.mdb");
+
+    open_tests::create_test(&path);
+    open_tests::open_test(&path);
+    open_tests::open_capped_test(&path);
+    open_tests::open_invalid_test(&path);
+}
+
+#[test]
+fn corruption_test() {
+    println!("[CORRUPTION TEST]");
+    let path: CPath = CPath::new("./corruption_database.mdb");
+
+    corruption_tests::plain_corruption_test(&path);
+    corruption_tests::capped_corruption_test(&path);
+    corruption_tests::torn_append_test(&path);
+    corruption_tests::corrupt_lookup_test(&path);
+}
+
+#[test]
+fn error_test() {
+    println!("[ERROR TEST]");
+    let path: CPath = CPath::new("./error_database.mdb");
+    error_tests::context_test(&path);
+    error_tests::kind_test(&path);
+}
+
+#[test]
+fn check_test() {
+    println!("[CHECK TEST]");
+    let path: CPath = CPath::new("./check_database.mdb");
+    let quarantine: CPath = CPath::new("./check_database.mdb.quarantine");
+    check_tests::clean_check_test(&path);
+    check_tests::torn_tail_test(&path, &quarantine);
+    check_tests::quarantine_test(&path, &quarantine);
+}
+
+#[test]
+fn stream_test() {
+    println!("[STREAM TEST]");
+    let path: CPath = CPath::new("./stream_database.mdb");
+    stream_tests::large_entry_test(&path);
+    stream_tests::skip_invalid_test(&path);
+    stream_tests::remove_test(&path);
+}
+
+#[test]
+fn heapless_test() {
+    println!("[HEAPLESS TEST]");
+    let path: CPath = CPath::new("./heapless_database.mdb");
+    heapless_tests::slice_storage_test();
+    heapless_tests::torn_tail_test();
+    heapless_tests::file_compat_test(&path);
+}
+
+#[test]
+fn block_size_test() {
+    println!("[BLOCK SIZE TEST]");
+    let path: CPath = CPath::new("./block_size_database.mdb");
+    block_size_tests::small_cache_test(&path);
+    block_size_tests::block_size_test(&path);
+    block_size_tests::mismatch_test(&path);
+}
+
+#[test]
+fn config_test() {
+    println!("[CONFIG TEST]");
+    let path: CPath = CPath::new("./config_database.mdb");
+    config_tests::encoding_test(&path);
+    config_tests::limit_test(&path);
+    config_tests::unknown_flags_test(&path);
+}
+
+#[test]
+fn uid_test() {
+    println!("[UID TEST]");
+    let path: CPath = CPath::new("./uid_database.mdb");
+    uid_tests::overflow_test(&path);
+    uid_tests::wide_uid_test(&path);
+    uid_tests::capped_uid_test(&path);
+}
+
+#[test]
+fn compact_test() {
+    println!("[COMPACT TEST]");
+    let path: CPath = CPath::new("./compact_database.mdb");
+    let block_path: CPath = CPath::new("./compact_block_database.mdb");
+    let quarantine: CPath = CPath::new("./compact_database.mdb.quarantine");
+    compact_tests::compact_layout_test(&path, &block_path);
+    compact_tests::compact_remove_test(&path, &block_path);
+    compact_tests::compact_repair_test(&path, &quarantine);
+    compact_tests::compact_capped_test(&path);
+}
+
+#[test]
+fn compress_test() {
+    println!("[COMPRESS TEST]");
+    let path: CPath = CPath::new("./compress_database.mdb");
+    let plain_path: CPath = CPath::new("./compress_plain_database.mdb");
+    compress_tests::round_trip_test();
+    compress_tests::compressed_database_test(&path, &plain_path);
+    compress_tests::compact_compressed_test(&path);
+    compress_tests::corrupt_compressed_test(&path);
+    compress_tests::capped_compressed_test(&path);
+}
+
+#[test]
+fn encrypt_test() {
+    println!("[ENCRYPT TEST]");
+    let path: CPath = CPath::new("./encrypt_database.mdb");
+    let quarantine: CPath = CPath::new("./encrypt_database.mdb.quarantine");
+    encrypt_tests::encrypted_database_test(&path);
+    encrypt_tests::key_test(&path);
+    encrypt_tests::tampered_test(&path, &quarantine);
+    encrypt_tests::binding_test(&path);
+    encrypt_tests::encrypted_layouts_test(&path);
+}
+
+#[test]
+fn catalog_test() {
+    println!("[CATALOG TEST]");
+    let path: CPath = CPath::new("./catalog_database.mdb");
+    catalog_tests::collections_test(&path);
+    catalog_tests::catalog_layers_test(&path);
+}
+
+#[test]
+fn kv_test() {
+    println!("[KV TEST]");
+    let path: CPath = CPath::new("./kv_database.mdb");
+    let scratch: CPath = CPath::new("./kv_database.mdb.compact");
+    kv_tests::kv_store_test(&path);
+    kv_tests::kv_compact_test(&path, &scratch);
+    kv_tests::retain_test(&path, &scratch);
+}
+
+#[test]
+fn series_test() {
+    println!("[SERIES TEST]");
+    let path: CPath = CPath::new("./series_database.mdb");
+    let index_path: CPath = CPath::new("./series_database.mdb.index");
+    series_tests::series_test(&path);
+    series_tests::series_empty_test(&path);
+    series_tests::series_index_test(&path, &index_path);
+}
+
+#[test]
+fn expiry_test() {
+    println!("[EXPIRY TEST]");
+    let path: CPath = CPath::new("./expiry_database.mdb");
+    expiry_tests::expiry_test(&path);
+    expiry_tests::purge_expired_test(&path);
+}
+
+#[test]
+fn schema_test() {
+    println!("[SCHEMA TEST]");
+    let path: CPath = CPath::new("./schema_database.mdb");
+    let scratch: CPath = CPath::new("./schema_database.mdb.migrate");
+    schema_tests::versioned_read_test(&path, &scratch);
+    schema_tests::migrate_test(&path, &scratch);
+    schema_tests::tagged_plain_test(&path, &scratch);
+}
+
+#[test]
+fn aggregate_test() {
+    println!("[AGGREGATE TEST]");
+    let path: CPath = CPath::new("./aggregate_database.mdb");
+    aggregate_tests::aggregate_test(&path);
+    aggregate_tests::aggregate_error_test(&path);
+}
+
+#[test]
+fn append_test() {
+    println!("[APPEND TEST]");
+    let path: CPath = CPath::new("./append_database.mdb");
+    append_tests::append_iter_test(&path);
+    append_tests::capped_append_iter_test(&path);
+}
+
+#[test]
+fn remove_test() {
+    println!("[REMOVE TEST]");
+    let path: CPath = CPath::new("./remove_database.mdb");
+    remove_tests::remove_where_test(&path);
+    remove_tests::remove_refused_test(&path);
+    remove_tests::remove_missing_test(&path);
+}
+
+#[test]
+fn clear_test() {
+    println!("[CLEAR TEST]");
+    let path: CPath = CPath::new("./clear_database.mdb");
+    clear_tests::clear_test(&path);
+    clear_tests::clear_capped_test(&path);
+    clear_tests::destroy_test(&path);
+}
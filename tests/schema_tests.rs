@@ -0,0 +1,314 @@
+extern crate alloc;
+extern crate micro_db;
+use alloc::collections::BTreeSet;
+
+use micro_db::config::DBBincodeConfig;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::DBClearPolicy;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::schema::DBFirstVersion;
+use micro_db::schema::DBVersioned;
+use micro_db::schema::DBVersionedRecord;
+use micro_db::schema::VersionedItem;
+use micro_db::schema::VERSION_TAG;
+use micro_db::structures::DBEntry;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::config::Fixint;
+use bincode::config::LittleEndian;
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 12;
+
+type FixintConfig = DBBincodeConfig<LittleEndian, Fixint>;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct ReadingV1 {
+    value: u32,
+}
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct ReadingV2 {
+    value: u32,
+    unit: String,
+}
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct ReadingV3 {
+    value: u64,
+    unit: String,
+    scale: u8,
+}
+
+impl VersionedItem for ReadingV1 {
+    const VERSION: u32 = 1;
+    type Previous = DBFirstVersion;
+
+    fn upgrade(_: DBFirstVersion) -> Self {
+        unreachable!()
+    }
+}
+
+impl VersionedItem for ReadingV2 {
+    const VERSION: u32 = 2;
+    type Previous = ReadingV1;
+
+    fn upgrade(previous: ReadingV1) -> Self {
+        let value: u32 = previous.value;
+        let unit: String = String::from("mV");
+        ReadingV2 { value, unit }
+    }
+}
+
+impl VersionedItem for ReadingV3 {
+    const VERSION: u32 = 3;
+    type Previous = ReadingV2;
+
+    fn upgrade(previous: ReadingV2) -> Self {
+        let value: u64 = previous.value as u64;
+        let unit: String = previous.unit;
+        let scale: u8 = 1;
+        ReadingV3 { value, unit, scale }
+    }
+}
+
+fn open_versioned<'a, I: VersionedItem, C: DBConfig>(
+    path: &dyn CPathTrait,
+) -> DBVersioned<'a, I, 2048, 4, C> {
+    let open: OpenFileBox = OpenFile::new();
+    DBVersioned::new(Database::open_or_create(path, open).unwrap())
+}
+
+fn expected(idx: u32) -> ReadingV3 {
+    let value: u64 = idx as u64;
+    let (unit, scale): (&str, u8) = match idx % 3 {
+        0 => ("mV", 1),
+        1 => ("V", 1),
+        _ => ("A", 10),
+    };
+    let unit: String = String::from(unit);
+    ReadingV3 { value, unit, scale }
+}
+
+fn write_versions<C: DBConfig>(path: &dyn CPathTrait) {
+    remove_database(path);
+    for idx in 0..WRITE_ENTRIES {
+        let value: u32 = idx;
+        match idx % 3 {
+            0 => {
+                let mut db: DBVersioned<'_, ReadingV1, 2048, 4, C> = open_versioned(path);
+                db.add_entry(&ReadingV1 { value }).unwrap();
+            }
+            1 => {
+                let mut db: DBVersioned<'_, ReadingV2, 2048, 4, C> = open_versioned(path);
+                let unit: String = String::from("V");
+                db.add_entry(&ReadingV2 { value, unit }).unwrap();
+            }
+            _ => {
+                let mut db: DBVersioned<'_, ReadingV3, 2048, 4, C> = open_versioned(path);
+                let value: u64 = value as u64;
+                let unit: String = String::from("A");
+                db.add_entry(&ReadingV3 {
+                    value,
+                    unit,
+                    scale: 10,
+                })
+                .unwrap();
+            }
+        }
+    }
+}
+
+fn migrate_versions<C: DBConfig<UID = u32>>(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    write_versions::<C>(path);
+
+    let mut db: DBVersioned<'_, ReadingV3, 2048, 4, C> = open_versioned(path);
+    let entries: Vec<DBEntry<ReadingV3>> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), WRITE_ENTRIES as usize);
+    for (idx, entry) in entries.iter().enumerate() {
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, expected(idx as u32));
+    }
+    assert_eq!(db.get_by_uid(4).unwrap().item, expected(4));
+    db.close().unwrap();
+
+    // Newer versions are not read as older ones
+    let mut old: DBVersioned<'_, ReadingV2, 2048, 4, C> = open_versioned(path);
+    let error: DBError = old.get_by_uid(2).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 2, .. }));
+    assert_eq!(old.get_by_uid(1).unwrap().item.unit, "V");
+    drop(old);
+
+    // A failed migration leaves the file as it was
+    let mut old: DBVersioned<'_, ReadingV2, 2048, 4, C> = open_versioned(path);
+    let before: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    let error: DBError = old.migrate(scratch).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 2, .. }));
+    assert_eq!(std::fs::read(path.as_str()).unwrap(), before);
+    assert!(std::fs::metadata(scratch.as_str()).is_err());
+    drop(old);
+
+    let mut db: DBVersioned<'_, ReadingV3, 2048, 4, C> = open_versioned(path);
+    assert_eq!(db.migrate(scratch).unwrap(), WRITE_ENTRIES as usize * 2 / 3);
+    assert_eq!(db.migrate(scratch).unwrap(), 0);
+    assert!(std::fs::metadata(scratch.as_str()).is_err());
+    assert!(db.database.check().unwrap().is_clean());
+    let records: Vec<DBEntry<DBVersionedRecord<ReadingV3>>> = db
+        .database
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(records.len(), WRITE_ENTRIES as usize);
+    assert!(records.iter().all(|entry| entry.item.version == 3));
+    db.close().unwrap();
+
+    let mut db: DBVersioned<'_, ReadingV3, 2048, 4, C> = open_versioned(path);
+    let items: Vec<ReadingV3> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    assert_eq!(
+        items,
+        (0..WRITE_ENTRIES).map(expected).collect::<Vec<ReadingV3>>()
+    );
+    remove_database(path);
+}
+
+pub fn versioned_read_test(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    println!("\n[SCHEMA VERSIONED READ TEST]");
+    migrate_versions::<DBStandardConfig>(path, scratch);
+}
+
+fn migrate_plain<C: DBConfig<UID = u32>>(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    remove_database(path);
+    let open: OpenFileBox = OpenFile::new();
+    let mut plain: Database<'_, BTreeSet<ReadingV1>, 2048, 4, C> =
+        Database::open_or_create(path, open).unwrap();
+    for value in 0..WRITE_ENTRIES {
+        plain.add_entry(&ReadingV1 { value }).unwrap();
+    }
+    plain.close().unwrap();
+
+    let mut db: DBVersioned<'_, ReadingV3, 2048, 4, C> = open_versioned(path);
+    let unit: String = String::from("A");
+    let value: u64 = WRITE_ENTRIES as u64;
+    db.add_entry(&ReadingV3 {
+        value,
+        unit,
+        scale: 10,
+    })
+    .unwrap();
+    assert_eq!(db.get_by_uid(3).unwrap().item.unit, "mV");
+    let items: Vec<ReadingV3> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    assert_eq!(items.len(), WRITE_ENTRIES as usize + 1);
+    assert_eq!(items[5].value, 5);
+    assert_eq!(items[WRITE_ENTRIES as usize].scale, 10);
+
+    assert_eq!(db.migrate(scratch).unwrap(), WRITE_ENTRIES as usize);
+    let records: Vec<DBEntry<DBVersionedRecord<ReadingV3>, u32>> = db
+        .database
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert!(records.iter().all(|entry| entry.item.version == 3));
+    db.close().unwrap();
+
+    // Plain entries are read as the first version by any later one
+    let open: OpenFileBox = OpenFile::new();
+    let mut plain: Database<'_, BTreeSet<ReadingV1>, 2048, 4, C> =
+        Database::open_or_create(path, open).unwrap();
+    plain.clear(DBClearPolicy::ResetUids).unwrap();
+    plain.add_entry(&ReadingV1 { value: 7 }).unwrap();
+    plain.close().unwrap();
+    let mut db: DBVersioned<'_, ReadingV1, 2048, 4, C> = open_versioned(path);
+    assert_eq!(db.get_by_uid(0).unwrap().item, ReadingV1 { value: 7 });
+    assert_eq!(db.migrate(scratch).unwrap(), 1);
+    assert_eq!(db.get_by_uid(0).unwrap().item, ReadingV1 { value: 7 });
+    assert_eq!(db.migrate(scratch).unwrap(), 0);
+    db.close().unwrap();
+    remove_database(path);
+}
+
+pub fn migrate_test(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    println!("\n[SCHEMA MIGRATE TEST]");
+    migrate_versions::<DBCompact>(path, scratch);
+    migrate_plain::<DBStandardConfig>(path, scratch);
+    migrate_plain::<DBCompact>(path, scratch);
+
+    // Current entries ahead of an outdated one keep their place
+    remove_database(path);
+    let mut db: DBVersioned<'_, ReadingV2, 2048, 4, DBStandardConfig> = open_versioned(path);
+    let unit: String = String::from("V");
+    db.add_entry(&ReadingV2 { value: 0, unit }).unwrap();
+    drop(db);
+    let mut db: DBVersioned<'_, ReadingV1, 2048, 4, DBStandardConfig> = open_versioned(path);
+    db.add_entry(&ReadingV1 { value: 1 }).unwrap();
+    drop(db);
+    let mut db: DBVersioned<'_, ReadingV2, 2048, 4, DBStandardConfig> = open_versioned(path);
+    assert_eq!(db.migrate(scratch).unwrap(), 1);
+    let items: Vec<ReadingV2> = db
+        .get_iterator()
+        .unwrap()
+        .map(|entry| entry.unwrap().item)
+        .collect();
+    assert_eq!(items[1].unit, "mV");
+    assert_eq!(items.len(), 2);
+    remove_database(path);
+}
+
+pub fn tagged_plain_test(path: &dyn CPathTrait, scratch: &dyn CPathTrait) {
+    println!("\n[SCHEMA TAGGED PLAIN TEST]");
+    remove_database(path);
+
+    // Under the fixint encoding a plain entry may start with the tag
+    let tagged: u32 = u32::from_le_bytes(VERSION_TAG);
+    let open: OpenFileBox = OpenFile::new();
+    let mut plain: Database<'_, BTreeSet<ReadingV1>, 2048, 4, FixintConfig> =
+        Database::create(path, open).unwrap();
+    plain.add_entry(&ReadingV1 { value: tagged }).unwrap();
+    plain.add_entry(&ReadingV1 { value: 1 }).unwrap();
+    plain.close().unwrap();
+
+    let mut db: DBVersioned<'_, ReadingV2, 2048, 4, FixintConfig> = open_versioned(path);
+    let unit: String = String::from("V");
+    db.add_entry(&ReadingV2 { value: 2, unit }).unwrap();
+    let record: DBVersionedRecord<ReadingV2> = db.database.get_by_uid(0).unwrap().item;
+    assert_eq!(record.version, 0);
+    for _ in 0..2 {
+        let items: Vec<ReadingV2> = db
+            .get_iterator()
+            .unwrap()
+            .map(|entry| entry.unwrap().item)
+            .collect();
+        let values: Vec<u32> = items.iter().map(|item| item.value).collect();
+        assert_eq!(values, vec![tagged, 1, 2]);
+        let units: Vec<&str> = items.iter().map(|item| item.unit.as_str()).collect();
+        assert_eq!(units, vec!["mV", "mV", "V"]);
+        db.migrate(scratch).unwrap();
+    }
+    assert_eq!(db.database.get_by_uid(0).unwrap().item.version, 2);
+    db.close().unwrap();
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
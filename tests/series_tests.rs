@@ -0,0 +1,258 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+use core::cell::Cell;
+
+use micro_db::capped::DBCapacity;
+use micro_db::config::DBCompact;
+use micro_db::config::DBConfig;
+use micro_db::config::DBStandardConfig;
+use micro_db::db::DBClearPolicy;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::series::DBTime;
+use micro_db::series::DBTimeIndexDatabase;
+use micro_db::series::DBTimeSeries;
+use micro_db::series::DBTimedDatabase;
+use micro_db::series::DBTimedEntry;
+use micro_db::series::DBTimedRecord;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::ClockBox;
+use micro_db::traits::ClockTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+use micro_db::EOE_BLOCK;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: u32 = 200;
+const START: u64 = 1_000;
+const STEP: u64 = 10;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, Clone)]
+pub struct Sample {
+    sensor: u8,
+    value: u32,
+}
+
+pub fn create_sample(idx: u32) -> Sample {
+    let sensor: u8 = (idx % 3) as u8;
+    let value: u32 = idx * 7;
+    Sample { sensor, value }
+}
+
+/// Clock the test moves by hand.
+#[derive(Clone)]
+pub struct ManualClock {
+    time: Rc<Cell<u64>>,
+}
+
+impl ClockTrait for ManualClock {
+    fn now(&self) -> u64 {
+        self.time.get()
+    }
+}
+
+type Series<'a, C> = DBTimeSeries<'a, Sample, 2048, 4, C>;
+
+fn open_series<'a, C: DBConfig>(path: &dyn CPathTrait, clock: &ManualClock) -> Series<'a, C> {
+    let open: OpenFileBox = OpenFile::new();
+    let clock: ClockBox = Box::new(clock.clone());
+    DBTimeSeries::new(Database::open_or_create(path, open).unwrap(), clock)
+}
+
+fn times(entries: Vec<Result<DBTimedEntry<Sample>, DBError>>) -> Vec<u64> {
+    entries
+        .into_iter()
+        .map(|entry| entry.unwrap().time)
+        .collect()
+}
+
+fn write_series<C: DBConfig<UID = u32>>(path: &dyn CPathTrait) {
+    remove_database(path);
+    let time: Rc<Cell<u64>> = Rc::new(Cell::new(START));
+    let clock: ManualClock = ManualClock { time: time.clone() };
+    let mut series: Series<'_, C> = open_series(path, &clock);
+    for idx in 0..WRITE_ENTRIES {
+        time.set(START + idx as u64 * STEP);
+        assert_eq!(series.add_entry(&create_sample(idx)).unwrap(), time.get());
+    }
+    series.close().unwrap();
+
+    let mut series: Series<'_, C> = open_series(path, &clock);
+    let entries: Vec<DBTimedEntry<Sample>> = series
+        .range_time(1_500..1_600)
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .collect();
+    assert_eq!(entries.len(), 10);
+    for (idx, entry) in (50..).zip(entries.iter()) {
+        assert_eq!(entry.uid, idx);
+        assert_eq!(entry.time, START + idx as u64 * STEP);
+        assert_eq!(entry.item, create_sample(idx));
+    }
+    assert_eq!(series.index.len(), 4);
+
+    let latest: DBTimedEntry<Sample> = series.latest().unwrap().unwrap();
+    assert_eq!(latest.uid, WRITE_ENTRIES - 1);
+    assert_eq!(latest.item, create_sample(WRITE_ENTRIES - 1));
+
+    // A clock going backwards stamps the entry with the latest time
+    time.set(START);
+    let last: u64 = START + (WRITE_ENTRIES as u64 - 1) * STEP;
+    assert_eq!(series.add_entry(&create_sample(0)).unwrap(), last);
+    assert_eq!(series.latest().unwrap().unwrap().time, last);
+
+    let entries: Vec<Result<DBTimedEntry<Sample>, DBError>> =
+        series.range_time(last..u64::MAX).unwrap().collect();
+    assert_eq!(times(entries), vec![last, last]);
+    let entries: Vec<Result<DBTimedEntry<Sample>, DBError>> =
+        series.range_time(0..START).unwrap().collect();
+    assert!(entries.is_empty());
+
+    // One entry of every 100 long bucket
+    let entries: Vec<Result<DBTimedEntry<Sample>, DBError>> = series
+        .range_time(1_005..2_000)
+        .unwrap()
+        .downsample(100)
+        .collect();
+    let expected: Vec<u64> = (0..10).map(|bucket| 1_010 + bucket * 100).collect();
+    assert_eq!(times(entries), expected);
+    assert!(series.database.check().unwrap().is_clean());
+    remove_database(path);
+}
+
+pub fn series_test(path: &dyn CPathTrait) {
+    println!("\n[TIME SERIES TEST]");
+    write_series::<DBStandardConfig>(path);
+    write_series::<DBCompact>(path);
+}
+
+pub fn series_empty_test(path: &dyn CPathTrait) {
+    println!("\n[TIME SERIES EMPTY TEST]");
+    remove_database(path);
+
+    let clock: ManualClock = ManualClock {
+        time: Rc::new(Cell::new(START)),
+    };
+    let mut series: Series<'_, DBStandardConfig> = open_series(path, &clock);
+    assert!(series.latest().unwrap().is_none());
+    assert_eq!(series.range_time(0..u64::MAX).unwrap().count(), 0);
+    series.add_entry(&create_sample(1)).unwrap();
+    assert_eq!(series.range_time(0..u64::MAX).unwrap().count(), 1);
+
+    // Entries after the header of a kept UID are scanned from the header
+    series.database.clear(DBClearPolicy::KeepUids).unwrap();
+    series.reload();
+    assert!(series.latest().unwrap().is_none());
+    series.add_entry(&create_sample(2)).unwrap();
+    assert_eq!(series.latest().unwrap().unwrap().uid, 1);
+    assert_eq!(series.range_time(0..u64::MAX).unwrap().count(), 1);
+    series.close().unwrap();
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(1024);
+    let open: OpenFileBox = OpenFile::new();
+    let clock: ClockBox = Box::new(clock);
+    let database: Database<'_, BTreeSet<DBTimedRecord<Sample>>, 2048, 4, DBStandardConfig> =
+        Database::create_capped(path, open, capacity).unwrap();
+    let mut series: Series<'_, DBStandardConfig> = DBTimeSeries::new(database, clock);
+    let error: DBError = series.add_entry(&create_sample(1)).unwrap_err();
+    assert!(matches!(error, DBError::Unsupported));
+    remove_database(path);
+}
+
+fn open_indexed<'a>(
+    path: &dyn CPathTrait,
+    index_path: &dyn CPathTrait,
+    clock: &ManualClock,
+) -> Series<'a, DBStandardConfig> {
+    let open: OpenFileBox = OpenFile::new();
+    let index_database: DBTimeIndexDatabase<'_, 2048, 4, DBStandardConfig> =
+        Database::open_or_create(index_path, open).unwrap();
+    open_series(path, clock).with_index(index_database)
+}
+
+fn count_keyframes(index_path: &dyn CPathTrait) -> usize {
+    let open: OpenFileBox = OpenFile::new();
+    let mut index_database: DBTimeIndexDatabase<'_, 2048, 4, DBStandardConfig> =
+        Database::open(index_path, open).unwrap();
+    index_database.count().unwrap()
+}
+
+pub fn series_index_test(path: &dyn CPathTrait, index_path: &dyn CPathTrait) {
+    println!("\n[TIME SERIES INDEX TEST]");
+    remove_database(path);
+    remove_database(index_path);
+
+    let time: Rc<Cell<u64>> = Rc::new(Cell::new(START));
+    let clock: ManualClock = ManualClock { time: time.clone() };
+    let mut series: Series<'_, DBStandardConfig> = open_indexed(path, index_path, &clock);
+    for idx in 0..WRITE_ENTRIES {
+        time.set(START + idx as u64 * STEP);
+        series.add_entry(&create_sample(idx)).unwrap();
+    }
+    series.close().unwrap();
+    assert_eq!(count_keyframes(index_path), 4);
+
+    // The keyframes are read back instead of the entries before the last one
+    let mut series: Series<'_, DBStandardConfig> = open_indexed(path, index_path, &clock);
+    assert!(series.index.is_empty());
+    let latest: DBTimedEntry<Sample> = series.latest().unwrap().unwrap();
+    assert_eq!(latest.uid, WRITE_ENTRIES - 1);
+    assert_eq!(series.index.len(), 4);
+    let entries: Vec<Result<DBTimedEntry<Sample>, DBError>> =
+        series.range_time(1_500..1_600).unwrap().collect();
+    let expected: Vec<u64> = (50..60).map(|idx| START + idx * STEP).collect();
+    assert_eq!(times(entries), expected);
+    series.close().unwrap();
+    assert_eq!(count_keyframes(index_path), 4);
+
+    // An index of another file is rebuilt
+    remove_database(path);
+    let mut series: Series<'_, DBStandardConfig> = open_indexed(path, index_path, &clock);
+    for idx in 0..10 {
+        time.set(START + idx as u64 * STEP);
+        series.add_entry(&create_sample(idx)).unwrap();
+    }
+    assert_eq!(series.latest().unwrap().unwrap().uid, 9);
+    series.close().unwrap();
+    assert_eq!(count_keyframes(index_path), 1);
+
+    // A torn tail ends the scan
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    bytes.extend([10, 0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+    bytes.extend(EOE_BLOCK);
+    std::fs::write(path.as_str(), &bytes).unwrap();
+    let mut series: Series<'_, DBStandardConfig> = open_series(path, &clock);
+    assert_eq!(series.latest().unwrap().unwrap().uid, 9);
+    let entries: Vec<Result<DBTimedEntry<Sample>, DBError>> =
+        series.range_time(START..START + 30).unwrap().collect();
+    assert_eq!(times(entries), vec![START, START + 10, START + 20]);
+    series.close().unwrap();
+
+    // Once an entry follows it, the entry that does not read is corrupt
+    let open: OpenFileBox = OpenFile::new();
+    let mut database: DBTimedDatabase<'_, Sample, 2048, 4, DBStandardConfig> =
+        Database::open(path, open).unwrap();
+    let time: DBTime = DBTime::Delta(STEP);
+    let item: Sample = create_sample(10);
+    database.add_entry(&DBTimedRecord { time, item }).unwrap();
+    database.close().unwrap();
+    let mut series: Series<'_, DBStandardConfig> = open_series(path, &clock);
+    let error: DBError = series.latest().unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 10, .. }));
+    let error: DBError = series.add_entry(&create_sample(11)).unwrap_err();
+    assert!(matches!(error, DBError::DeserializeError { uid: 10, .. }));
+    series.close().unwrap();
+    remove_database(path);
+    remove_database(index_path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
@@ -0,0 +1,140 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use micro_db::capped::DBCapacity;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBChunkIterator;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const WRITE_ENTRIES: usize = 12;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct BlobRecord {
+    valid: bool,
+    blob: Vec<u8>,
+}
+
+pub fn create_blob_record(idx: usize) -> BlobRecord {
+    let valid: bool = true;
+    let blob: Vec<u8> = (0..idx * 9001 + idx % 4).map(|byte| byte as u8).collect();
+    BlobRecord { valid, blob }
+}
+
+type BlobDatabase<'a> = Database<'a, BTreeSet<BlobRecord>>;
+
+fn collect_entries(db: &mut BlobDatabase<'_>) -> Vec<Result<DBEntry<BlobRecord>, DBError>> {
+    let db_iterator: DBIterator<'_, BTreeSet<BlobRecord>> = db.get_iterator().unwrap();
+    db_iterator.collect()
+}
+
+pub fn large_entry_test(path: &dyn CPathTrait) {
+    println!("\n[LARGE ENTRY STREAM TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: BlobDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_blob_record(idx)).unwrap();
+    }
+
+    let entries: Vec<Result<DBEntry<BlobRecord>, DBError>> = collect_entries(&mut db);
+    assert_eq!(entries.len(), WRITE_ENTRIES);
+    for (idx, entry) in entries.into_iter().enumerate() {
+        let entry: DBEntry<BlobRecord> = entry.unwrap();
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_blob_record(idx));
+    }
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(256 * 1024);
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: BlobDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_blob_record(idx)).unwrap();
+    }
+
+    let entries: Vec<Result<DBEntry<BlobRecord>, DBError>> = collect_entries(&mut db);
+    assert!(!entries.is_empty());
+    let last: &DBEntry<BlobRecord> = entries.last().unwrap().as_ref().unwrap();
+    assert_eq!(last.uid, WRITE_ENTRIES as u32 - 1);
+    assert_eq!(last.item, create_blob_record(WRITE_ENTRIES - 1));
+    remove_database(path);
+}
+
+pub fn skip_invalid_test(path: &dyn CPathTrait) {
+    println!("\n[SKIP INVALID STREAM TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: BlobDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_blob_record(idx % 3)).unwrap();
+    }
+
+    let chunk_iterator: DBChunkIterator<'_> = db.get_chunk_iterator().unwrap();
+    let lengths: Vec<usize> = chunk_iterator.map(|chunk| chunk.unwrap().len()).collect();
+    db.close().unwrap();
+
+    let mut bytes: Vec<u8> = std::fs::read(path.as_str()).unwrap();
+    let offset: usize = lengths[..4].iter().sum();
+    // Store an invalid bool in entry 4, then leave a torn entry at the end
+    bytes[offset + 4] = 7;
+    bytes.extend([12, 0, 0, 0, 1, 3]);
+    std::fs::write(path.as_str(), &bytes).unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: BlobDatabase<'_> = Database::open(path, open).unwrap();
+    let entries: Vec<Result<DBEntry<BlobRecord>, DBError>> = collect_entries(&mut db);
+    assert_eq!(entries.len(), WRITE_ENTRIES);
+    for (idx, entry) in entries.into_iter().enumerate() {
+        if idx == 4 {
+            assert!(matches!(
+                entry,
+                Err(DBError::DeserializeError { uid: 4, .. })
+            ));
+            continue;
+        }
+        assert_eq!(entry.unwrap().item, create_blob_record(idx % 3));
+    }
+    remove_database(path);
+}
+
+pub fn remove_test(path: &dyn CPathTrait) {
+    println!("\n[REMOVE STREAM TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: BlobDatabase<'_> = Database::create(path, open).unwrap();
+    for idx in 0..WRITE_ENTRIES {
+        db.add_entry(&create_blob_record(idx % 4)).unwrap();
+    }
+
+    // Removing the last entry leaves no chunk to move up
+    db.remove_by_uid(WRITE_ENTRIES as u32 - 1).unwrap();
+    db.remove_by_uid(2).unwrap();
+
+    let entries: Vec<Result<DBEntry<BlobRecord>, DBError>> = collect_entries(&mut db);
+    assert_eq!(entries.len(), WRITE_ENTRIES - 2);
+    for (idx, entry) in entries.into_iter().enumerate() {
+        let entry: DBEntry<BlobRecord> = entry.unwrap();
+        let original: usize = if idx < 2 { idx } else { idx + 1 };
+        assert_eq!(entry.uid, idx as u32);
+        assert_eq!(entry.item, create_blob_record(original % 4));
+    }
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}
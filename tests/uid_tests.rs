@@ -0,0 +1,171 @@
+extern crate alloc;
+extern crate micro_db;
+
+use alloc::collections::BTreeSet;
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use micro_db::capped::DBCapacity;
+use micro_db::config::DBWideUID;
+use micro_db::db::Database;
+use micro_db::error::DBError;
+use micro_db::impls::OpenFile;
+use micro_db::structures::DBEntry;
+use micro_db::structures::DBIterator;
+use micro_db::traits::CPathTrait;
+use micro_db::traits::OpenFileBox;
+use micro_db::traits::OpenFileTrait;
+
+use bincode::Decode;
+use bincode::Encode;
+
+const MAX_BYTES: usize = 1024;
+// Format header of an 8-byte block file, the ring header follows it
+const WIDE_HEADER_SIZE: u64 = 24;
+// Low and high halves of `next_uid` in the ring header
+const RING_NEXT_UID: u64 = 28;
+
+#[derive(Encode, Decode, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct CounterRecord {
+    counter: u16,
+    total: u64,
+}
+
+pub fn create_counter_record(idx: u16) -> CounterRecord {
+    let counter: u16 = idx;
+    let total: u64 = idx as u64 * 1000;
+    CounterRecord { counter, total }
+}
+
+type WideDatabase<'a> = Database<'a, BTreeSet<CounterRecord>, 2048, 8, DBWideUID>;
+
+fn patch_file(path: &dyn CPathTrait, offset: u64, bytes: &[u8]) {
+    let mut file: File = OpenOptions::new().write(true).open(path.as_str()).unwrap();
+    file.seek(SeekFrom::Start(offset)).unwrap();
+    file.write_all(bytes).unwrap();
+}
+
+fn file_length(path: &dyn CPathTrait) -> u64 {
+    std::fs::metadata(path.as_str()).unwrap().len()
+}
+
+pub fn overflow_test(path: &dyn CPathTrait) {
+    println!("\n[UID OVERFLOW TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<CounterRecord>> = Database::create(path, open).unwrap();
+    db.add_entry(&create_counter_record(0)).unwrap();
+    db.close().unwrap();
+    patch_file(path, 0, &(u32::MAX - 2).to_le_bytes());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<CounterRecord>> = Database::open(path, open).unwrap();
+    let length: u64 = file_length(path);
+
+    // A batch that would run past the last UID is rejected as a whole
+    let items: BTreeSet<CounterRecord> = (1..4).map(create_counter_record).collect();
+    let error: DBError = db.add_entries(items).unwrap_err();
+    assert!(matches!(error, DBError::UIDOverflow));
+    assert_eq!(file_length(path), length);
+
+    let items: BTreeSet<CounterRecord> = (1..3).map(create_counter_record).collect();
+    db.add_entries(items).unwrap();
+    let entry: DBEntry<CounterRecord> = db.get_by_uid(u32::MAX).unwrap();
+    assert_eq!(entry.item, create_counter_record(2));
+
+    let length: u64 = file_length(path);
+    let error: DBError = db.add_entry(&create_counter_record(3)).unwrap_err();
+    assert!(matches!(error, DBError::UIDOverflow));
+    assert_eq!(file_length(path), length);
+    remove_database(path);
+}
+
+pub fn wide_uid_test(path: &dyn CPathTrait) {
+    println!("\n[WIDE UID TEST]");
+    remove_database(path);
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: WideDatabase<'_> = Database::create(path, open).unwrap();
+    db.add_entry(&create_counter_record(0)).unwrap();
+    db.close().unwrap();
+    patch_file(path, WIDE_HEADER_SIZE, &(u32::MAX as u64).to_le_bytes());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: WideDatabase<'_> = Database::open(path, open).unwrap();
+    db.add_entry(&create_counter_record(1)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: WideDatabase<'_> = Database::open(path, open).unwrap();
+    let entry: DBEntry<CounterRecord, u64> = db.get_by_uid(1 << 32).unwrap();
+    assert_eq!(entry.item, create_counter_record(1));
+    let db_iterator: DBIterator<'_, BTreeSet<CounterRecord>, 2048, 8, DBWideUID> =
+        db.get_iterator().unwrap();
+    let uids: Vec<u64> = db_iterator.map(|entry| entry.unwrap().uid).collect();
+    assert_eq!(uids, vec![u32::MAX as u64, 1 << 32]);
+    db.close().unwrap();
+
+    // The same block size with 32-bit UIDs is a different format
+    let open: OpenFileBox = OpenFile::new();
+    let error: DBError = Database::<'_, BTreeSet<CounterRecord>, 2048, 8>::open(path, open)
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        DBError::EncodingMismatch { expected, found }
+            if expected.uid_size == 4 && found.uid_size == 8
+    ));
+    remove_database(path);
+}
+
+pub fn capped_uid_test(path: &dyn CPathTrait) {
+    println!("\n[CAPPED UID TEST]");
+    remove_database(path);
+
+    let capacity: DBCapacity = DBCapacity::new(MAX_BYTES);
+    let open: OpenFileBox = OpenFile::new();
+    let db: Database<'_, BTreeSet<CounterRecord>> =
+        Database::create_capped(path, open, capacity).unwrap();
+    db.close().unwrap();
+    patch_file(path, RING_NEXT_UID, &(u32::MAX - 1).to_le_bytes());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: Database<'_, BTreeSet<CounterRecord>> = Database::open(path, open).unwrap();
+    db.add_entry(&create_counter_record(0)).unwrap();
+    let error: DBError = db.add_entry(&create_counter_record(1)).unwrap_err();
+    assert!(matches!(error, DBError::UIDOverflow));
+    db.close().unwrap();
+    remove_database(path);
+
+    // Wide UIDs keep counting past the 32-bit range
+    let open: OpenFileBox = OpenFile::new();
+    let db: WideDatabase<'_> = Database::create_capped(path, open, capacity).unwrap();
+    db.close().unwrap();
+    let offset: u64 = WIDE_HEADER_SIZE + RING_NEXT_UID;
+    patch_file(path, offset, &(u32::MAX as u64).to_le_bytes());
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: WideDatabase<'_> = Database::open(path, open).unwrap();
+    db.add_entry(&create_counter_record(0)).unwrap();
+    db.add_entry(&create_counter_record(1)).unwrap();
+    db.close().unwrap();
+
+    let open: OpenFileBox = OpenFile::new();
+    let mut db: WideDatabase<'_> = Database::open(path, open).unwrap();
+    db.add_entry(&create_counter_record(2)).unwrap();
+    let db_iterator: DBIterator<'_, BTreeSet<CounterRecord>, 2048, 8, DBWideUID> =
+        db.get_iterator().unwrap();
+    let uids: Vec<u64> = db_iterator.map(|entry| entry.unwrap().uid).collect();
+    let first: u64 = u32::MAX as u64;
+    assert_eq!(uids, vec![first, first + 1, first + 2]);
+    remove_database(path);
+}
+
+pub fn remove_database(path: &dyn CPathTrait) {
+    let _ = std::fs::remove_file(path.as_str());
+}